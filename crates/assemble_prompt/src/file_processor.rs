@@ -2,16 +2,40 @@
 
 use anyhow::Result;
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
 
-use substring_marker_snippet_extractor::{
-    filter_substring_markers, EnclosingBlockScope, FileAnalysis,
-};
+use substring_marker_snippet_extractor::{filter_line_ranges, EnclosingBlockScope, FileAnalysis};
 
 /// Trait that abstracts file processing.
 pub trait FileProcessor {
     /// Processes the file at the given path, optionally using the expected file basename.
     fn process_file(&self, file_path: &Path, todo_file_basename: Option<&str>) -> Result<String>;
+
+    /// Like `process_file`, for a TODO marked with `marker_token` (e.g.
+    /// `AI: -`) rather than the default token.  Processors that do not look
+    /// for the TODO can rely on the default, which ignores the token.
+    fn process_file_with_marker_token(
+        &self,
+        file_path: &Path,
+        todo_file_basename: Option<&str>,
+        _marker_token: Option<&str>,
+    ) -> Result<String> {
+        self.process_file(file_path, todo_file_basename)
+    }
+
+    /// Only the `declarations` (0-based, end-exclusive line ranges) of a
+    /// definition file and its imports, with placeholders for the lines in
+    /// between, or `None` to show the file whole.  The default always shows
+    /// it whole.
+    fn process_declarations(
+        &self,
+        _file_path: &Path,
+        _declarations: &[Range<usize>],
+        _marker_token: Option<&str>,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
 }
 
 /// Default implementation of the `FileProcessor` trait.
@@ -19,11 +43,27 @@ pub struct DefaultFileProcessor;
 
 impl FileProcessor for DefaultFileProcessor {
     fn process_file(&self, file_path: &Path, todo_file_basename: Option<&str>) -> Result<String> {
+        self.process_file_with_marker_token(file_path, todo_file_basename, None)
+    }
+
+    /// Substring markers, the placeholder and the context header are written
+    /// in the file's own comment syntax.
+    fn process_file_with_marker_token(
+        &self,
+        file_path: &Path,
+        todo_file_basename: Option<&str>,
+        marker_token: Option<&str>,
+    ) -> Result<String> {
         let file_content = fs::read_to_string(file_path)?;
-        let analysis = FileAnalysis::for_path(&file_content, file_path);
+        let grammar = lang_support::marker_grammar(file_path, marker_token);
+        let analysis = FileAnalysis::for_path_with_grammar(&file_content, file_path, grammar);
+        let grammar = analysis.grammar();
 
-        let processed_content = if file_content.lines().any(|line| line.trim() == "// v") {
-            filter_substring_markers(&file_content, "// ...")
+        let processed_content = if file_content
+            .lines()
+            .any(|line| grammar.is_substring_open(line))
+        {
+            analysis.filtered_content(&grammar.placeholder())
         } else {
             file_content.clone()
         };
@@ -36,7 +76,10 @@ impl FileProcessor for DefaultFileProcessor {
                 if file_basename == expected_basename {
                     if let Some(context) = analysis.enclosing_block(EnclosingBlockScope::Functions)
                     {
-                        combined_content.push_str("\n\n// Enclosing function context:\n");
+                        combined_content.push_str(&format!(
+                            "\n\n{}\n",
+                            grammar.comment("Enclosing function context:")
+                        ));
                         combined_content.push_str(&context);
                     }
                 }
@@ -45,6 +88,91 @@ impl FileProcessor for DefaultFileProcessor {
 
         Ok(combined_content)
     }
+
+    /// Laid out as if each kept region were wrapped in substring markers.
+    /// Files in a language without support, or that pick their own excerpt
+    /// with substring markers, are shown whole.
+    fn process_declarations(
+        &self,
+        file_path: &Path,
+        declarations: &[Range<usize>],
+        marker_token: Option<&str>,
+    ) -> Result<Option<String>> {
+        let Some(language) = file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(lang_support::for_extension)
+        else {
+            return Ok(None);
+        };
+        let file_content = fs::read_to_string(file_path)?;
+        let grammar = lang_support::marker_grammar(file_path, marker_token);
+        if file_content
+            .lines()
+            .any(|line| grammar.is_substring_open(line))
+        {
+            return Ok(None);
+        }
+        let mut kept = lang_support::import_lines(language, &file_content);
+        kept.extend(declarations.iter().cloned());
+        Ok(Some(filter_line_ranges(
+            &file_content,
+            &kept,
+            &grammar.placeholder(),
+        )))
+    }
+}
+
+/// The function enclosing the TODO marker of the file at `file_path`,
+/// between placeholders for the code around it, or `None` when the marker
+/// is not inside a function.
+pub(crate) fn enclosing_function_excerpt(
+    file_path: &Path,
+    marker_token: Option<&str>,
+) -> Result<Option<String>> {
+    let file_content = fs::read_to_string(file_path)?;
+    let grammar = lang_support::marker_grammar(file_path, marker_token);
+    let analysis = FileAnalysis::for_path_with_grammar(&file_content, file_path, grammar);
+    let placeholder = analysis.grammar().placeholder();
+    Ok(analysis
+        .block_around_todo(EnclosingBlockScope::Functions)
+        .map(|block| format!("{}\n\n{}\n\n{}\n", placeholder, block, placeholder)))
+}
+
+/// The file at `file_path` focused on its TODO marker, or `None` when it
+/// cannot be focused and is shown as usual.
+pub(crate) fn focus_excerpt(
+    file_path: &Path,
+    marker_token: Option<&str>,
+) -> Result<Option<String>> {
+    let file_content = fs::read_to_string(file_path)?;
+    let grammar = lang_support::marker_grammar(file_path, marker_token);
+    let analysis = FileAnalysis::for_path_with_grammar(&file_content, file_path, grammar);
+    Ok(analysis.focused_content(&analysis.grammar().placeholder()))
+}
+
+/// The skeleton of the file at `file_path`, or `None` when its language
+/// has no support or its substring markers already pick what to show.
+pub(crate) fn skeleton_excerpt(
+    file_path: &Path,
+    marker_token: Option<&str>,
+) -> Result<Option<String>> {
+    let Some(language) = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(lang_support::for_extension)
+    else {
+        return Ok(None);
+    };
+    let file_content = fs::read_to_string(file_path)?;
+    let grammar = lang_support::marker_grammar(file_path, marker_token);
+    if file_content
+        .lines()
+        .any(|line| grammar.is_substring_open(line))
+    {
+        return Ok(None);
+    }
+    Ok(Some(lang_support::skeleton(language, &file_content)))
 }
 
 /// Public API function to process a file using a provided `FileProcessor` implementation.
@@ -137,4 +265,29 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_default_processor_uses_the_files_comment_syntax() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("views.py");
+        let content = concat!(
+            "# v\n",
+            "import json\n",
+            "# ^\n",
+            "def load(path):\n",
+            "    # AI: - cache the result\n",
+            "    return json.load(path)\n",
+        );
+        std::fs::write(&path, content).unwrap();
+
+        let result = DefaultFileProcessor
+            .process_file_with_marker_token(&path, Some("views.py"), Some("AI: -"))
+            .unwrap();
+
+        assert!(result.contains("# ...\n"));
+        assert!(!result.contains("// ..."));
+        assert!(result.contains(
+            "# Enclosing function context:\ndef load(path):\n    # AI: - cache the result"
+        ));
+    }
 }
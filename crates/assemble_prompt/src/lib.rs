@@ -4,19 +4,150 @@ mod file_processor;
 
 pub use file_processor::{process_file_with_processor, DefaultFileProcessor, FileProcessor};
 
+use file_processor::{enclosing_function_excerpt, focus_excerpt, skeleton_excerpt};
+
 use anyhow::Result;
 use diff_with_branch::run_diff_against;
 use log::warn;
+use std::collections::HashMap;
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use todo_marker::{MarkerGrammar, MarkerVerb};
 use unescape_newlines::unescape_newlines;
 
+/// The closing instruction for the default `// TODO: -` marker.
+#[cfg(test)]
 const FIXED_INSTRUCTION: &str = "Can you do the TODO:- in the above code? But ignoring all FIXMEs and other TODOs...i.e. only do the one and only one TODO that is marked by \"// TODO: - \", i.e. ignore things like \"// TODO: example\" because it doesn't have the hyphen";
 
+/// The closing instruction for `verb`, quoting its marker as the TODO file
+/// writes it.  Each instruction mentions the marker exactly once, which is
+/// what `validate_marker_count` relies on.
+fn closing_instruction(grammar: &MarkerGrammar, verb: MarkerVerb) -> String {
+    let marker = grammar.marker_for(verb);
+    match verb {
+        MarkerVerb::Todo => format!(
+            "Can you do the TODO:- in the above code? But ignoring all FIXMEs and other TODOs...i.e. only do the one and only one TODO that is marked by \"{} \", i.e. ignore things like \"{}\" because it doesn't have the hyphen",
+            marker,
+            grammar.comment("TODO: example"),
+        ),
+        MarkerVerb::Fixme => format!(
+            "Can you fix the bug described by the FIXME:- in the above code? Only fix the one and only one FIXME that is marked by \"{} \", ignoring all other FIXMEs and TODOs, and explain the cause of the bug",
+            marker,
+        ),
+        MarkerVerb::Test => format!(
+            "Can you write tests for the function enclosing the TEST:- in the above code? The function is marked by \"{} \"; cover its edge cases and follow the style of any existing tests. Don't change the function itself",
+            marker,
+        ),
+        MarkerVerb::Explain => format!(
+            "Can you answer the question in the EXPLAIN:- in the above code? It is marked by \"{} \". Please don't change any code, just explain, referring to the code above where it helps",
+            marker,
+        ),
+        MarkerVerb::Review => format!(
+            "Can you review the block enclosing the REVIEW:- in the above code? It is marked by \"{} \". Point out bugs, edge cases and readability problems with concrete suggestions rather than rewriting the whole block",
+            marker,
+        ),
+    }
+}
+
+/// The closing instruction for a task group: the group's marker is quoted
+/// once and each task is listed by its text and location, without marker
+/// text, so the prompt holds one marker per task plus this one.  Only the
+/// template has its escapes interpreted; the tasks are quoted from the
+/// source as they are.
+fn group_closing_instruction(grammar: &MarkerGrammar, group: &TaskGroup) -> String {
+    let mut instruction = unescape_newlines(&format!(
+        "Can you do the {} tasks tagged #{} in the above code? They are marked by \"{} #{}\" (or another marker verb carrying the same tag); ignore all FIXMEs and TODOs without that tag. The tasks are:",
+        group.tasks.len(),
+        group.tag,
+        grammar.marker(),
+        group.tag,
+    ));
+    for (i, task) in group.tasks.iter().enumerate() {
+        instruction.push_str(&format!(
+            "\n{}. {}: {} ({})",
+            i + 1,
+            task.verb,
+            task.task,
+            task.location
+        ));
+    }
+    instruction
+}
+
+/// One task of a multi-task prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSummary {
+    pub verb: MarkerVerb,
+    /// Basename of the file holding the marker.
+    pub file: String,
+    /// What the marker asks for, without the marker itself.
+    pub task: String,
+    /// Where the marker sits, e.g. "in `login()` at Login.swift:12".
+    pub location: String,
+}
+
+/// Markers sharing a `#tag`, handled together in one prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskGroup {
+    pub tag: String,
+    pub tasks: Vec<TaskSummary>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct AssemblyOptions {
     pub todo_file_basename: Option<String>,
     pub diff_branch: Option<String>,
+    /// Where the TODO sits, e.g. "the TODO is in `Outer.reload()` at Foo.swift:12";
+    /// appended to the closing instruction when set.
+    pub todo_location: Option<String>,
+    /// How the TODO file writes its marker; the closing instruction quotes
+    /// it and its token identifies the TODO in every file.
+    pub marker_grammar: MarkerGrammar,
+    /// What the marker asks for; picks the closing instruction.
+    pub verb: MarkerVerb,
+    /// When set, the prompt covers every task of the group: each task file
+    /// is treated as a TODO file and the closing instruction lists the tasks
+    /// instead of naming `verb` and `todo_location`.
+    pub task_group: Option<TaskGroup>,
+    /// How particular files are shown, overriding `skeleton_over_lines`.
+    pub renderings: HashMap<PathBuf, Rendering>,
+    /// Files other than TODO files that are longer than this many lines are
+    /// shown as skeletons; `Some(0)` shows every one of them that way.
+    pub skeleton_over_lines: Option<usize>,
+    /// Lines (0-based, end-exclusive) of the declarations to show of
+    /// particular files, which are then shown as
+    /// [`Rendering::Declarations`] unless `skeleton_over_lines` applies.
+    pub declarations: HashMap<PathBuf, Vec<Range<usize>>>,
+    /// Show TODO files as [`Rendering::Focus`] unless `renderings` says
+    /// otherwise.
+    pub focus: bool,
+    /// Marker lines of the TODO files as written, each with the line shown
+    /// in its place, e.g. without its directive bracket.  Only the TODO
+    /// files' own contents are rewritten; other files and diffs are not.
+    pub marker_rewrites: Vec<(String, String)>,
+}
+
+/// How much of a file its section shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rendering {
+    #[default]
+    Full,
+    /// Only the function enclosing the marker, between placeholders; files
+    /// without one are shown in full.
+    EnclosingFunction,
+    /// Declarations, signatures and doc comments, with function bodies
+    /// elided as `{ … }`.  Files in a language without support, or that
+    /// pick their own excerpt with substring markers, are shown in full.
+    Skeleton,
+    /// Only the file's `declarations` and imports, between placeholders;
+    /// files without declarations, in a language without support, or that
+    /// pick their own excerpt with substring markers are shown in full.
+    Declarations,
+    /// The function around the TODO marker, the outline of the blocks
+    /// around it, the members of its type it uses and the imports, between
+    /// placeholders; files that cannot be focused are shown in full.
+    Focus,
 }
 
 /// Public API: assembles the final prompt from the found files and explicit options.
@@ -68,7 +199,79 @@ where
     P: FileProcessor,
     D: DiffProvider,
 {
-    let mut final_prompt = String::new();
+    let sections = assemble_sections_with_processor_and_diff_provider(
+        found_files,
+        processor,
+        options,
+        diff_provider,
+    )?;
+    Ok(join_sections(&sections, options))
+}
+
+/// The part of the prompt one file contributes, before the closing
+/// instruction is added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptSection {
+    pub path: PathBuf,
+    /// How the file ended up being shown.
+    pub rendering: Rendering,
+    /// The file's contents, its diff when there is one, and the separator
+    /// that follows them.
+    pub text: String,
+}
+
+/// The section of each of `found_files`, in order, leaving out files that
+/// do not exist.  [`join_sections`] turns the sections, or any subset of
+/// them, into the prompt [`assemble_prompt`] would build.
+pub fn assemble_sections(
+    found_files: &[PathBuf],
+    options: &AssemblyOptions,
+) -> Result<Vec<PromptSection>> {
+    assemble_sections_with_processor_and_diff_provider(
+        found_files,
+        &DefaultFileProcessor,
+        options,
+        &GitDiffProvider,
+    )
+}
+
+/// Concatenates `sections` and appends the closing instruction.
+pub fn join_sections(sections: &[PromptSection], options: &AssemblyOptions) -> String {
+    let mut final_prompt: String = sections.iter().map(|s| s.text.as_str()).collect();
+    final_prompt.push_str(&closing_text(options));
+    final_prompt
+}
+
+/// The text [`join_sections`] appends after the last section.
+pub fn closing_text(options: &AssemblyOptions) -> String {
+    // Only the tool's own text has its escapes interpreted; file contents,
+    // diffs and the task text and locations quoted from them go out
+    // byte-for-byte, `\n` in string literals included.
+    let closing = match &options.task_group {
+        Some(group) => group_closing_instruction(&options.marker_grammar, group),
+        None => {
+            let mut closing =
+                unescape_newlines(&closing_instruction(&options.marker_grammar, options.verb));
+            if let Some(location) = options.todo_location.as_deref() {
+                closing.push_str(&format!(". For reference, {}.", location));
+            }
+            closing
+        }
+    };
+    format!("\n\n{}", closing)
+}
+
+fn assemble_sections_with_processor_and_diff_provider<P, D>(
+    found_files: &[PathBuf],
+    processor: &P,
+    options: &AssemblyOptions,
+    diff_provider: &D,
+) -> Result<Vec<PromptSection>>
+where
+    P: FileProcessor,
+    D: DiffProvider,
+{
+    let mut sections = Vec::new();
     let todo_file_basename = options.todo_file_basename.as_deref().unwrap_or("");
 
     for file_path in found_files {
@@ -82,23 +285,71 @@ where
             .and_then(|s| s.to_str())
             .unwrap_or(&display_path)
             .to_string();
+        let is_task_file = options
+            .task_group
+            .as_ref()
+            .is_some_and(|group| group.tasks.iter().any(|task| task.file == basename));
+        let todo_file_basename = if is_task_file {
+            basename.as_str()
+        } else {
+            todo_file_basename
+        };
 
-        let processed_content =
-            match process_file_with_processor(processor, file_path, Some(todo_file_basename)) {
-                Ok(content) => content,
-                Err(err) => {
-                    warn!(
-                        "Error processing {}: {}. Falling back to raw file contents.",
-                        display_path, err
-                    );
-                    fs::read_to_string(file_path).unwrap_or_default()
+        let rendering = options
+            .renderings
+            .get(file_path)
+            .copied()
+            .unwrap_or_else(|| match options.skeleton_over_lines {
+                _ if options.focus && basename == todo_file_basename => Rendering::Focus,
+                Some(max) if basename != todo_file_basename && line_count(file_path) > max => {
+                    Rendering::Skeleton
                 }
-            };
+                _ if options.declarations.contains_key(file_path) => Rendering::Declarations,
+                _ => Rendering::Full,
+            });
+        let marker_token = Some(options.marker_grammar.token());
+        let excerpt = match rendering {
+            Rendering::Full => Ok(None),
+            Rendering::EnclosingFunction => enclosing_function_excerpt(file_path, marker_token),
+            Rendering::Skeleton => skeleton_excerpt(file_path, marker_token),
+            Rendering::Focus => focus_excerpt(file_path, marker_token),
+            Rendering::Declarations => match options.declarations.get(file_path) {
+                Some(declarations) => {
+                    processor.process_declarations(file_path, declarations, marker_token)
+                }
+                None => Ok(None),
+            },
+        };
+        let (rendering, rendered) = match excerpt {
+            Ok(Some(excerpt)) => (rendering, Ok(excerpt)),
+            Ok(None) => (
+                Rendering::Full,
+                processor.process_file_with_marker_token(
+                    file_path,
+                    Some(todo_file_basename),
+                    marker_token,
+                ),
+            ),
+            Err(err) => (Rendering::Full, Err(err)),
+        };
+        let mut processed_content = match rendered {
+            Ok(content) => content,
+            Err(err) => {
+                warn!(
+                    "Error processing {}: {}. Falling back to raw file contents.",
+                    display_path, err
+                );
+                fs::read_to_string(file_path).unwrap_or_default()
+            }
+        };
+        if basename == todo_file_basename && !options.marker_rewrites.is_empty() {
+            processed_content = rewrite_marker_lines(&processed_content, &options.marker_rewrites);
+        }
 
-        final_prompt.push_str(&format!(
+        let mut text = format!(
             "\nThe contents of {} is as follows:\n\n{}\n\n",
             basename, processed_content
-        ));
+        );
 
         if let Some(diff_branch) = options.diff_branch.as_deref() {
             let diff_output = match diff_provider.diff_for_file(file_path, diff_branch) {
@@ -110,20 +361,41 @@ where
                 }
             };
             if !diff_output.trim().is_empty() {
-                final_prompt.push_str(&format!(
+                text.push_str(&format!(
                     "\n--------------------------------------------------\nThe diff for {} (against branch {}) is as follows:\n\n{}\n\n",
                     basename, diff_branch, diff_output
                 ));
             }
         }
 
-        final_prompt.push_str("\n--------------------------------------------------\n");
+        text.push_str("\n--------------------------------------------------\n");
+        sections.push(PromptSection {
+            path: file_path.clone(),
+            rendering,
+            text,
+        });
     }
 
-    final_prompt.push_str(&format!("\n\n{}", FIXED_INSTRUCTION));
+    Ok(sections)
+}
+
+/// `content` with each line that is one of `rewrites`' marker lines, give or
+/// take its indentation, replaced by the line paired with it.
+fn rewrite_marker_lines(content: &str, rewrites: &[(String, String)]) -> String {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let marker = line.trim();
+            match rewrites.iter().find(|(raw, _)| raw.trim() == marker) {
+                Some((_, shown)) if !marker.is_empty() => line.replacen(marker, shown.trim(), 1),
+                _ => line.to_string(),
+            }
+        })
+        .collect()
+}
 
-    let final_prompt = unescape_newlines(&final_prompt);
-    Ok(final_prompt)
+fn line_count(file_path: &Path) -> usize {
+    fs::read_to_string(file_path).map_or(0, |content| content.lines().count())
 }
 
 #[cfg(test)]
@@ -151,6 +423,114 @@ mod tests {
         assert!(output.trim().ends_with(FIXED_INSTRUCTION));
     }
 
+    #[test]
+    fn test_closing_instruction_quotes_the_todo_files_marker() {
+        assert_eq!(
+            closing_instruction(&MarkerGrammar::default(), MarkerVerb::Todo),
+            FIXED_INSTRUCTION
+        );
+
+        let options = AssemblyOptions {
+            marker_grammar: MarkerGrammar::HASH,
+            ..Default::default()
+        };
+        let output = assemble_prompt(&[], &options).expect("assemble_prompt failed");
+        assert!(output.contains("marked by \"# TODO: - \""));
+        assert!(output.contains("ignore things like \"# TODO: example\""));
+        assert!(!output.contains("// TODO"));
+    }
+
+    #[test]
+    fn test_each_verb_has_its_own_closing_instruction() {
+        let grammar = MarkerGrammar::default();
+        for verb in MarkerVerb::ALL {
+            let instruction = closing_instruction(&grammar, verb);
+            let marker = format!("\"{} \"", grammar.marker_for(verb));
+            assert_eq!(instruction.matches(&marker).count(), 1, "{verb}");
+            assert!(instruction.contains(&format!("{}:-", verb.keyword())));
+        }
+
+        let options = AssemblyOptions {
+            verb: MarkerVerb::Explain,
+            ..Default::default()
+        };
+        let output = assemble_prompt(&[], &options).expect("assemble_prompt failed");
+        assert!(output.contains("Please don't change any code"));
+        assert!(!output.contains(FIXED_INSTRUCTION));
+    }
+
+    #[test]
+    fn test_task_group_closing_instruction_lists_each_task() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let login = dir.path().join("Login.swift");
+        let session = dir.path().join("Session.swift");
+        fs::write(
+            &login,
+            "// v\nlet retries = 3\n// ^\nfunc login() {\n    // TODO: - #auth make login async\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            &session,
+            "// v\nlet ttl = 60\n// ^\nfunc refresh() {\n    // FIXME: - #auth expire old tokens\n}\n",
+        )
+        .unwrap();
+
+        let options = AssemblyOptions {
+            todo_file_basename: Some("Login.swift".to_string()),
+            todo_location: Some("the TODO is at Login.swift:5".to_string()),
+            task_group: Some(TaskGroup {
+                tag: "auth".to_string(),
+                tasks: vec![
+                    TaskSummary {
+                        verb: MarkerVerb::Todo,
+                        file: "Login.swift".to_string(),
+                        task: "make login async".to_string(),
+                        location: "in `login()` at Login.swift:5".to_string(),
+                    },
+                    TaskSummary {
+                        verb: MarkerVerb::Fixme,
+                        file: "Session.swift".to_string(),
+                        task: "expire old tokens".to_string(),
+                        location: "in `refresh()` at Session.swift:5".to_string(),
+                    },
+                ],
+            }),
+            ..Default::default()
+        };
+        let output = assemble_prompt(&[login, session], &options).expect("assemble_prompt failed");
+
+        assert!(output.contains("Can you do the 2 tasks tagged #auth"));
+        assert_eq!(output.matches("\"// TODO: - #auth\"").count(), 1);
+        assert!(output.ends_with(
+            "The tasks are:\n1. TODO: make login async (in `login()` at Login.swift:5)\n2. FIXME: expire old tokens (in `refresh()` at Session.swift:5)"
+        ));
+        assert!(!output.contains("For reference"));
+        // Both task files get their enclosing function alongside the markers.
+        assert!(output.contains("func login() {"));
+        assert!(output.contains("func refresh() {"));
+    }
+
+    #[test]
+    fn test_task_group_closing_instruction_quotes_tasks_verbatim() {
+        let options = AssemblyOptions {
+            task_group: Some(TaskGroup {
+                tag: "io".to_string(),
+                tasks: vec![TaskSummary {
+                    verb: MarkerVerb::Todo,
+                    file: "Reader.swift".to_string(),
+                    task: r#"split on "\n""#.to_string(),
+                    location: "in `lines()` at Reader.swift:3".to_string(),
+                }],
+            }),
+            ..Default::default()
+        };
+
+        assert!(closing_text(&options).ends_with(
+            r#"The tasks are:
+1. TODO: split on "\n" (in `lines()` at Reader.swift:3)"#
+        ));
+    }
+
     #[test]
     fn test_formatting_output_with_fixed_instruction() {
         let mut file1 = NamedTempFile::new().expect("Failed to create file1");
@@ -310,6 +690,7 @@ Parse.Cloud.define(\"getDashboardData\", async (request) => {
         let options = AssemblyOptions {
             todo_file_basename: Some(todo_basename),
             diff_branch: None,
+            ..Default::default()
         };
 
         let output = assemble_prompt(&found_files, &options).expect("assemble_prompt failed");
@@ -335,6 +716,21 @@ Parse.Cloud.define(\"getDashboardData\", async (request) => {
         assert!(output.contains(FIXED_INSTRUCTION));
     }
 
+    #[test]
+    fn test_todo_location_is_appended_to_fixed_instruction() {
+        let options = AssemblyOptions {
+            todo_location: Some("the TODO is in `Feed.reload()` at Feed.swift:3".to_string()),
+            ..Default::default()
+        };
+
+        let output = assemble_prompt(&[], &options).expect("assemble_prompt failed");
+
+        assert!(output.trim().ends_with(&format!(
+            "{}. For reference, the TODO is in `Feed.reload()` at Feed.swift:3.",
+            FIXED_INSTRUCTION
+        )));
+    }
+
     #[test]
     fn test_empty_found_files_list() {
         let found_files: Vec<PathBuf> = Vec::new();
@@ -567,6 +963,7 @@ func outsideFunction() {
         AssemblyOptions {
             todo_file_basename: None,
             diff_branch: Some(branch.to_string()),
+            ..Default::default()
         }
     }
 
@@ -601,6 +998,7 @@ func outsideFunction() {
         let options = AssemblyOptions {
             todo_file_basename: Some("Instruction.swift".to_string()),
             diff_branch: None,
+            ..Default::default()
         };
 
         let output = assemble_prompt_with_processor_and_options(
@@ -621,6 +1019,7 @@ func outsideFunction() {
         let options = AssemblyOptions {
             todo_file_basename: Some("Instruction.swift".to_string()),
             diff_branch: None,
+            ..Default::default()
         };
 
         env::remove_var("TODO_FILE_BASENAME");
@@ -643,6 +1042,7 @@ func outsideFunction() {
         let options = AssemblyOptions {
             todo_file_basename: Some("ExplicitInstruction.swift".to_string()),
             diff_branch: None,
+            ..Default::default()
         };
 
         env::set_var("TODO_FILE_BASENAME", "EnvInstruction.swift");
@@ -668,6 +1068,7 @@ func outsideFunction() {
         let options = AssemblyOptions {
             todo_file_basename: None,
             diff_branch: Some("explicit-branch".to_string()),
+            ..Default::default()
         };
 
         env::set_var("DIFF_WITH_BRANCH", "env-branch");
@@ -718,6 +1119,193 @@ func outsideFunction() {
             "Output should fallback to raw file content"
         );
     }
+
+    #[test]
+    fn test_sections_join_into_the_assembled_prompt() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let feed = dir.path().join("Feed.swift");
+        let cache = dir.path().join("Cache.swift");
+        fs::write(
+            &feed,
+            "import Foundation\n\nstruct Feed {\n    func load() {\n        // TODO: - paginate\n    }\n\n    func reset() {}\n}\n",
+        )
+        .unwrap();
+        fs::write(&cache, "struct Cache {}\n").unwrap();
+        let found_files = vec![cache.clone(), feed.clone(), dir.path().join("Gone.swift")];
+        let mut options = AssemblyOptions {
+            todo_file_basename: Some("Feed.swift".to_string()),
+            ..Default::default()
+        };
+
+        let sections = assemble_sections(&found_files, &options).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(
+            join_sections(&sections, &options),
+            assemble_prompt(&found_files, &options).unwrap()
+        );
+        let without_cache = join_sections(&sections[1..], &options);
+        assert!(!without_cache.contains("struct Cache"));
+        assert!(without_cache.ends_with(&closing_text(&options)));
+
+        options
+            .renderings
+            .insert(feed.clone(), Rendering::EnclosingFunction);
+        let excerpt = assemble_sections(&[feed], &options).unwrap();
+        assert!(excerpt[0]
+            .text
+            .contains("// ...\n\n    func load() {\n        // TODO: - paginate\n    }\n\n// ..."));
+        assert!(!excerpt[0].text.contains("reset"));
+        assert!(excerpt[0].text.len() < sections[1].text.len());
+    }
+
+    #[test]
+    fn test_files_over_the_line_threshold_are_skeletons() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let feed = dir.path().join("Feed.swift");
+        let cache = dir.path().join("Cache.swift");
+        let picked = dir.path().join("Picked.swift");
+        fs::write(
+            &feed,
+            "struct Feed {\n    func load() {\n        // TODO: - paginate\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            &cache,
+            "struct Cache {\n    /// Drops everything.\n    func clear() {\n        items = []\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            &picked,
+            "// v\nfunc keep() {\n    run()\n}\n// ^\nfunc other() {\n    skip()\n}\n",
+        )
+        .unwrap();
+        let options = AssemblyOptions {
+            todo_file_basename: Some("Feed.swift".to_string()),
+            skeleton_over_lines: Some(3),
+            ..Default::default()
+        };
+
+        let sections = assemble_sections(&[cache, feed, picked.clone()], &options).unwrap();
+        assert_eq!(sections[0].rendering, Rendering::Skeleton);
+        assert!(sections[0]
+            .text
+            .contains("    /// Drops everything.\n    func clear() { … }\n"));
+        assert!(!sections[0].text.contains("items = []"));
+        // The TODO file is never a skeleton, and substring markers win.
+        assert_eq!(sections[1].rendering, Rendering::Full);
+        assert!(sections[1].text.contains("// TODO: - paginate"));
+        assert_eq!(sections[2].rendering, Rendering::Full);
+        assert!(sections[2].text.contains("    run()"));
+
+        let explicit = AssemblyOptions {
+            renderings: [(picked.clone(), Rendering::Full)].into(),
+            skeleton_over_lines: Some(0),
+            ..options
+        };
+        assert_eq!(
+            assemble_sections(&[picked], &explicit).unwrap()[0].rendering,
+            Rendering::Full
+        );
+    }
+
+    #[test]
+    fn test_definition_files_show_only_their_declarations() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let models = dir.path().join("Models.swift");
+        fs::write(
+            &models,
+            concat!(
+                "import Foundation\n",
+                "\n",
+                "struct Other {\n",
+                "    let skipped: Int\n",
+                "}\n",
+                "\n",
+                "struct User {\n",
+                "    let id: Int\n",
+                "}\n",
+            ),
+        )
+        .unwrap();
+        let user = 6..9;
+        let options = AssemblyOptions {
+            declarations: [(models.clone(), vec![user])].into(),
+            ..Default::default()
+        };
+
+        let sections = assemble_sections(&[models], &options).unwrap();
+        assert_eq!(sections[0].rendering, Rendering::Declarations);
+        assert_eq!(
+            sections[0].text,
+            concat!(
+                "\nThe contents of Models.swift is as follows:\n\n",
+                "import Foundation\n",
+                "\n\n// ...\n\n",
+                "struct User {\n",
+                "    let id: Int\n",
+                "}\n",
+                "\n\n",
+                "\n--------------------------------------------------\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_marker_rewrites_apply_to_the_todo_file_only() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let feed = dir.path().join("Feed.swift");
+        let notes = dir.path().join("Notes.swift");
+        let raw = "// TODO: - [singular] make this async";
+        fs::write(&feed, format!("func load() {{\n    {}\n}}\n", raw)).unwrap();
+        fs::write(&notes, format!("let sample = \"{}\"\n{}\n", raw, raw)).unwrap();
+        let options = AssemblyOptions {
+            todo_file_basename: Some("Feed.swift".to_string()),
+            marker_rewrites: vec![(raw.to_string(), "// TODO: - make this async".to_string())],
+            ..Default::default()
+        };
+
+        let sections = assemble_sections(&[feed, notes], &options).unwrap();
+        assert!(sections[0]
+            .text
+            .contains("func load() {\n    // TODO: - make this async\n}"));
+        assert!(sections[1]
+            .text
+            .contains(&format!("let sample = \"{}\"\n{}\n", raw, raw)));
+    }
+
+    #[test]
+    fn test_todo_file_is_focused_around_its_marker() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let feed = dir.path().join("Feed.swift");
+        fs::write(
+            &feed,
+            concat!(
+                "import SwiftUI\n",
+                "\n",
+                "final class FeedModel {\n",
+                "    var page = 0\n",
+                "    var title = \"Feed\"\n",
+                "\n",
+                "    func next() {\n",
+                "        // TODO: - stop at the last page\n",
+                "        page += 1\n",
+                "    }\n",
+                "}\n",
+            ),
+        )
+        .unwrap();
+        let options = AssemblyOptions {
+            todo_file_basename: Some("Feed.swift".to_string()),
+            focus: true,
+            ..Default::default()
+        };
+
+        let sections = assemble_sections(&[feed], &options).unwrap();
+        assert_eq!(sections[0].rendering, Rendering::Focus);
+        assert!(sections[0].text.contains("        page += 1\n"));
+        assert!(sections[0].text.contains("// ..."));
+        assert!(!sections[0].text.contains("var title"));
+    }
 }
 
 #[cfg(test)]
@@ -737,6 +1325,7 @@ mod explicit_options_tests {
         let options = AssemblyOptions {
             todo_file_basename: Some("FromExplicit.swift".to_string()),
             diff_branch: None,
+            ..Default::default()
         };
 
         let output = assemble_prompt(&found_files, &options).expect("assemble_prompt failed");
@@ -0,0 +1,114 @@
+// crates/assemble_prompt/tests/byte_exact_contents.rs
+
+//! Regression suite: escapes inside quoted files must reach the prompt
+//! unchanged.  `\n` in a string literal, regex or JSON fixture is code, not
+//! a line break.
+
+use assemble_prompt::{assemble_prompt, AssemblyOptions};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{tempdir, TempDir};
+
+fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn assemble(files: &[PathBuf], todo_file: Option<&str>) -> String {
+    let options = AssemblyOptions {
+        todo_file_basename: todo_file.map(str::to_string),
+        ..Default::default()
+    };
+    assemble_prompt(files, &options).expect("assemble_prompt failed")
+}
+
+#[test]
+fn swift_string_literals_keep_their_escapes() {
+    let dir = tempdir().unwrap();
+    let content = r#"let greeting = "line1\nline2"
+let multi = """
+    first\n\tsecond \\n not a newline
+    """
+let path = "C:\\new\\table"
+"#;
+    let file = write(&dir, "Greeting.swift", content);
+
+    let output = assemble(&[file], None);
+
+    assert!(output.contains(content), "content was altered:\n{output}");
+}
+
+#[test]
+fn javascript_regexes_keep_their_escapes() {
+    let dir = tempdir().unwrap();
+    let content = r#"const lines = text.split(/\r?\n/);
+const escaped = text.replace(/\\n/g, "\n");
+const template = `row\n${value}`;
+"#;
+    let file = write(&dir, "lines.js", content);
+
+    let output = assemble(&[file], None);
+
+    assert!(output.contains(content), "content was altered:\n{output}");
+}
+
+#[test]
+fn json_fixtures_keep_their_escapes() {
+    let dir = tempdir().unwrap();
+    let content = r#"{
+  "message": "first\nsecond",
+  "windowsPath": "C:\\new\\folder",
+  "nested": "{\"inner\": \"a\\nb\"}"
+}
+"#;
+    let file = write(&dir, "fixture.json", content);
+
+    let output = assemble(&[file], None);
+
+    assert!(output.contains(content), "content was altered:\n{output}");
+}
+
+#[test]
+fn python_raw_and_triple_quoted_strings_keep_their_escapes() {
+    let dir = tempdir().unwrap();
+    let content = r#"PATTERN = r"\n+"
+DOC = """usage:\n  run"""
+"#;
+    let file = write(&dir, "patterns.py", content);
+
+    let output = assemble(&[file], None);
+
+    assert!(output.contains(content), "content was altered:\n{output}");
+}
+
+#[test]
+fn filtered_and_enclosing_context_keep_their_escapes() {
+    let dir = tempdir().unwrap();
+    let content = r#"// v
+let header = "a\nb"
+// ^
+func render() {
+    // TODO: - join with "\n"
+    print(lines.joined(separator: "\n"))
+}
+"#;
+    let file = write(&dir, "Render.swift", content);
+
+    let output = assemble(&[file], Some("Render.swift"));
+
+    assert!(output.contains(r#"let header = "a\nb""#));
+    assert!(output.contains(r#"    // TODO: - join with "\n""#));
+    assert!(output.contains(r#"print(lines.joined(separator: "\n"))"#));
+}
+
+#[test]
+fn closing_instruction_is_still_appended_once() {
+    let dir = tempdir().unwrap();
+    let file = write(&dir, "Plain.swift", "let s = \"x\\ny\"\n");
+
+    let output = assemble(&[file], None);
+
+    assert_eq!(output.matches("Can you do the TODO:-").count(), 1);
+    assert!(output.contains("let s = \"x\\ny\""));
+}
@@ -33,6 +33,7 @@ mod integration_swift {
             &AssemblyOptions {
                 todo_file_basename: Some(file_name.clone()),
                 diff_branch: None,
+                ..Default::default()
             },
         )
         .expect("assemble_prompt failed");
@@ -88,6 +89,7 @@ mod integration_swift {
             &AssemblyOptions {
                 todo_file_basename: Some(file_name1.clone()),
                 diff_branch: None,
+                ..Default::default()
             },
         )
         .expect("assemble_prompt failed");
@@ -236,6 +238,7 @@ public func genericFunction<T: Equatable>(param: T) -> T? {
             &AssemblyOptions {
                 todo_file_basename: Some(file_name.clone()),
                 diff_branch: None,
+                ..Default::default()
             },
         )
         .expect("assemble_prompt failed");
@@ -318,6 +321,7 @@ public func genericFunction<T: Equatable>(param: T) -> T? {
             &AssemblyOptions {
                 todo_file_basename: Some(file_basename),
                 diff_branch: Some("HEAD".to_string()),
+                ..Default::default()
             },
         )
         .expect("assemble_prompt failed");
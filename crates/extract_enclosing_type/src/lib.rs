@@ -2,149 +2,92 @@
 
 //
 // Finds the type (class/struct/enum) that encloses the shared TODO marker.
-// Dispatches on the file's extension via `lang_support`: Swift files use a
-// tree‑sitter pass first, and every language then falls back to its own
-// line-based scan (`LanguageSupport::extract_enclosing_type_name`).
+// Dispatches on the file's extension via `lang_support`: the language's
+// structured `EnclosingScope` is consulted first, then its own line-based
+// scan (`LanguageSupport::extract_enclosing_type_name`), and finally the
+// file's stem.
 
 use anyhow::{anyhow, Context, Result};
 use std::fs;
-use std::mem;
 use std::path::Path;
-use tree_sitter::{Node, Parser};
 
-use todo_marker::TODO_MARKER_WS;
+use todo_marker::MarkerGrammar;
 
-// ---------------------------------------------------------------------------
-//  Parser abstraction
-// ---------------------------------------------------------------------------
-
-/// Something that can parse Swift source and give back a simplified tree.
-pub trait SwiftParser {
-    fn parse_content(&mut self, content: &str) -> Option<SwiftParseTree>;
-}
-
-/// Minimal tree wrapper.
-#[derive(Clone)]
-pub struct SwiftParseTree {
-    pub root: SwiftNode,
-}
-
-/// Minimal node wrapper.
-#[derive(Clone)]
-pub struct SwiftNode {
-    pub kind: String,
-    pub start_byte: usize,
-    pub children: Vec<SwiftNode>,
-    pub name: Option<String>,
-}
-
-impl SwiftNode {
-    fn from_tree_sitter_node(node: Node, content: &str) -> Self {
-        let kind = node.kind().to_string();
-        let start_byte = node.start_byte();
-        let name = node
-            .child_by_field_name("name")
-            .and_then(|n| n.utf8_text(content.as_bytes()).ok())
-            .map(|s| s.to_string());
-
-        let mut children = Vec::new();
-        let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            if child.is_named() {
-                children.push(SwiftNode::from_tree_sitter_node(child, content));
-            }
-        }
-        SwiftNode {
-            kind,
-            start_byte,
-            children,
-            name,
-        }
-    }
-}
-
-/// Real parser backed by tree‑sitter‑swift.
-pub struct RealSwiftParser {
-    parser: Parser,
-}
-
-impl RealSwiftParser {
-    pub fn new() -> Option<Self> {
-        let mut parser = Parser::new();
-        // SAFETY: tree_sitter_swift::LANGUAGE matches the ABI expected by tree‑sitter.
-        let lang: tree_sitter::Language = unsafe { mem::transmute(tree_sitter_swift::LANGUAGE) };
-        parser.set_language(&lang).ok()?;
-        Some(Self { parser })
-    }
-}
-
-impl SwiftParser for RealSwiftParser {
-    fn parse_content(&mut self, content: &str) -> Option<SwiftParseTree> {
-        let tree = self.parser.parse(content, None)?;
-        Some(SwiftParseTree {
-            root: SwiftNode::from_tree_sitter_node(tree.root_node(), content),
-        })
-    }
-}
+pub use lang_support::{EnclosingScope, FunctionScope, ScopeKind};
 
 // ---------------------------------------------------------------------------
-//  Tree walk helpers
+//  Public API
 // ---------------------------------------------------------------------------
 
-fn find_last_type_declaration(node: &SwiftNode, todo_offset: usize) -> Option<String> {
-    let mut candidate = None;
-
-    if matches!(
-        node.kind.as_str(),
-        "class_declaration" | "struct_declaration" | "enum_declaration"
-    ) && node.start_byte <= todo_offset
-    {
-        candidate = node.name.clone();
-    }
+/// Returns the structured scope (nested types, kind, function, line range)
+/// enclosing the TODO marker.
+///
+/// `Ok(None)` means the file has no marker, its language does not model
+/// scopes, or the marker sits outside every declaration.
+pub fn extract_enclosing_scope(file_path: &Path) -> Result<Option<EnclosingScope>> {
+    extract_enclosing_scope_with_grammar(file_path, &lang_support::marker_grammar(file_path, None))
+}
 
-    for child in &node.children {
-        if let Some(found) = find_last_type_declaration(child, todo_offset) {
-            candidate = Some(found);
-        }
-    }
-    candidate
+/// [`extract_enclosing_scope`] for a TODO marker written in `grammar`.
+pub fn extract_enclosing_scope_with_grammar(
+    file_path: &Path,
+    grammar: &MarkerGrammar,
+) -> Result<Option<EnclosingScope>> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Error reading file {}", file_path.display()))?;
+    Ok(enclosing_scope_in(&content, file_path, grammar))
 }
 
-pub fn extract_enclosing_type_with_parser(
+fn enclosing_scope_in(
     content: &str,
-    todo_offset: usize,
-    parser: &mut impl SwiftParser,
-) -> Option<String> {
-    let tree = parser.parse_content(content)?;
-    find_last_type_declaration(&tree.root, todo_offset)
+    file_path: &Path,
+    grammar: &MarkerGrammar,
+) -> Option<EnclosingScope> {
+    let todo_offset = grammar.find_instruction(content)?;
+    let extension = file_path.extension().and_then(|s| s.to_str())?;
+    lang_support::for_extension(extension)?.enclosing_scope(content, todo_offset)
 }
 
-// ---------------------------------------------------------------------------
-//  Public API – fall back to regex if parser fails
-// ---------------------------------------------------------------------------
-
 /// Returns the enclosing type’s name, or (as a last resort) the file’s stem.
 pub fn extract_enclosing_type(file_path: &Path) -> Result<String> {
+    extract_enclosing_type_with_grammar(file_path, &lang_support::marker_grammar(file_path, None))
+}
+
+/// [`extract_enclosing_type`] for a TODO marker written in `grammar`.
+pub fn extract_enclosing_type_with_grammar(
+    file_path: &Path,
+    grammar: &MarkerGrammar,
+) -> Result<String> {
     let content = fs::read_to_string(file_path)
         .with_context(|| format!("Error reading file {}", file_path.display()))?;
 
-    let todo_offset = content.find(TODO_MARKER_WS).unwrap_or(content.len());
+    // 1️⃣  Innermost type of the structured scope, which also sees
+    //     `extension`, `protocol` and `actor` declarations.
+    if let Some(name) = enclosing_scope_in(&content, file_path, grammar)
+        .as_ref()
+        .and_then(EnclosingScope::type_name)
+    {
+        return Ok(name.to_string());
+    }
+
+    let todo_offset = grammar.find_instruction(&content).unwrap_or(content.len());
 
     let extension = file_path.extension().and_then(|s| s.to_str()).unwrap_or("");
     let language = lang_support::for_extension(extension);
 
-    // 1️⃣  Swift-specific fast path: try the tree‑sitter parser first.
-    if extension.eq_ignore_ascii_case("swift") {
-        if let Some(mut parser) = RealSwiftParser::new() {
-            if let Some(ty) = extract_enclosing_type_with_parser(&content, todo_offset, &mut parser)
-            {
-                return Ok(ty);
-            }
-        }
-    }
-
-    // 2️⃣  Language-dispatched line scan fallback.
-    if let Some(found) = language.and_then(|lang| lang.extract_enclosing_type_name(&content)) {
+    // 2️⃣  Language-dispatched line scan fallback, over the lines before the
+    //     one holding the marker.
+    let todo_line_start = if todo_offset < content.len() {
+        content[..todo_offset]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1)
+    } else {
+        content.len()
+    };
+    let before_todo = &content[..todo_line_start];
+    if let Some(found) =
+        language.and_then(|lang| lang.extract_enclosing_type_name(before_todo, grammar))
+    {
         return Ok(found);
     }
 
@@ -316,7 +259,7 @@ enum ThirdEnum {
     }
 
     #[test]
-    fn test_treesitter_finds_class_struct() {
+    fn test_finds_last_type_before_marker_outside_types() {
         let content = r#"
         class OuterClass {
             // Some code
@@ -337,212 +280,58 @@ enum ThirdEnum {
         assert_eq!(extracted, "NestedStruct");
     }
 
-    #[derive(Default)]
-    struct MockSwiftParserFailure;
-
-    impl SwiftParser for MockSwiftParserFailure {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            None
-        }
-    }
-
     #[test]
-    fn test_extract_enclosing_type_with_parser_returns_none() {
-        let content = "irrelevant";
-        let mut parser = MockSwiftParserFailure;
-        let todo_offset = content.len();
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // This should simulate the failure branch, without invoking unsafe code.
-        assert!(result.is_none());
-    }
-
-    #[derive(Clone)]
-    struct MockParserNoName;
-
-    impl SwiftParser for MockParserNoName {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            Some(SwiftParseTree {
-                root: SwiftNode {
-                    kind: "class_declaration".to_string(),
-                    start_byte: 0,
-                    name: None, // Force missing name
-                    children: vec![],
-                },
-            })
-        }
-    }
-
-    #[test]
-    fn test_class_declaration_with_no_name() {
-        let content = "class { } // missing name, or mock scenario";
-        let todo_offset = content.len();
-        let mut parser = MockParserNoName;
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // The parser sees a class_declaration but no name => find_last_type_declaration returns None.
-        assert_eq!(result, None);
-    }
-
-    // 1) A mock parser that always returns None.
-    #[derive(Default)]
-    struct MockSwiftParserNone;
-    impl SwiftParser for MockSwiftParserNone {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            None
-        }
-    }
-
-    #[test]
-    fn test_parse_content_returns_none() {
-        let content = "irrelevant";
-        let mut parser = MockSwiftParserNone;
-        let todo_offset = content.len();
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // We expect None, which covers the path in `extract_enclosing_type_with_parser`
-        // where parse_tree = None.
-        assert_eq!(result, None);
-    }
-
-    // 2) A mock parser with a single node whose start_byte is AFTER the TODO offset.
-    struct MockParserStartByteAfterTodo;
-    impl SwiftParser for MockParserStartByteAfterTodo {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            Some(SwiftParseTree {
-                root: SwiftNode {
-                    kind: "struct_declaration".to_string(),
-                    start_byte: 200,
-                    name: Some("LateStruct".to_string()),
-                    children: vec![],
-                },
-            })
-        }
-    }
-
-    #[test]
-    fn test_type_starts_after_todo_offset() {
-        let content = "class EarlyClass {} // no TODO yet...";
-        // We'll pretend the TODO offset is 100, so the struct at start_byte=200 is skipped.
-        let todo_offset = 100;
-        let mut parser = MockParserStartByteAfterTodo;
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // Because the only node is after the offset, find_last_type_declaration should return None.
-        assert_eq!(result, None);
-    }
-
-    // 3) A mock parser that simulates multiple children, including an unnamed node.
-    struct MockParserMultipleChildren;
-    impl SwiftParser for MockParserMultipleChildren {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            Some(SwiftParseTree {
-                root: SwiftNode {
-                    kind: "class_declaration".to_string(),
-                    start_byte: 0,
-                    name: Some("Outer".to_string()),
-                    children: vec![
-                        // A child that's not recognized as a type
-                        SwiftNode {
-                            kind: "function_declaration".to_string(),
-                            start_byte: 10,
-                            name: Some("doSomething".to_string()),
-                            children: vec![],
-                        },
-                        // A nested struct_declaration
-                        SwiftNode {
-                            kind: "struct_declaration".to_string(),
-                            start_byte: 20,
-                            name: Some("Inner".to_string()),
-                            children: vec![],
-                        },
-                    ],
-                },
-            })
+    fn test_enclosing_type_prefers_innermost_scope_type() {
+        let content = "\
+struct Outer {
+    class Inner {
+        func reload() {
+            // TODO: - Refresh
         }
     }
+    struct After {}
+}";
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let file_path = tmp_dir.path().join("Nested.swift");
+        fs::write(&file_path, content).unwrap();
 
-    #[test]
-    fn test_multiple_children_recursion() {
-        let content = "class Outer { func doSomething() {} struct Inner {} }";
-        let todo_offset = content.len();
-        let mut parser = MockParserMultipleChildren;
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // The last type encountered is "Inner".
-        assert_eq!(result, Some("Inner".to_string()));
-    }
+        assert_eq!(extract_enclosing_type(&file_path).unwrap(), "Inner");
 
-    // 4) A mock parser that simulates an invalid UTF-8 scenario
-    //    or simply a "class_declaration" but name is None.
-    struct MockParserNoNameInvalidUtf8;
-    impl SwiftParser for MockParserNoNameInvalidUtf8 {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            Some(SwiftParseTree {
-                root: SwiftNode {
-                    kind: "class_declaration".to_string(),
-                    start_byte: 0,
-                    // Pretend we tried utf8_text and it failed => name is None
-                    name: None,
-                    children: vec![],
-                },
-            })
-        }
+        let scope = extract_enclosing_scope(&file_path).unwrap().unwrap();
+        assert_eq!(scope.qualified_name(), "Outer.Inner.reload()");
+        assert_eq!(scope.kind, Some(ScopeKind::Class));
+        assert_eq!(scope.lines, 3..=5);
     }
 
     #[test]
-    fn test_class_declaration_missing_name() {
-        let content = "class {} // invalid syntax but let's mock it anyway";
-        let todo_offset = content.len();
-        let mut parser = MockParserNoNameInvalidUtf8;
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // We found a class_declaration but there's no name => returns None.
-        assert_eq!(result, None);
+    fn test_enclosing_type_sees_extensions() {
+        let content = "\
+class Feed {}
+extension Feed {
+    func refresh() {
+        // TODO: - Refresh
     }
+}";
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let file_path = tmp_dir.path().join("Feed+Refresh.swift");
+        fs::write(&file_path, content).unwrap();
 
-    struct MockParserSkipType;
-
-    impl SwiftParser for MockParserSkipType {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            Some(SwiftParseTree {
-                root: SwiftNode {
-                    kind: "struct_declaration".to_string(),
-                    start_byte: 300,
-                    name: Some("LateStruct".to_string()),
-                    children: vec![],
-                },
-            })
-        }
+        assert_eq!(extract_enclosing_type(&file_path).unwrap(), "Feed");
+        let scope = extract_enclosing_scope(&file_path).unwrap().unwrap();
+        assert_eq!(scope.kind, Some(ScopeKind::Extension));
     }
 
     #[test]
-    fn test_skip_type_after_todo_offset() {
-        let content =
-            "class EarlyClass {} // ...no TODO in content, but let's pretend it's earlier";
-        let todo_offset = 100;
-        let mut parser = MockParserSkipType;
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // The single struct_declaration starts at 300, which is after 100 => should be skipped => None
-        assert_eq!(result, None);
-    }
+    fn test_enclosing_scope_none_without_marker_or_language() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let no_marker = tmp_dir.path().join("NoMarker.swift");
+        fs::write(&no_marker, "class Foo {}").unwrap();
+        assert!(extract_enclosing_scope(&no_marker).unwrap().is_none());
 
-    struct MockParserInvalidUtf8;
-
-    impl SwiftParser for MockParserInvalidUtf8 {
-        fn parse_content(&mut self, _content: &str) -> Option<SwiftParseTree> {
-            Some(SwiftParseTree {
-                root: SwiftNode {
-                    kind: "class_declaration".to_string(),
-                    start_byte: 0,
-                    name: None, // pretend we tried to parse it and it failed
-                    children: vec![],
-                },
-            })
-        }
-    }
+        let js = tmp_dir.path().join("file.js");
+        fs::write(&js, "class Foo {\n  // TODO: - x\n}").unwrap();
+        assert!(extract_enclosing_scope(&js).unwrap().is_none());
 
-    #[test]
-    fn test_class_declaration_invalid_utf8_name() {
-        let mut parser = MockParserInvalidUtf8;
-        let content = "class ???";
-        let todo_offset = content.len();
-        let result = extract_enclosing_type_with_parser(content, todo_offset, &mut parser);
-        // No name => None
-        assert_eq!(result, None);
+        assert!(extract_enclosing_scope(&tmp_dir.path().join("Missing.swift")).is_err());
     }
 }
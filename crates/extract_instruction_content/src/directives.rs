@@ -0,0 +1,248 @@
+// crates/extract_instruction_content/src/directives.rs
+
+//! Directives carried by the TODO line itself, so the options a TODO needs
+//! travel with it instead of being retyped on the command line:
+//!
+//! ```text
+//! // TODO: - [singular, refs, exclude=Legacy.swift, +NetworkClient] make this async
+//! ```
+//!
+//! The bracket must directly follow the marker.  A bracket holding anything
+//! that is not a directive (e.g. `[WIP]`) is left in the instruction.
+//!
+//! A `#tag` in the same position (before or after the bracket) groups the
+//! TODO with every other marker carrying that tag, for multi-task prompts:
+//!
+//! ```text
+//! // TODO: - #auth make login async
+//! ```
+
+use todo_marker::{MarkerGrammar, MarkerVerb};
+
+/// Options parsed from the directive bracket of a TODO line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directives {
+    /// `singular` — only include the TODO file.
+    pub singular: bool,
+    /// `global` — search from the Git root.
+    pub force_global: bool,
+    /// `refs` — include files referencing the enclosing type.
+    pub include_references: bool,
+    /// `tgtd` — only extract types from the enclosing block.
+    pub targeted: bool,
+    /// `+Type` — look up the definition of `Type` even if the TODO file does
+    /// not mention it.
+    pub include_types: Vec<String>,
+    /// `-Type` — never look up the definition of `Type`.
+    pub exclude_types: Vec<String>,
+    /// `include=File.swift` — include files with this basename.
+    pub include_files: Vec<String>,
+    /// `exclude=File.swift` — leave out files with this basename.
+    pub exclude_files: Vec<String>,
+}
+
+impl Directives {
+    pub fn is_empty(&self) -> bool {
+        *self == Directives::default()
+    }
+
+    /// Parses the comma-separated entries of a directive bracket, or `None`
+    /// if any entry is not a directive.
+    fn parse(entries: &str) -> Option<Self> {
+        let mut directives = Directives::default();
+        for entry in entries.split(',').map(str::trim) {
+            match entry {
+                "singular" => directives.singular = true,
+                "global" => directives.force_global = true,
+                "refs" => directives.include_references = true,
+                "tgtd" => directives.targeted = true,
+                _ => {
+                    if let Some(name) = entry.strip_prefix('+') {
+                        directives.include_types.push(identifier(name)?);
+                    } else if let Some(name) = entry.strip_prefix('-') {
+                        directives.exclude_types.push(identifier(name)?);
+                    } else if let Some(file) = entry.strip_prefix("include=") {
+                        directives.include_files.push(file_name(file)?);
+                    } else if let Some(file) = entry.strip_prefix("exclude=") {
+                        directives.exclude_files.push(file_name(file)?);
+                    } else {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(directives)
+    }
+}
+
+fn identifier(name: &str) -> Option<String> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    let starts_well = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_');
+    (starts_well && chars.all(|c| c.is_alphanumeric() || c == '_')).then(|| name.to_string())
+}
+
+fn file_name(file: &str) -> Option<String> {
+    let file = file.trim();
+    (!file.is_empty() && !file.contains(char::is_whitespace)).then(|| file.to_string())
+}
+
+/// `#tag` at the start of `text`, and the text after it.
+fn split_tag(text: &str) -> Option<(&str, &str)> {
+    let body = text.strip_prefix('#')?;
+    let end = body
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(body.len());
+    let after = &body[end..];
+    (end > 0 && (after.is_empty() || after.starts_with(char::is_whitespace)))
+        .then(|| (&body[..end], after))
+}
+
+/// The TODO line with its directives parsed out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstructionContent {
+    /// The line as written in the file, trimmed of leading whitespace.
+    pub raw: String,
+    /// The line without its directive bracket, e.g. `// TODO: - make this async`.
+    pub instruction: String,
+    pub directives: Directives,
+    pub verb: MarkerVerb,
+    /// The task group, e.g. `auth` for `// TODO: - #auth ...`.
+    pub group: Option<String>,
+    /// What the marker asks for, without the marker, tag or directives.
+    pub task: String,
+}
+
+/// Splits the directive bracket and task-group tag off a TODO `line`.
+/// The tag stays in the instruction; lines without either come back
+/// unchanged.
+pub fn parse_instruction(line: &str, grammar: &MarkerGrammar) -> InstructionContent {
+    let raw = line.trim_start().to_string();
+    let mut content = InstructionContent {
+        raw: raw.clone(),
+        instruction: raw.clone(),
+        ..Default::default()
+    };
+
+    let Some((offset, verb)) = grammar.find_marker(&raw) else {
+        return content;
+    };
+    content.verb = verb;
+    let token = grammar.token_for(verb);
+    let Some(token_end) = raw[offset..].find(token).map(|i| offset + i + token.len()) else {
+        return content;
+    };
+
+    let mut rest = raw[token_end..].trim_start();
+    let mut stripped_directives = false;
+    loop {
+        if let Some((tag, after)) = split_tag(rest).filter(|_| content.group.is_none()) {
+            content.group = Some(tag.to_string());
+            rest = after.trim_start();
+        } else if let Some((directives, after)) = rest
+            .strip_prefix('[')
+            .and_then(|inner| inner.split_once(']'))
+            .filter(|_| !stripped_directives)
+            .and_then(|(entries, after)| Some((Directives::parse(entries)?, after)))
+        {
+            content.directives = directives;
+            stripped_directives = true;
+            rest = after.trim_start();
+        } else {
+            break;
+        }
+    }
+
+    content.task = rest.trim_end().to_string();
+    if stripped_directives {
+        content.instruction = match &content.group {
+            Some(tag) => format!("{} #{} {}", &raw[..token_end], tag, rest),
+            None => format!("{} {}", &raw[..token_end], rest),
+        };
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_are_parsed_and_stripped() {
+        let parsed = parse_instruction(
+            "    // TODO: - [singular, refs, exclude=Legacy.swift, +NetworkClient, -Logger] make this async",
+            &MarkerGrammar::default(),
+        );
+
+        assert_eq!(parsed.instruction, "// TODO: - make this async");
+        assert_eq!(
+            parsed.raw,
+            "// TODO: - [singular, refs, exclude=Legacy.swift, +NetworkClient, -Logger] make this async"
+        );
+        assert_eq!(
+            parsed.directives,
+            Directives {
+                singular: true,
+                include_references: true,
+                include_types: vec!["NetworkClient".to_string()],
+                exclude_types: vec!["Logger".to_string()],
+                exclude_files: vec!["Legacy.swift".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn brackets_that_are_not_directives_stay_in_the_instruction() {
+        let grammar = MarkerGrammar::default();
+        for line in [
+            "// TODO: - [WIP] make this async",
+            "// TODO: - [singular, soon] make this async",
+            "// TODO: - make [singular] this async",
+            "// TODO: - [+] unbalanced",
+        ] {
+            let parsed = parse_instruction(line, &grammar);
+            assert_eq!(parsed.instruction, line);
+            assert!(parsed.directives.is_empty(), "{line}");
+        }
+    }
+
+    #[test]
+    fn group_tags_are_parsed_and_kept() {
+        let grammar = MarkerGrammar::default();
+
+        let parsed = parse_instruction("// TODO: - #auth make login async", &grammar);
+        assert_eq!(parsed.group.as_deref(), Some("auth"));
+        assert_eq!(parsed.instruction, "// TODO: - #auth make login async");
+        assert_eq!(parsed.task, "make login async");
+
+        for line in [
+            "// TODO: - [singular] #auth-v2 make login async",
+            "// TODO: - #auth-v2 [singular] make login async",
+        ] {
+            let parsed = parse_instruction(line, &grammar);
+            assert_eq!(parsed.group.as_deref(), Some("auth-v2"), "{line}");
+            assert!(parsed.directives.singular, "{line}");
+            assert_eq!(parsed.instruction, "// TODO: - #auth-v2 make login async");
+        }
+
+        let parsed = parse_instruction("// FIXME: - #2 retry on timeout", &grammar);
+        assert_eq!(parsed.group.as_deref(), Some("2"));
+        assert_eq!(parsed.verb, MarkerVerb::Fixme);
+        let parsed = parse_instruction("// TODO: - fix #42 later", &grammar);
+        assert_eq!(parsed.group, None);
+        assert_eq!(parsed.task, "fix #42 later");
+    }
+
+    #[test]
+    fn directives_follow_the_grammar() {
+        let parsed = parse_instruction(
+            "# REVIEW: - [tgtd, global, include=conftest.py] is this safe?",
+            &MarkerGrammar::HASH,
+        );
+        assert_eq!(parsed.instruction, "# REVIEW: - is this safe?");
+        assert!(parsed.directives.targeted);
+        assert!(parsed.directives.force_global);
+        assert_eq!(parsed.directives.include_files, vec!["conftest.py"]);
+    }
+}
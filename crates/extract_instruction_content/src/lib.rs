@@ -1,10 +1,14 @@
 // crates/extract_instruction_content/src/lib.rs
 
+mod directives;
+
+pub use directives::{parse_instruction, Directives, InstructionContent};
+
 use anyhow::{Context, Result};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
-use todo_marker::TODO_MARKER_WS as MARK;
+use todo_marker::MarkerGrammar;
 
 /// Reads the given Swift file and returns the first line that contains the TODO marker.
 /// The returned string is trimmed of any leading whitespace.
@@ -16,18 +20,29 @@ use todo_marker::TODO_MARKER_WS as MARK;
 /// # Errors
 ///
 /// Returns an error if the file cannot be opened, read, or if no valid TODO instruction is found.
+/// Any directive bracket (`[singular, +Type]`) is stripped from the line.
 pub fn extract_instruction_content<P: AsRef<Path>>(file_path: P) -> Result<String> {
+    extract_instruction_content_with_grammar(file_path, &MarkerGrammar::default())
+        .map(|content| content.instruction)
+}
+
+/// Like [`extract_instruction_content`], but recognises the marker as written
+/// in `grammar` (e.g. `# TODO: - ` in a Python file) and returns the parsed
+/// directives alongside the clean instruction.
+pub fn extract_instruction_content_with_grammar<P: AsRef<Path>>(
+    file_path: P,
+    grammar: &MarkerGrammar,
+) -> Result<InstructionContent> {
     let file_path_ref = file_path.as_ref();
     let file = File::open(file_path_ref)
         .with_context(|| format!("Error opening file {}", file_path_ref.display()))?;
     let reader = BufReader::new(file);
-    let marker = MARK;
 
     for line in reader.lines() {
         let line =
             line.with_context(|| format!("Error reading file {}", file_path_ref.display()))?;
-        if line.contains(marker) {
-            return Ok(line.trim_start().to_string());
+        if grammar.find_instruction(&line).is_some() {
+            return Ok(parse_instruction(&line, grammar));
         }
     }
 
@@ -37,6 +52,34 @@ pub fn extract_instruction_content<P: AsRef<Path>>(file_path: P) -> Result<Strin
     );
 }
 
+/// Parses the marker on 1-based `line` of the file, for a marker picked by
+/// position rather than the first one in the file.
+pub fn extract_instruction_at_line_with_grammar<P: AsRef<Path>>(
+    file_path: P,
+    line: usize,
+    grammar: &MarkerGrammar,
+) -> Result<InstructionContent> {
+    let file_path_ref = file_path.as_ref();
+    let file = File::open(file_path_ref)
+        .with_context(|| format!("Error opening file {}", file_path_ref.display()))?;
+    let text = BufReader::new(file)
+        .lines()
+        .nth(line.saturating_sub(1))
+        .transpose()
+        .with_context(|| format!("Error reading file {}", file_path_ref.display()))?;
+
+    match text {
+        Some(text) if line > 0 && grammar.find_instruction(&text).is_some() => {
+            Ok(parse_instruction(&text, grammar))
+        }
+        _ => anyhow::bail!(
+            "No valid TODO instruction found at {}:{}",
+            file_path_ref.display(),
+            line
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,4 +160,52 @@ mod tests {
             err_msg
         );
     }
+
+    #[test]
+    fn test_extract_with_hash_grammar() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temp file");
+        let content = "x = 1  // TODO: - not python\n    # TODO: - Cache the result\n";
+        write!(temp_file, "{}", content).expect("Failed to write to temp file");
+
+        let extracted =
+            extract_instruction_content_with_grammar(temp_file.path(), &MarkerGrammar::HASH)
+                .unwrap();
+        assert_eq!(extracted.instruction, "# TODO: - Cache the result");
+    }
+
+    #[test]
+    fn test_extract_strips_directives() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temp file");
+        let content = "    // TODO: - [singular, +Client] Make it async\n";
+        write!(temp_file, "{}", content).expect("Failed to write to temp file");
+
+        let extracted = extract_instruction_content(temp_file.path()).unwrap();
+        assert_eq!(extracted, "// TODO: - Make it async");
+    }
+
+    #[test]
+    fn test_extract_instruction_at_line() {
+        let mut temp_file = NamedTempFile::new().expect("Failed to create temp file");
+        write!(
+            temp_file,
+            "// TODO: - First
+code
+    // FIXME: - [singular] Second
+"
+        )
+        .unwrap();
+        let grammar = MarkerGrammar::default();
+
+        let content = extract_instruction_at_line_with_grammar(temp_file.path(), 3, &grammar)
+            .expect("marker on line 3");
+        assert_eq!(content.instruction, "// FIXME: - Second");
+        assert!(content.directives.singular);
+
+        for line in [0, 2, 9] {
+            assert!(
+                extract_instruction_at_line_with_grammar(temp_file.path(), line, &grammar).is_err(),
+                "line {line}"
+            );
+        }
+    }
 }
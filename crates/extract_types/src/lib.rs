@@ -5,9 +5,12 @@ use std::collections::BTreeSet;
 use std::fs;
 use std::path::Path;
 
-use lang_support::{extract_generic_identifiers, for_extension};
+use lang_support::{
+    extract_generic_identifiers, for_extension, marker_grammar, BlockMap, LanguageSupport,
+    LexicalRules,
+};
 use substring_marker_snippet_extractor::{EnclosingBlockScope, FileAnalysis};
-use todo_marker::{TODO_MARKER, TODO_MARKER_WS};
+use todo_marker::MarkerGrammar;
 
 /// Test-only helper that exercises the Swift type-candidate predicate
 /// explicitly. Production code dispatches this predicate on each file's own
@@ -21,9 +24,11 @@ fn swift_type_candidate_line(line: &str) -> bool {
 /// ---------------------------------------------------------------------------
 ///  Public API
 /// ---------------------------------------------------------------------------
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct ExtractTypesOptions {
     pub targeted: bool,
+    /// User-chosen marker token replacing `TODO: -`, if any.
+    pub marker_token: Option<String>,
 }
 
 pub fn extract_types_from_file<P: AsRef<Path>>(swift_file: P) -> Result<BTreeSet<String>> {
@@ -43,19 +48,27 @@ pub fn extract_types_from_file_with_options<P: AsRef<Path>>(
     // a hardcoded one.
     let ext = swift_file.as_ref().extension().and_then(|s| s.to_str());
     let language = ext.and_then(for_extension);
+    let grammar = marker_grammar(swift_file.as_ref(), options.marker_token.as_deref());
 
     // Decide which slice of the file to analyse
     let content_slice = if options.targeted {
-        if let Some(inner) = extract_inner_block_from_content(&full_content) {
+        if let Some(inner) = extract_inner_block_from_content(&full_content, language, &grammar) {
             inner
         } else {
             full_content.clone()
         }
     } else {
-        let analysis = FileAnalysis::for_path(&full_content, swift_file.as_ref());
+        let analysis = FileAnalysis::for_path_with_grammar(
+            &full_content,
+            swift_file.as_ref(),
+            grammar.clone(),
+        );
         if analysis.has_markers() {
             let mut filtered = analysis.filtered_content("");
-            if !filtered.contains(TODO_MARKER) {
+            if !filtered
+                .lines()
+                .any(|line| analysis.grammar().mentions_marker(line))
+            {
                 if let Some(enclosing) =
                     analysis.enclosing_block(EnclosingBlockScope::FunctionsAndTypes)
                 {
@@ -70,7 +83,7 @@ pub fn extract_types_from_file_with_options<P: AsRef<Path>>(
     };
 
     // 1️⃣  Generic, language-agnostic token extraction on the slice.
-    let mut all_types: BTreeSet<String> = extract_generic_identifiers(&content_slice)
+    let mut all_types: BTreeSet<String> = extract_generic_identifiers(&content_slice, &grammar)
         .into_iter()
         .collect();
 
@@ -85,38 +98,22 @@ pub fn extract_types_from_file_with_options<P: AsRef<Path>>(
 }
 
 /// ---------------------------------------------------------------------------
-///  Helper: extract inner block (targeted mode, unchanged)
+///  Helper: extract inner block (targeted mode)
 /// ---------------------------------------------------------------------------
-fn extract_inner_block_from_content(content: &str) -> Option<String> {
-    let pos = content.find(TODO_MARKER_WS)?;
-    let mut stack = Vec::new();
-
-    for (i, ch) in content[..pos].char_indices() {
-        if ch == '{' {
-            stack.push(i);
-        } else if ch == '}' {
-            stack.pop();
-        }
-    }
-    let open_brace = stack.pop()?;
-
-    let mut brace_count = 1;
-    let mut index = open_brace + 1;
-    let bytes = content.as_bytes();
-    while index < content.len() && brace_count > 0 {
-        match bytes[index] {
-            b'{' => brace_count += 1,
-            b'}' => brace_count -= 1,
-            _ => {}
-        }
-        index += 1;
-    }
-
-    if brace_count == 0 {
-        Some(content[open_brace + 1..index - 1].to_string())
-    } else {
-        None
-    }
+/// Returns the body of the innermost block around the TODO marker, delimited
+/// the way `language` delimits blocks (C-like braces when it is unknown).
+fn extract_inner_block_from_content(
+    content: &str,
+    language: Option<&dyn LanguageSupport>,
+    grammar: &MarkerGrammar,
+) -> Option<String> {
+    let pos = grammar.find_instruction(content)?;
+    let blocks = language.map_or_else(
+        || BlockMap::new(content, &LexicalRules::C_LIKE),
+        |language| language.block_map(content),
+    );
+    let (open, close) = blocks.innermost_containing(pos)?;
+    Some(content[open + 1..close].to_string())
 }
 
 #[cfg(test)]
@@ -143,7 +140,7 @@ mod tests {
                 }
             }
         "#;
-        let inner = extract_inner_block_from_content(content);
+        let inner = extract_inner_block_from_content(content, None, &MarkerGrammar::default());
         assert!(inner.is_some());
         let inner_str = inner.unwrap();
         // Ensure that the extracted inner block contains the inner declaration and the TODO marker,
@@ -168,7 +165,9 @@ mod tests {
     #[test]
     fn test_extract_inner_block_no_marker() {
         let content = "func foo() { class InnerType {} }";
-        assert!(extract_inner_block_from_content(content).is_none());
+        assert!(
+            extract_inner_block_from_content(content, None, &MarkerGrammar::default()).is_none()
+        );
     }
 
     #[test]
@@ -257,6 +256,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_extract_types_includes_trigger_comment_with_custom_token() -> Result<()> {
+        let mut swift_file = tempfile::Builder::new().suffix(".swift").tempfile()?;
+        writeln!(
+            swift_file,
+            "import Foundation\n// AI: - make NetworkClient retry"
+        )?;
+        let result = extract_types_from_file_with_options(
+            swift_file.path(),
+            &ExtractTypesOptions {
+                marker_token: Some("AI: -".to_string()),
+                ..Default::default()
+            },
+        )?;
+        assert!(result.contains("NetworkClient"), "{:?}", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_types_includes_python_trigger_comment() -> Result<()> {
+        let mut python_file = tempfile::Builder::new().suffix(".py").tempfile()?;
+        writeln!(
+            python_file,
+            "# Talks to the LegacyGateway\n# TODO: - charge through PaymentGateway"
+        )?;
+        let result = extract_types_from_file(python_file.path())?;
+        assert!(result.contains("PaymentGateway"), "{:?}", result);
+        assert!(!result.contains("LegacyGateway"), "{:?}", result);
+        Ok(())
+    }
+
     #[test]
     fn test_extract_types_with_substring_markers() -> Result<()> {
         let swift_content = r#"
@@ -357,12 +387,71 @@ class Widget {\n\
         write!(swift_file, "{}", swift_content)?;
         let result = extract_types_from_file_with_options(
             swift_file.path(),
-            &ExtractTypesOptions { targeted: true },
+            &ExtractTypesOptions {
+                targeted: true,
+                ..Default::default()
+            },
         )?;
         assert_eq!(result, types(&["InnerType", "Perform"]));
         Ok(())
     }
 
+    #[test]
+    fn test_extract_types_targeted_mode_ignores_braces_in_literals() -> Result<()> {
+        let swift_content = r#"
+class OuterType {}
+func testFunction() {
+    let banner = """
+    } closing brace in a multi-line string
+    """
+    // A comment with a stray }
+    class InnerType {}
+    // TODO: - Perform action
+}
+"#;
+        let mut swift_file = tempfile::Builder::new().suffix(".swift").tempfile()?;
+        write!(swift_file, "{}", swift_content)?;
+        let result = extract_types_from_file_with_options(
+            swift_file.path(),
+            &ExtractTypesOptions {
+                targeted: true,
+                ..Default::default()
+            },
+        )?;
+        assert!(result.contains("InnerType"), "{:?}", result);
+        assert!(!result.contains("OuterType"), "{:?}", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_types_targeted_mode_python_indented_block() -> Result<()> {
+        let python_content = r#"
+class OuterType:
+    pass
+
+def handle(request):
+    payload = InnerType.parse(request)
+    # TODO: - Validate the payload
+    return payload
+
+class LaterType:
+    pass
+"#;
+        let mut python_file = tempfile::Builder::new().suffix(".py").tempfile()?;
+        write!(python_file, "{}", python_content)?;
+        let result = extract_types_from_file_with_options(
+            python_file.path(),
+            &ExtractTypesOptions {
+                targeted: true,
+                ..Default::default()
+            },
+        )?;
+        assert!(result.contains("InnerType"), "{:?}", result);
+        assert!(!result.contains("OuterType"), "{:?}", result);
+        assert!(!result.contains("LaterType"), "{:?}", result);
+        Ok(())
+    }
+
     #[test]
     fn test_extract_types_targeted_mode_no_enclosing_block() -> Result<()> {
         let swift_content = r#"
@@ -373,7 +462,10 @@ class Widget {\n\
         write!(swift_file, "{}", swift_content)?;
         let result = extract_types_from_file_with_options(
             swift_file.path(),
-            &ExtractTypesOptions { targeted: true },
+            &ExtractTypesOptions {
+                targeted: true,
+                ..Default::default()
+            },
         )?;
         assert_eq!(result, types(&["OuterType", "Some"]));
         Ok(())
@@ -420,7 +512,9 @@ func testFunction() {\n\
     class InnerType {}\n\
     // TODO: - Missing close\n";
 
-        assert!(extract_inner_block_from_content(content).is_none());
+        assert!(
+            extract_inner_block_from_content(content, None, &MarkerGrammar::default()).is_none()
+        );
     }
 }
 
@@ -145,7 +145,10 @@ fn integration_extract_types_targeted_mode() -> Result<()> {
 
     let result = extract_types_from_file_with_options(
         temp_file.path(),
-        &ExtractTypesOptions { targeted: true },
+        &ExtractTypesOptions {
+            targeted: true,
+            ..Default::default()
+        },
     )?;
     assert_eq!(result, types(&["InnerType", "Perform"]));
 
@@ -209,7 +212,10 @@ fn integration_extract_types_explicit_targeted_option_ignores_env() -> Result<()
 
     let result = extract_types_from_file_with_options(
         temp_file.path(),
-        &ExtractTypesOptions { targeted: true },
+        &ExtractTypesOptions {
+            targeted: true,
+            ..Default::default()
+        },
     )?;
     assert_eq!(result, types(&["InnerType", "Perform"]));
 
@@ -232,7 +238,10 @@ fn integration_extract_types_explicit_non_targeted_option_ignores_targeted_env()
 
     let result = extract_types_from_file_with_options(
         temp_file.path(),
-        &ExtractTypesOptions { targeted: false },
+        &ExtractTypesOptions {
+            targeted: false,
+            ..Default::default()
+        },
     )?;
     assert_eq!(result, types(&["InnerType", "OuterType", "Perform"]));
 
@@ -256,7 +265,10 @@ fn integration_extract_types_explicit_targeted_option_works_with_targeted_env()
 
     let result = extract_types_from_file_with_options(
         temp_file.path(),
-        &ExtractTypesOptions { targeted: true },
+        &ExtractTypesOptions {
+            targeted: true,
+            ..Default::default()
+        },
     )?;
     assert_eq!(result, types(&["InnerType", "Perform"]));
 
@@ -276,7 +288,10 @@ fn integration_extract_types_targeted_mode_no_enclosing_block() -> Result<()> {
 
     let result = extract_types_from_file_with_options(
         temp_file.path(),
-        &ExtractTypesOptions { targeted: true },
+        &ExtractTypesOptions {
+            targeted: true,
+            ..Default::default()
+        },
     )?;
     assert_eq!(result, types(&["OuterType", "Some"]));
 
@@ -304,7 +319,10 @@ fn integration_extract_types_targeted_inner_block_excludes_outer() -> Result<()>
 
     let result = extract_types_from_file_with_options(
         temp_file.path(),
-        &ExtractTypesOptions { targeted: true },
+        &ExtractTypesOptions {
+            targeted: true,
+            ..Default::default()
+        },
     )?;
     assert_eq!(result, types(&["Do", "InnerType"]));
 
@@ -2,10 +2,12 @@
 
 use anyhow::Result;
 use std::collections::BTreeSet;
+use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
 use get_search_roots::get_search_roots;
-use lang_support::{walk_source_files, SourceFile};
+use lang_support::{walk_source_files, SymbolIndex};
 
 /// ---------------------------------------------------------------------------
 ///  DefinitionFinder
@@ -29,38 +31,143 @@ impl DefinitionFinder {
         let mut found_files = BTreeSet::new();
 
         for sr in &self.search_roots {
-            for source_file in walk_source_files(sr) {
-                if source_file
-                    .language
-                    .file_defines_any(&source_file.content, &self.types)
-                {
-                    found_files.insert(source_file.path);
-                }
-            }
+            let index = SymbolIndex::new(walk_source_files(sr));
+            found_files.extend(index.files_defining(&self.types));
         }
 
         found_files
     }
 }
 
-/// Filters a pre-walked set of source files for those that define any of the
-/// requested types. Use when the caller has already materialised the source
-/// collection and wants to avoid a redundant filesystem walk.
+/// Looks up the files of an indexed source collection that define any of
+/// the requested types. Use when the caller has already materialised the
+/// source collection and wants to avoid a redundant filesystem walk.
 pub fn find_definition_files_from_sources(
     types: &BTreeSet<String>,
-    sources: &[SourceFile],
+    index: &SymbolIndex,
 ) -> BTreeSet<PathBuf> {
-    if types.is_empty() {
+    index.files_defining(types)
+}
+
+/// Looks up the files of an indexed source collection that extend any of
+/// the requested types without defining them (Swift `extension Foo`, Obj‑C
+/// categories). Files that also define one of the types are left to
+/// `find_definition_files_from_sources`.
+pub fn find_extension_files_from_sources(
+    types: &BTreeSet<String>,
+    index: &SymbolIndex,
+) -> BTreeSet<PathBuf> {
+    index.files_extending(types)
+}
+
+/// Files that `todo_file` imports by naming one of `types`, as resolved by
+/// its language's `resolve_dependency_path` — e.g. Python's
+/// `from app.models import User`.  This picks up the module the file really
+/// uses even when it lies outside the search roots or only re-exports the
+/// name, as a package `__init__.py` does.
+pub fn find_imported_definition_files(
+    types: &BTreeSet<String>,
+    todo_file: &Path,
+) -> BTreeSet<PathBuf> {
+    let language = todo_file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(lang_support::for_extension);
+    let (Some(language), Some(current_dir)) = (language, todo_file.parent()) else {
         return BTreeSet::new();
+    };
+    let Ok(content) = fs::read_to_string(todo_file) else {
+        return BTreeSet::new();
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut found = BTreeSet::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(path) = language.resolve_dependency_path(line, current_dir) else {
+            continue;
+        };
+        if path.is_file() && import_names_any(&lines[i..], types) {
+            found.insert(path);
+        }
+    }
+    found
+}
+
+/// Whether the import statement starting at `lines[0]` names any of `types`.
+/// A parenthesised name list may continue over the following lines.
+fn import_names_any(lines: &[&str], types: &BTreeSet<String>) -> bool {
+    let mut open_parens = 0i32;
+    for line in lines {
+        let names_type = line
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .any(|word| types.contains(word));
+        if names_type {
+            return true;
+        }
+        open_parens += line.matches('(').count() as i32 - line.matches(')').count() as i32;
+        if open_parens <= 0 {
+            break;
+        }
     }
-    let type_vec: Vec<String> = types.iter().cloned().collect();
-    sources
+    false
+}
+
+/// Where one of the requested types is declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionHit {
+    pub path: PathBuf,
+    pub name: String,
+    /// The declaration's lines, 0-based and end-exclusive, doc comments
+    /// and attributes included.
+    pub lines: Range<usize>,
+}
+
+/// The declarations of `types` in each of `files`, in file and line order.
+///
+/// A file only has hits when its language can place every one of `types`
+/// it defines; any other file, like one that only re-exports a type, has
+/// none and is meant to be shown whole.
+pub fn find_definition_hits(
+    types: &BTreeSet<String>,
+    files: &BTreeSet<PathBuf>,
+) -> Vec<DefinitionHit> {
+    files
         .iter()
-        .filter(|sf| sf.language.file_defines_any(&sf.content, &type_vec))
-        .map(|sf| sf.path.clone())
+        .flat_map(|path| definition_hits_in(types, path))
         .collect()
 }
 
+fn definition_hits_in(types: &BTreeSet<String>, path: &Path) -> Vec<DefinitionHit> {
+    let language = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(lang_support::for_extension);
+    let (Some(language), Ok(content)) = (language, fs::read_to_string(path)) else {
+        return Vec::new();
+    };
+
+    let defined: BTreeSet<String> = language
+        .declarations(&content)
+        .into_iter()
+        .filter(|decl| decl.defines() && types.contains(&decl.name))
+        .map(|decl| decl.name)
+        .collect();
+    let mut hits = Vec::new();
+    for name in defined {
+        let lines = language.declaration_lines(&content, &name);
+        if lines.is_empty() {
+            return Vec::new();
+        }
+        hits.extend(lines.into_iter().map(|lines| DefinitionHit {
+            path: path.to_path_buf(),
+            name: name.clone(),
+            lines,
+        }));
+    }
+    hits.sort_by_key(|hit| (hit.lines.start, hit.lines.end));
+    hits
+}
+
 /// Public API — walks the source tree then filters for definitions.
 pub fn find_definition_files(types: &BTreeSet<String>, root: &Path) -> Result<BTreeSet<PathBuf>> {
     if types.is_empty() {
@@ -91,6 +198,55 @@ mod tests {
         assert_eq!(roots[0], dir.path());
     }
 
+    #[test]
+    fn test_find_imported_definition_files_python() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let app = root.join("app");
+        fs::create_dir_all(app.join("models")).unwrap();
+        let init = app.join("models").join("__init__.py");
+        fs::write(&init, "from .user import User\n").unwrap();
+        let services = app.join("services.py");
+        fs::write(&services, "def send(): pass\n").unwrap();
+        let helpers = app.join("helpers.py");
+        fs::write(&helpers, "def slugify(s): pass\n").unwrap();
+
+        let todo_file = app.join("views.py");
+        fs::write(
+            &todo_file,
+            "from app.models import User\nfrom .services import (\n    send,\n)\nimport app.helpers\n",
+        )
+        .unwrap();
+
+        let found = find_imported_definition_files(&types(&["User", "send"]), &todo_file);
+        assert_eq!(found, BTreeSet::from([init, services]));
+    }
+
+    #[test]
+    fn test_find_definition_hits_locates_each_declaration() {
+        let dir = tempdir().unwrap();
+        let models = dir.path().join("Models.swift");
+        fs::write(
+            &models,
+            "import Foundation\n\nstruct Other {}\n\nstruct User {\n    let id: Int\n}\n",
+        )
+        .unwrap();
+        let helpers = dir.path().join("helpers.js");
+        fs::write(&helpers, "function format() {}\nclass User {}\n").unwrap();
+
+        let files = BTreeSet::from([models.clone(), helpers]);
+        let hits = find_definition_hits(&types(&["User", "format"]), &files);
+        assert_eq!(
+            hits,
+            vec![DefinitionHit {
+                path: models,
+                name: "User".to_string(),
+                lines: 4..7,
+            }],
+            "JavaScript has no type names to place its class by, so is left whole"
+        );
+    }
+
     #[test]
     fn test_find_definition_files_basic() {
         let dir = tempdir().unwrap();
@@ -338,8 +494,8 @@ mod tests {
         fs::write(root.join("Match.swift"), "class MyType {}\n").unwrap();
         fs::write(root.join("NoMatch.swift"), "let x = 1\n").unwrap();
 
-        let sources = walk_source_files(root);
-        let found = find_definition_files_from_sources(&types(&["MyType"]), &sources);
+        let index = SymbolIndex::new(walk_source_files(root));
+        let found = find_definition_files_from_sources(&types(&["MyType"]), &index);
 
         assert!(found.contains(&root.join("Match.swift")));
         assert!(!found.contains(&root.join("NoMatch.swift")));
@@ -349,9 +505,47 @@ mod tests {
     fn test_from_sources_returns_empty_for_empty_types() {
         let dir = tempdir().unwrap();
         fs::write(dir.path().join("A.swift"), "class A {}\n").unwrap();
-        let sources = walk_source_files(dir.path());
+        let index = SymbolIndex::new(walk_source_files(dir.path()));
+
+        let found = find_definition_files_from_sources(&BTreeSet::new(), &index);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_from_sources_finds_extension_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        fs::write(root.join("MyType.swift"), "class MyType {}\n").unwrap();
+        fs::write(
+            root.join("MyType+Networking.swift"),
+            "extension MyType {\n    func fetch() {}\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("Other.swift"), "extension Other {}\n").unwrap();
+
+        let index = SymbolIndex::new(walk_source_files(root));
+        let found = find_extension_files_from_sources(&types(&["MyType"]), &index);
+
+        assert_eq!(
+            found,
+            BTreeSet::from([root.join("MyType+Networking.swift")])
+        );
+    }
+
+    #[test]
+    fn test_from_sources_extension_excludes_defining_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+
+        fs::write(
+            root.join("MyType.swift"),
+            "class MyType {}\nextension MyType {}\n",
+        )
+        .unwrap();
 
-        let found = find_definition_files_from_sources(&BTreeSet::new(), &sources);
+        let index = SymbolIndex::new(walk_source_files(root));
+        let found = find_extension_files_from_sources(&types(&["MyType"]), &index);
         assert!(found.is_empty());
     }
 }
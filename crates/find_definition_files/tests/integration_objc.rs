@@ -1,6 +1,7 @@
 // crates/find_definition_files/tests/integration_objc.rs
 
-use find_definition_files::find_definition_files;
+use find_definition_files::{find_definition_files, find_extension_files_from_sources};
+use lang_support::{walk_source_files, SymbolIndex};
 use std::collections::BTreeSet;
 use std::fs;
 use std::path::PathBuf;
@@ -132,4 +133,32 @@ mod integration_objc {
         assert_eq!(result, expected);
         Ok(())
     }
+
+    #[test]
+    fn test_category_is_an_extension_not_a_definition_objc(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+
+        let header_path = dir.path().join("MyType.h");
+        fs::write(&header_path, "@interface MyType : NSObject @end")?;
+        let category_path = dir.path().join("MyType+Networking.h");
+        fs::write(&category_path, "@interface MyType (Networking)\n@end")?;
+        let category_impl_path = dir.path().join("MyType+Networking.m");
+        fs::write(
+            &category_impl_path,
+            "@implementation MyType (Networking)\n@end",
+        )?;
+
+        let types = types(&["MyType"]);
+        let definitions = find_definition_files(&types, dir.path())?;
+        assert_eq!(definitions, BTreeSet::from([header_path]));
+
+        let index = SymbolIndex::new(walk_source_files(dir.path()));
+        let extensions = find_extension_files_from_sources(&types, &index);
+        assert_eq!(
+            extensions,
+            BTreeSet::from([category_path, category_impl_path])
+        );
+        Ok(())
+    }
 }
@@ -1,113 +1,175 @@
 // crates/find_prompt_instruction/src/lib.rs
 
-use anyhow::{anyhow, Context, Result};
+mod markers;
+mod strategy;
+
+pub use markers::{
+    list_markers_in_dir, list_markers_in_dir_with_options, pick_marker, select_default_marker,
+    MarkerEntry, MarkerPick,
+};
+pub use strategy::DiscoveryStrategy;
+
+use anyhow::{anyhow, Result};
+use extract_instruction_content::InstructionContent;
+use index_cache::IndexCache;
+use lang_support::{walk_source_files_with_options, SourceFile, WalkOptions};
 use log::debug;
-use std::fs;
-use std::io::{self, BufRead};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
-use todo_marker::TODO_MARKER_WS;
-use walkdir::WalkDir;
+use todo_marker::MarkerGrammar;
 
 /// Searches the given directory (and its subdirectories) for files with allowed extensions
 /// that contain the TODO marker. If multiple files are found, returns the one with the most
 /// recent modification time. Diagnostic details are emitted via `log::debug!`.
 ///
 /// Allowed extensions come from `lang_support::supported_extensions()`.
-/// The marker searched for is `todo_marker::TODO_MARKER_WS`.
+/// Each file is searched for the marker in its own comment syntax
+/// (`// TODO: - ` in Swift, `# TODO: - ` in Python).
 pub fn find_prompt_instruction_in_dir(search_dir: &Path) -> Result<PathBuf> {
-    let finder = PromptInstructionFinder::new(search_dir);
+    find_prompt_instruction_in_dir_with_token(search_dir, None)
+}
+
+/// Like [`find_prompt_instruction_in_dir`], but looks for `marker_token`
+/// (e.g. `AI: -`) instead of the default `TODO: -` when one is given.
+pub fn find_prompt_instruction_in_dir_with_token(
+    search_dir: &Path,
+    marker_token: Option<&str>,
+) -> Result<PathBuf> {
+    find_prompt_instruction_in_dir_with_options(
+        search_dir,
+        &DiscoveryOptions::with_token(marker_token),
+    )
+}
+
+/// Like [`find_prompt_instruction_in_dir`], searching as `options` say.
+pub fn find_prompt_instruction_in_dir_with_options(
+    search_dir: &Path,
+    options: &DiscoveryOptions,
+) -> Result<PathBuf> {
+    let finder = PromptInstructionFinder::new(search_dir, options);
     finder.find()
 }
 
+/// How instruction discovery searches a tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveryOptions {
+    /// Marker token to look for instead of `TODO: -`, e.g. `AI: -`.
+    pub marker_token: Option<String>,
+    /// Which files are searched; the same policy as definition and
+    /// reference search.
+    pub walk: WalkOptions,
+    /// Persistent cache of which lines of each file are markers; `None`
+    /// scans every file.
+    pub index: Option<IndexCache>,
+    /// Which files are read to find the default marker.  Listing markers
+    /// always reads every file.
+    pub strategy: DiscoveryStrategy,
+}
+
+impl DiscoveryOptions {
+    pub fn with_token(marker_token: Option<&str>) -> Self {
+        Self {
+            marker_token: marker_token.map(str::to_string),
+            ..Default::default()
+        }
+    }
+}
+
+/// One marker of a task group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskMarker {
+    pub path: PathBuf,
+    /// 1-based line of the marker.
+    pub line: usize,
+    pub content: InstructionContent,
+}
+
+/// Collects every marker tagged `#tag` under `search_dir`, ordered by path
+/// and line, so a group spread over several files becomes one prompt.
+pub fn find_task_group_in_dir(
+    search_dir: &Path,
+    tag: &str,
+    options: &DiscoveryOptions,
+) -> Result<Vec<TaskMarker>> {
+    let mut tasks: Vec<TaskMarker> = list_markers_in_dir_with_options(search_dir, options)
+        .into_iter()
+        .filter(|marker| marker.content.group.as_deref() == Some(tag))
+        .map(|marker| TaskMarker {
+            path: marker.path,
+            line: marker.line,
+            content: marker.content,
+        })
+        .collect();
+
+    if tasks.is_empty() {
+        return Err(anyhow!("No markers found for task group #{}", tag));
+    }
+    tasks.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    Ok(tasks)
+}
+
 // === Private Implementation === //
 
 struct PromptInstructionFinder<'a> {
     search_dir: &'a Path,
-    allowed_extensions: &'static [&'static str],
-    todo_marker: &'static str,
+    options: &'a DiscoveryOptions,
 }
 
 impl<'a> PromptInstructionFinder<'a> {
-    fn new(search_dir: &'a Path) -> Self {
+    fn new(search_dir: &'a Path, options: &'a DiscoveryOptions) -> Self {
         Self {
             search_dir,
-            allowed_extensions: lang_support::supported_extensions(),
-            todo_marker: TODO_MARKER_WS,
+            options,
         }
     }
 
-    fn find(&self) -> Result<PathBuf> {
-        let matching_files: Vec<PathBuf> = WalkDir::new(self.search_dir)
-            .into_iter()
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| entry.file_type().is_file())
-            .map(|entry| entry.into_path())
-            .filter(|path| {
-                path.extension()
-                    .and_then(|s| s.to_str())
-                    .map(|ext| self.allowed_extensions.contains(&ext))
-                    .unwrap_or(false)
-            })
-            .filter(|path| {
-                if let Ok(file) = fs::File::open(path) {
-                    let reader = io::BufReader::new(file);
-                    reader
-                        .lines()
-                        .map_while(Result::ok)
-                        .any(|line| line.contains(self.todo_marker))
-                } else {
-                    false
-                }
-            })
-            .collect();
+    fn marker_token(&self) -> Option<&str> {
+        self.options.marker_token.as_deref()
+    }
 
-        if matching_files.is_empty() {
-            return Err(anyhow!("No files found containing '{}'", self.todo_marker));
-        }
+    fn grammar_for(&self, path: &Path) -> MarkerGrammar {
+        lang_support::marker_grammar(path, self.marker_token())
+    }
 
-        let chosen_file = matching_files
-            .iter()
-            .max_by(|a, b| {
-                let mod_a = fs::metadata(a)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-                let mod_b = fs::metadata(b)
-                    .and_then(|m| m.modified())
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-                mod_a.cmp(&mod_b)
-            })
-            .cloned()
-            .ok_or_else(|| anyhow!("No files found containing '{}'", self.todo_marker))?;
-
-        let content = fs::read_to_string(&chosen_file)
-            .with_context(|| format!("Failed to read {}", chosen_file.display()))?;
-        let marker_lines: Vec<String> = content
-            .lines()
-            .filter(|line| line.contains(self.todo_marker))
-            .map(|line| line.trim().to_string())
-            .collect();
-        let marker_count = marker_lines.len();
-        if marker_count > 1 {
+    /// The marker as written in C-like files, for error messages.
+    fn display_marker(&self) -> String {
+        let grammar = match self.marker_token() {
+            Some(token) => MarkerGrammar::default().with_token(token),
+            None => MarkerGrammar::default(),
+        };
+        format!("{} ", grammar.marker())
+    }
+
+    /// Source files in supported languages under the search directory.
+    fn candidate_files(&self) -> Vec<SourceFile> {
+        walk_source_files_with_options(self.search_dir, &self.options.walk)
+    }
+
+    fn find(&self) -> Result<PathBuf> {
+        let markers = self.default_candidates();
+        if markers.is_empty() {
             return Err(anyhow!(
-                "Ambiguous TODO marker: file {} contains {} markers:\n{}",
-                chosen_file.display(),
-                marker_count,
-                marker_lines.join("\n")
+                "No files found containing '{}'",
+                self.display_marker()
             ));
         }
+        let chosen_file = select_default_marker(&markers)?.path.clone();
 
+        let mut matching_files: Vec<&MarkerEntry> = Vec::new();
+        for marker in &markers {
+            if !matching_files.iter().any(|m| m.path == marker.path) {
+                matching_files.push(marker);
+            }
+        }
         debug!("{} matching file(s) found.", matching_files.len());
         if matching_files.len() > 1 {
             debug!("Ignoring the following files:");
-            for file in matching_files.iter().filter(|&f| f != &chosen_file) {
-                let basename = file
+            for marker in matching_files.iter().filter(|m| m.path != chosen_file) {
+                let basename = marker
+                    .path
                     .file_name()
                     .and_then(|s| s.to_str())
                     .unwrap_or("<unknown>");
-                let todo_line = extract_first_todo_line(file, self.todo_marker)
-                    .unwrap_or_else(|| "<no TODO line found>".to_string());
-                debug!("  - {}: {}", basename, todo_line.trim());
+                debug!("  - {}: {}", basename, marker.content.raw.trim());
                 debug!("--------------------------------------------------");
             }
             debug!("Chosen file: {}", chosen_file.display());
@@ -119,19 +181,6 @@ impl<'a> PromptInstructionFinder<'a> {
     }
 }
 
-/// Private helper: extracts the first line in the file that contains the given marker.
-fn extract_first_todo_line(path: &Path, marker: &str) -> Option<String> {
-    if let Ok(file) = fs::File::open(path) {
-        let reader = io::BufReader::new(file);
-        for line in reader.lines().map_while(Result::ok) {
-            if line.contains(marker) {
-                return Some(line);
-            }
-        }
-    }
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,15 +196,151 @@ mod tests {
 
         let result = find_prompt_instruction_in_dir(dir.path());
         let err = result.expect_err("expected missing TODO marker to return an error");
-        assert!(err.to_string().contains(TODO_MARKER_WS));
+        assert!(err.to_string().contains(todo_marker::TODO_MARKER_WS));
     }
 
     #[test]
     fn test_finder_uses_shared_todo_marker() {
         let dir = tempdir().unwrap();
-        let finder = PromptInstructionFinder::new(dir.path());
+        let options = DiscoveryOptions::default();
+        let finder = PromptInstructionFinder::new(dir.path(), &options);
+
+        assert_eq!(finder.display_marker(), todo_marker::TODO_MARKER_WS);
+    }
+
+    #[test]
+    fn test_python_files_use_hash_markers() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("c_style.py"),
+            "x = 1  // TODO: - not python
+",
+        )
+        .unwrap();
+        let file_path = dir.path().join("views.py");
+        fs::write(
+            &file_path,
+            "def load():
+    # TODO: - cache this
+",
+        )
+        .unwrap();
+
+        let result = find_prompt_instruction_in_dir(dir.path()).unwrap();
+        assert_eq!(result, file_path);
+    }
+
+    #[test]
+    fn test_marker_verbs_are_instructions() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("Feed.swift");
+        fs::write(&file_path, "// REVIEW: - is this thread safe?\n").unwrap();
+
+        let result = find_prompt_instruction_in_dir(dir.path()).unwrap();
+        assert_eq!(result, file_path);
 
-        assert_eq!(finder.todo_marker, todo_marker::TODO_MARKER_WS);
+        fs::write(&file_path, "// REVIEW: - one\n// TODO: - two\n").unwrap();
+        let err = find_prompt_instruction_in_dir(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("Ambiguous TODO marker"));
+    }
+
+    #[test]
+    fn test_markers_of_one_group_are_not_ambiguous() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("Login.swift");
+        fs::write(
+            &file_path,
+            "// TODO: - #auth make login async
+// TODO: - #auth drop the retry loop
+",
+        )
+        .unwrap();
+        assert_eq!(
+            find_prompt_instruction_in_dir(dir.path()).unwrap(),
+            file_path
+        );
+
+        fs::write(
+            &file_path,
+            "// TODO: - #auth make login async
+// TODO: - #ui restyle the button
+",
+        )
+        .unwrap();
+        let err = find_prompt_instruction_in_dir(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("Ambiguous TODO marker"));
+    }
+
+    #[test]
+    fn test_find_task_group_collects_markers_across_files() {
+        let dir = tempdir().unwrap();
+        let login = dir.path().join("Login.swift");
+        let session = dir.path().join("Session.py");
+        fs::write(
+            &login,
+            "func login() {
+    // TODO: - #auth make login async
+}
+// TODO: - #ui unrelated
+",
+        )
+        .unwrap();
+        fs::write(
+            &session,
+            "def refresh():
+    # FIXME: - #auth expire old tokens
+",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("Other.swift"),
+            "// TODO: - #authz not this one
+",
+        )
+        .unwrap();
+
+        let tasks =
+            find_task_group_in_dir(dir.path(), "auth", &DiscoveryOptions::default()).unwrap();
+
+        let found: Vec<(&Path, usize, &str)> = tasks
+            .iter()
+            .map(|t| (t.path.as_path(), t.line, t.content.task.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (login.as_path(), 2, "make login async"),
+                (session.as_path(), 2, "expire old tokens"),
+            ]
+        );
+        assert!(
+            find_task_group_in_dir(dir.path(), "missing", &DiscoveryOptions::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_custom_marker_token() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("Old.swift"),
+            "// TODO: - default marker
+",
+        )
+        .unwrap();
+        let file_path = dir.path().join("New.swift");
+        fs::write(
+            &file_path,
+            "// AI: - custom marker
+",
+        )
+        .unwrap();
+
+        let result = find_prompt_instruction_in_dir_with_token(dir.path(), Some("AI: -")).unwrap();
+        assert_eq!(result, file_path);
+
+        let err = find_prompt_instruction_in_dir_with_token(dir.path(), Some("FIX: -"))
+            .expect_err("no file uses the FIX marker");
+        assert!(err.to_string().contains("// FIX: - "));
     }
 
     #[test]
@@ -304,37 +489,7 @@ mod internal_tests {
     use super::*;
     use filetime::{set_file_mtime, FileTime};
     use std::fs::{self};
-    use std::io::Write;
-    use tempfile::{tempdir, NamedTempFile};
-
-    #[test]
-    fn test_extract_first_todo_line_found() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        writeln!(temp_file, "Line one").unwrap();
-        writeln!(temp_file, "// TODO: - This is the todo line").unwrap();
-        writeln!(temp_file, "Line three").unwrap();
-
-        let path = temp_file.path();
-        let result = extract_first_todo_line(path, "// TODO: - ");
-        assert!(result.is_some(), "Expected to find a TODO line");
-        let line = result.unwrap();
-        assert!(
-            line.contains("This is the todo line"),
-            "Unexpected TODO line: {}",
-            line
-        );
-    }
-
-    #[test]
-    fn test_extract_first_todo_line_not_found() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        writeln!(temp_file, "Line one").unwrap();
-        writeln!(temp_file, "Line two without marker").unwrap();
-
-        let path = temp_file.path();
-        let result = extract_first_todo_line(path, "// TODO: - ");
-        assert!(result.is_none(), "Did not expect a TODO line to be found");
-    }
+    use tempfile::tempdir;
 
     #[test]
     fn test_prompt_instruction_finder_with_multiple_files() {
@@ -354,7 +509,8 @@ mod internal_tests {
         set_file_mtime(&file1, ft1).unwrap();
         set_file_mtime(&file2, ft2).unwrap();
 
-        let finder = PromptInstructionFinder::new(dir.path());
+        let options = DiscoveryOptions::default();
+        let finder = PromptInstructionFinder::new(dir.path(), &options);
         let chosen_file = finder.find().expect("Expected to find a valid file");
         assert_eq!(
             chosen_file, file2,
@@ -372,7 +528,8 @@ mod internal_tests {
         )
         .unwrap();
 
-        let finder = PromptInstructionFinder::new(dir.path());
+        let options = DiscoveryOptions::default();
+        let finder = PromptInstructionFinder::new(dir.path(), &options);
         let result = finder.find();
         assert!(
             result.is_err(),
@@ -400,7 +557,8 @@ mod internal_tests {
         perms.set_mode(0o000);
         fs::set_permissions(&unreadable, perms).unwrap();
 
-        let finder = PromptInstructionFinder::new(dir.path());
+        let options = DiscoveryOptions::default();
+        let finder = PromptInstructionFinder::new(dir.path(), &options);
         let chosen_file = finder.find().expect("Expected to pick a valid file");
         assert_eq!(
             chosen_file, readable,
@@ -538,11 +696,19 @@ mod extension_characterization_tests {
     }
 
     #[test]
-    fn test_ts_extension_rejected() {
+    fn test_ts_extension_accepted() {
         let dir = tempdir().unwrap();
-        write_todo_file(dir.path(), "test.ts");
-        let result = find_prompt_instruction_in_dir(dir.path());
-        assert!(result.is_err());
+        let file = write_todo_file(dir.path(), "test.ts");
+        let result = find_prompt_instruction_in_dir(dir.path()).unwrap();
+        assert_eq!(result, file);
+    }
+
+    #[test]
+    fn test_tsx_extension_accepted() {
+        let dir = tempdir().unwrap();
+        let file = write_todo_file(dir.path(), "test.tsx");
+        let result = find_prompt_instruction_in_dir(dir.path()).unwrap();
+        assert_eq!(result, file);
     }
 
     #[test]
@@ -0,0 +1,345 @@
+// crates/find_prompt_instruction/src/markers.rs
+
+//! Every marker in a tree, and the ways of choosing one of them: the
+//! default pick (the most recently modified file) or an explicit
+//! `--pick <n|file:line>`.
+
+use anyhow::{anyhow, Result};
+use extract_instruction_content::{parse_instruction, InstructionContent};
+use lang_support::SourceFile;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::{DiscoveryOptions, PromptInstructionFinder};
+
+/// One marker found under the search directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerEntry {
+    pub path: PathBuf,
+    /// 1-based line of the marker.
+    pub line: usize,
+    pub modified: SystemTime,
+    /// The enclosing scope, e.g. `Outer.Inner.reload()`, where the language
+    /// models scopes.
+    pub scope: Option<String>,
+    pub content: InstructionContent,
+}
+
+/// Lists every marker under `search_dir`, newest file first, then by path
+/// and line.  The position in this list is the `n` of `--pick <n>`, counted
+/// from 1.
+pub fn list_markers_in_dir(search_dir: &Path, marker_token: Option<&str>) -> Vec<MarkerEntry> {
+    list_markers_in_dir_with_options(search_dir, &DiscoveryOptions::with_token(marker_token))
+}
+
+/// Like [`list_markers_in_dir`], searching as `options` say.
+pub fn list_markers_in_dir_with_options(
+    search_dir: &Path,
+    options: &DiscoveryOptions,
+) -> Vec<MarkerEntry> {
+    PromptInstructionFinder::new(search_dir, options).list()
+}
+
+/// A marker chosen on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerPick {
+    /// The `n`th entry of [`list_markers_in_dir`], counted from 1.
+    Index(usize),
+    /// The marker at `line` of the file whose path ends with `file`.
+    Location { file: String, line: usize },
+}
+
+impl FromStr for MarkerPick {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(index) = s.parse::<usize>() {
+            return match index {
+                0 => Err(anyhow!("Marker numbers start at 1")),
+                _ => Ok(MarkerPick::Index(index)),
+            };
+        }
+        let (file, line) = s
+            .rsplit_once(':')
+            .filter(|(file, _)| !file.is_empty())
+            .ok_or_else(|| anyhow!("Expected a marker number or file:line, got '{}'", s))?;
+        let line = line
+            .parse()
+            .map_err(|_| anyhow!("Invalid line number in '{}'", s))?;
+        Ok(MarkerPick::Location {
+            file: file.to_string(),
+            line,
+        })
+    }
+}
+
+/// Returns the marker `pick` refers to.
+pub fn pick_marker<'a>(markers: &'a [MarkerEntry], pick: &MarkerPick) -> Result<&'a MarkerEntry> {
+    match pick {
+        MarkerPick::Index(index) => markers.get(index - 1).ok_or_else(|| {
+            anyhow!(
+                "No marker #{}: only {} marker(s) found",
+                index,
+                markers.len()
+            )
+        }),
+        MarkerPick::Location { file, line } => {
+            let matches: Vec<&MarkerEntry> = markers
+                .iter()
+                .filter(|m| m.line == *line && m.path.ends_with(file))
+                .collect();
+            match matches.as_slice() {
+                [marker] => Ok(marker),
+                [] => Err(anyhow!("No marker found at {}:{}", file, line)),
+                _ => Err(anyhow!(
+                    "{}:{} matches {} files; give more of the path",
+                    file,
+                    line,
+                    matches.len()
+                )),
+            }
+        }
+    }
+}
+
+/// The default choice: the first marker of the most recently modified file.
+/// That file must hold a single marker, or only markers of one task group.
+pub fn select_default_marker(markers: &[MarkerEntry]) -> Result<&MarkerEntry> {
+    let newest = markers
+        .iter()
+        .max_by(|a, b| a.modified.cmp(&b.modified))
+        .ok_or_else(|| anyhow!("No markers found"))?;
+    let in_file: Vec<&MarkerEntry> = markers.iter().filter(|m| m.path == newest.path).collect();
+
+    // Several markers of one task group are a single multi-task prompt.
+    let group = &in_file[0].content.group;
+    let one_group = group.is_some() && in_file.iter().all(|m| m.content.group == *group);
+    if in_file.len() > 1 && !one_group {
+        let lines: Vec<&str> = in_file.iter().map(|m| m.content.raw.trim()).collect();
+        return Err(anyhow!(
+            "Ambiguous TODO marker: file {} contains {} markers:\n{}",
+            newest.path.display(),
+            in_file.len(),
+            lines.join("\n")
+        ));
+    }
+    Ok(in_file[0])
+}
+
+/// Newest file first, then by path and line: the order of
+/// [`list_markers_in_dir`].
+pub(crate) fn sort_markers(markers: &mut [MarkerEntry]) {
+    markers.sort_by(|a, b| {
+        b.modified
+            .cmp(&a.modified)
+            .then_with(|| a.path.cmp(&b.path))
+            .then(a.line.cmp(&b.line))
+    });
+}
+
+impl PromptInstructionFinder<'_> {
+    pub(crate) fn list(&self) -> Vec<MarkerEntry> {
+        let sources = self.candidate_files();
+        let lines: Vec<Vec<usize>> = match &self.options.index {
+            Some(index) => {
+                index.marker_lines(&sources, self.marker_token(), |s| self.marker_lines(s))
+            }
+            None => sources.par_iter().map(|s| self.marker_lines(s)).collect(),
+        };
+
+        let mut markers: Vec<MarkerEntry> = sources
+            .par_iter()
+            .zip(lines)
+            .filter(|(_, lines)| !lines.is_empty())
+            .flat_map_iter(|(source, lines)| self.markers_at(source, &lines))
+            .collect();
+
+        sort_markers(&mut markers);
+        markers
+    }
+
+    /// Every marker of `source`.
+    pub(crate) fn markers_in(&self, source: &SourceFile) -> Vec<MarkerEntry> {
+        self.markers_at(source, &self.marker_lines(source))
+    }
+
+    /// The 1-based lines of `source` that hold a marker.
+    fn marker_lines(&self, source: &SourceFile) -> Vec<usize> {
+        let grammar = self.grammar_for(&source.path);
+        source
+            .content()
+            .split_inclusive('\n')
+            .enumerate()
+            .filter(|(_, line)| grammar.find_instruction(line).is_some())
+            .map(|(index, _)| index + 1)
+            .collect()
+    }
+
+    /// The markers at `lines` of `source`, with their enclosing scopes.
+    fn markers_at(&self, source: &SourceFile, lines: &[usize]) -> Vec<MarkerEntry> {
+        let path = &source.path;
+        let content = source.content();
+        let modified = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let grammar = self.grammar_for(path);
+
+        let mut markers = Vec::new();
+        let mut offset = 0;
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            if lines.contains(&(index + 1)) && grammar.find_instruction(line).is_some() {
+                let scope = source
+                    .language
+                    .enclosing_scope(content, offset)
+                    .map(|scope| scope.to_string());
+                markers.push(MarkerEntry {
+                    path: path.clone(),
+                    line: index + 1,
+                    modified,
+                    scope,
+                    content: parse_instruction(line.trim_end(), &grammar),
+                });
+            }
+            offset += line.len();
+        }
+        markers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::{set_file_mtime, FileTime};
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, content: &str, mtime: i64) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        set_file_mtime(&path, FileTime::from_unix_time(mtime, 0)).unwrap();
+        path
+    }
+
+    #[test]
+    fn markers_are_listed_newest_first_with_scope() {
+        let dir = tempdir().unwrap();
+        let old = write(dir.path(), "Old.swift", "// TODO: - old\n", 1000);
+        let feed = write(
+            dir.path(),
+            "Feed.swift",
+            "struct Feed {\n    func reload() {\n        // TODO: - one\n    }\n}\n// TODO: not a marker\n// FIXME: - two\n",
+            2000,
+        );
+
+        let markers = list_markers_in_dir(dir.path(), None);
+
+        let summary: Vec<(&Path, usize, Option<&str>, &str)> = markers
+            .iter()
+            .map(|m| {
+                (
+                    m.path.as_path(),
+                    m.line,
+                    m.scope.as_deref(),
+                    m.content.instruction.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (feed.as_path(), 3, Some("Feed.reload()"), "// TODO: - one"),
+                (feed.as_path(), 7, None, "// FIXME: - two"),
+                (old.as_path(), 1, None, "// TODO: - old"),
+            ]
+        );
+    }
+
+    #[test]
+    fn markers_follow_the_walk_policy() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "Feed.swift", "// TODO: - keep\n", 1000);
+        write(dir.path(), "app.min.js", "// TODO: - minified\n", 2000);
+        let big = format!("// TODO: - big\n{}", "let x = 1\n".repeat(100));
+        write(dir.path(), "Big.swift", &big, 3000);
+
+        let options = DiscoveryOptions {
+            walk: lang_support::WalkOptions {
+                max_file_size: Some(500),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let markers = list_markers_in_dir_with_options(dir.path(), &options);
+        let tasks: Vec<&str> = markers.iter().map(|m| m.content.task.as_str()).collect();
+        assert_eq!(tasks, vec!["keep"]);
+    }
+
+    #[test]
+    fn markers_are_found_through_the_index_cache() {
+        let dir = tempdir().unwrap();
+        let cache = index_cache::IndexCache::new(dir.path().join(".index"));
+        let options = DiscoveryOptions {
+            index: Some(cache.clone()),
+            ..Default::default()
+        };
+        write(dir.path(), "Feed.swift", "// TODO: - one\n", 1000);
+        write(dir.path(), "Cache.swift", "struct Cache {}\n", 1000);
+        let tasks = |options: &DiscoveryOptions| -> Vec<String> {
+            list_markers_in_dir_with_options(dir.path(), options)
+                .into_iter()
+                .map(|m| m.content.task)
+                .collect()
+        };
+
+        assert_eq!(tasks(&options), vec!["one"]);
+        assert_eq!(cache.stats().markers[0].files_with_markers, 1);
+
+        write(dir.path(), "Cache.swift", "// TODO: - two\n", 2000);
+        assert_eq!(tasks(&options), vec!["two", "one"]);
+        assert_eq!(tasks(&options), tasks(&DiscoveryOptions::default()));
+    }
+
+    #[test]
+    fn picks_resolve_by_number_or_location() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a")).unwrap();
+        fs::create_dir_all(dir.path().join("b")).unwrap();
+        let a = write(dir.path(), "a/Feed.swift", "// TODO: - a\n", 2000);
+        let b = write(dir.path(), "b/Feed.swift", "\n// TODO: - b\n", 1000);
+        let markers = list_markers_in_dir(dir.path(), None);
+
+        let pick = |s: &str| pick_marker(&markers, &s.parse().unwrap()).map(|m| m.path.clone());
+        assert_eq!(pick("1").unwrap(), a);
+        assert_eq!(pick("2").unwrap(), b);
+        assert_eq!(pick("b/Feed.swift:2").unwrap(), b);
+        assert_eq!(pick("Feed.swift:1").unwrap(), a);
+        assert!(pick("3").is_err());
+        assert!(pick("Feed.swift:5").is_err());
+
+        assert!("0".parse::<MarkerPick>().is_err());
+        assert!("Feed.swift".parse::<MarkerPick>().is_err());
+        assert!("Feed.swift:x".parse::<MarkerPick>().is_err());
+    }
+
+    #[test]
+    fn default_selection_rejects_ambiguous_newest_file() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "Old.swift", "// TODO: - old\n", 1000);
+        let new = write(dir.path(), "New.swift", "// TODO: - new\n", 2000);
+        let markers = list_markers_in_dir(dir.path(), None);
+        assert_eq!(select_default_marker(&markers).unwrap().path, new);
+
+        write(
+            dir.path(),
+            "New.swift",
+            "// TODO: - a\n// TODO: - b\n",
+            2000,
+        );
+        let markers = list_markers_in_dir(dir.path(), None);
+        let err = select_default_marker(&markers).unwrap_err();
+        assert!(err.to_string().contains("Ambiguous TODO marker"));
+        assert!(select_default_marker(&[]).is_err());
+    }
+}
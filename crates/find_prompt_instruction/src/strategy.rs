@@ -0,0 +1,283 @@
+// crates/find_prompt_instruction/src/strategy.rs
+
+//! Ways of finding the default marker without reading the whole tree: the
+//! files `git status` reports first, then every file newest first, stopping
+//! once the newest file holding a marker is known.
+
+use anyhow::{anyhow, Context, Result};
+use lang_support::{walk_source_paths, SourceFile};
+use log::debug;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::markers::sort_markers;
+use crate::{MarkerEntry, PromptInstructionFinder};
+
+/// How the default marker is looked for.  Every strategy picks the first
+/// marker of the newest file among those it scans; they differ in which
+/// files those are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiscoveryStrategy {
+    /// `GitStatus`, then `Recent` when no dirty file holds a marker.
+    #[default]
+    Auto,
+    /// Only files `git status` reports as modified, added or untracked.
+    GitStatus,
+    /// Files newest first, stopping after the newest file with a marker;
+    /// without any marker this reads the whole tree.
+    Recent,
+    /// Every file, through the index cache when there is one.
+    Full,
+}
+
+impl FromStr for DiscoveryStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "git" => Ok(Self::GitStatus),
+            "recent" => Ok(Self::Recent),
+            "full" => Ok(Self::Full),
+            _ => Err(anyhow!(
+                "Unknown discovery strategy '{}': expected auto, git, recent or full",
+                s
+            )),
+        }
+    }
+}
+
+impl PromptInstructionFinder<'_> {
+    /// The markers the default choice is made from, as the strategy finds
+    /// them.
+    pub(crate) fn default_candidates(&self) -> Vec<MarkerEntry> {
+        match self.options.strategy {
+            DiscoveryStrategy::Full => self.list(),
+            DiscoveryStrategy::Recent => self.newest_markers(),
+            DiscoveryStrategy::GitStatus => self.dirty_markers(),
+            DiscoveryStrategy::Auto => {
+                let dirty = self.dirty_markers();
+                if !dirty.is_empty() {
+                    return dirty;
+                }
+                debug!("No marker in files changed in git; scanning newest files first");
+                self.newest_markers()
+            }
+        }
+    }
+
+    fn dirty_markers(&self) -> Vec<MarkerEntry> {
+        let paths = match dirty_files(self.search_dir) {
+            Ok(paths) => paths,
+            Err(err) => {
+                debug!("Skipping git status: {:#}", err);
+                return Vec::new();
+            }
+        };
+        debug!("{} file(s) changed in git", paths.len());
+
+        let walk = &self.options.walk;
+        let ignore = walk.ignore_matcher(self.search_dir);
+        let mut markers: Vec<MarkerEntry> = paths
+            .into_par_iter()
+            .filter(|path| !ignore.ignores(path))
+            .filter_map(|path| SourceFile::open(path, walk))
+            .flat_map_iter(|source| self.markers_in(&source))
+            .collect();
+        sort_markers(&mut markers);
+        markers
+    }
+
+    fn newest_markers(&self) -> Vec<MarkerEntry> {
+        let mut paths: Vec<(SystemTime, PathBuf)> =
+            walk_source_paths(self.search_dir, &self.options.walk)
+                .into_par_iter()
+                .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+                .collect();
+        paths.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+        // Files sharing the newest marker file's mtime are all read, so ties
+        // are broken exactly as a full scan breaks them.
+        let mut markers = Vec::new();
+        let mut newest = None;
+        for (modified, path) in paths {
+            if newest.is_some_and(|newest| newest != modified) {
+                break;
+            }
+            let Some(source) = SourceFile::open(path, &self.options.walk) else {
+                continue;
+            };
+            let found = self.markers_in(&source);
+            if !found.is_empty() {
+                newest = Some(modified);
+                markers.extend(found);
+            }
+        }
+        sort_markers(&mut markers);
+        markers
+    }
+}
+
+/// Modified, added, renamed and untracked files under `search_dir`, as git
+/// reports them; deleted files are left for [`SourceFile::open`] to drop.
+fn dirty_files(search_dir: &Path) -> Result<Vec<PathBuf>> {
+    // Porcelain paths are relative to the repository root, so strip the
+    // search directory's own place in the repository from them.
+    let prefix = git(search_dir, &["rev-parse", "--show-prefix"])?;
+    let prefix = prefix.trim_end_matches('\n');
+    let status = git(
+        search_dir,
+        &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            ".",
+        ],
+    )?;
+
+    let mut paths = Vec::new();
+    let mut fields = status.split('\0');
+    while let Some(entry) = fields.next() {
+        let Some((code, path)) = entry.split_at_checked(3) else {
+            continue;
+        };
+        // A rename or copy is followed by the path it came from.
+        if code.starts_with(['R', 'C']) {
+            fields.next();
+        }
+        if let Some(relative) = path.strip_prefix(prefix) {
+            paths.push(search_dir.join(relative));
+        }
+    }
+    Ok(paths)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to execute git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_prompt_instruction_in_dir_with_options, DiscoveryOptions};
+    use filetime::{set_file_mtime, FileTime};
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, name: &str, content: &str, mtime: i64) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        set_file_mtime(&path, FileTime::from_unix_time(mtime, 0)).unwrap();
+        path
+    }
+
+    fn find(dir: &Path, strategy: DiscoveryStrategy) -> Result<PathBuf> {
+        let options = DiscoveryOptions {
+            strategy,
+            ..Default::default()
+        };
+        find_prompt_instruction_in_dir_with_options(dir, &options)
+    }
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn strategies_parse_from_their_names() {
+        assert_eq!(
+            "git".parse::<DiscoveryStrategy>().unwrap(),
+            DiscoveryStrategy::GitStatus
+        );
+        assert_eq!(
+            "recent".parse::<DiscoveryStrategy>().unwrap(),
+            DiscoveryStrategy::Recent
+        );
+        assert!("fast".parse::<DiscoveryStrategy>().is_err());
+    }
+
+    #[test]
+    fn recent_scan_agrees_with_the_full_scan() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "Old.swift", "// TODO: - old\n", 1000);
+        write(dir.path(), "Plain.swift", "struct Plain {}\n", 3000);
+        let tie_a = write(dir.path(), "A.swift", "// TODO: - a\n", 2000);
+        let tie_b = write(dir.path(), "B.swift", "// TODO: - b\n", 2000);
+
+        let full = find(dir.path(), DiscoveryStrategy::Full).unwrap();
+        assert!(full == tie_a || full == tie_b);
+        assert_eq!(find(dir.path(), DiscoveryStrategy::Recent).unwrap(), full);
+
+        write(dir.path(), "B.swift", "// TODO: - b\n// TODO: - c\n", 2000);
+        let err = find(dir.path(), DiscoveryStrategy::Recent).unwrap_err();
+        assert!(err.to_string().contains("Ambiguous TODO marker"));
+    }
+
+    #[test]
+    fn dirty_files_come_first() {
+        let repo = tempdir().unwrap();
+        let root = repo.path();
+        run_git(root, &["init", "-q"]);
+        fs::create_dir_all(root.join("Sources")).unwrap();
+        write(
+            root,
+            "Sources/Committed.swift",
+            "// TODO: - committed\n",
+            3000,
+        );
+        let edited = write(root, "Sources/Edited.swift", "struct Edited {}\n", 1000);
+        run_git(root, &["add", "."]);
+        run_git(root, &["commit", "-q", "-m", "init"]);
+
+        write(root, "Sources/Edited.swift", "// TODO: - edited\n", 1000);
+        let untracked = write(root, "Sources/New.swift", "// TODO: - new\n", 2000);
+
+        assert_eq!(find(root, DiscoveryStrategy::GitStatus).unwrap(), untracked);
+        assert_eq!(find(root, DiscoveryStrategy::Auto).unwrap(), untracked);
+        assert_eq!(
+            find(root, DiscoveryStrategy::Full).unwrap(),
+            root.join("Sources/Committed.swift")
+        );
+
+        // Searching a subdirectory still maps git's paths onto it.
+        fs::remove_file(&untracked).unwrap();
+        let sources = root.join("Sources");
+        assert_eq!(
+            find(&sources, DiscoveryStrategy::GitStatus).unwrap(),
+            edited
+        );
+
+        // With nothing dirty holding a marker, Auto falls back to the
+        // newest file.
+        run_git(root, &["checkout", "-q", "--", "."]);
+        assert!(find(root, DiscoveryStrategy::GitStatus).is_err());
+        assert_eq!(
+            find(root, DiscoveryStrategy::Auto).unwrap(),
+            root.join("Sources/Committed.swift")
+        );
+    }
+}
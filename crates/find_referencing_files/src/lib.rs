@@ -1,26 +1,19 @@
 // crates/find_referencing_files/src/lib.rs
 
 use anyhow::Result;
-use lang_support::{walk_source_files, SourceFile};
+use lang_support::{walk_source_files, SymbolIndex};
 use regex::Regex;
 use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 
-/// Filters a pre-walked set of source files for those whose content contains
+/// Looks up the files of an indexed source collection whose content contains
 /// `type_name` as a whole word. Use when the caller has already materialised
 /// the source collection and wants to avoid a redundant filesystem walk.
 pub fn find_files_referencing_from_sources(
     type_name: &str,
-    sources: &[SourceFile],
+    index: &SymbolIndex,
 ) -> Result<BTreeSet<PathBuf>> {
-    let pattern = format!(r"\b{}\b", regex::escape(type_name));
-    let re = Regex::new(&pattern)?;
-
-    Ok(sources
-        .iter()
-        .filter(|sf| re.is_match(&sf.content))
-        .map(|sf| sf.path.clone())
-        .collect())
+    Ok(index.files_mentioning(type_name))
 }
 
 /// Searches the given directory (and its subdirectories) for files with allowed
@@ -55,7 +48,7 @@ pub fn find_files_referencing(type_name: &str, search_root: &Path) -> Result<Vec
     let mut matches = Vec::new();
 
     for source_file in walk_source_files(search_root) {
-        if re.is_match(&source_file.content) {
+        if re.is_match(source_file.content()) {
             matches.push(source_file.path);
         }
     }
@@ -371,8 +364,8 @@ mod from_sources_tests {
         let mut f2 = fs::File::create(&nomatch_path)?;
         writeln!(f2, "let y = 42")?;
 
-        let sources = walk_source_files(dir.path());
-        let results = find_files_referencing_from_sources("Widget", &sources)?;
+        let index = SymbolIndex::new(walk_source_files(dir.path()));
+        let results = find_files_referencing_from_sources("Widget", &index)?;
 
         assert!(results.contains(&match_path));
         assert!(!results.contains(&nomatch_path));
@@ -386,8 +379,8 @@ mod from_sources_tests {
         let mut f = fs::File::create(&path)?;
         writeln!(f, "let x = Gadget()")?;
 
-        let sources = walk_source_files(dir.path());
-        let results = find_files_referencing_from_sources("Gadget", &sources)?;
+        let index = SymbolIndex::new(walk_source_files(dir.path()));
+        let results = find_files_referencing_from_sources("Gadget", &index)?;
 
         assert_eq!(results.len(), 1);
         assert!(results.contains(&path));
@@ -3,9 +3,9 @@
 use anyhow::{anyhow, Context, Result};
 use std::io::Write;
 use std::process::{Command, Stdio};
-use unescape_newlines::unescape_newlines;
 
-/// Copies the provided prompt to the clipboard using the `pbcopy` command.
+/// Copies the provided prompt to the clipboard using the `pbcopy` command,
+/// byte-for-byte: escapes in the prompt belong to the code it quotes.
 ///
 /// This always performs the copy; whether copying should happen at all is decided by the
 /// caller at the binary edge (see `main`).
@@ -20,7 +20,7 @@ pub fn copy_to_clipboard(final_prompt: &str) -> Result<()> {
             .as_mut()
             .context("Failed to open pbcopy stdin")?;
         pb_stdin
-            .write_all(unescape_newlines(final_prompt).as_bytes())
+            .write_all(final_prompt.as_bytes())
             .context("Failed to write to pbcopy")?;
     }
 
@@ -78,9 +78,9 @@ mod tests {
         let new_path = format!("{}:{}", fake_dir_path.display(), original_path);
         env::set_var("PATH", &new_path);
 
-        // Call copy_to_clipboard with a prompt containing an escaped newline.
-        // The unescape_newlines function should convert "Test\\nPrompt" to "Test\nPrompt".
-        copy_to_clipboard("Test\\nPrompt").expect("clipboard copy should succeed");
+        // Call copy_to_clipboard with a prompt containing a real newline and
+        // a `\n` escape from quoted code; both must arrive unchanged.
+        copy_to_clipboard("let s = \"a\\nb\"\nPrompt").expect("clipboard copy should succeed");
 
         // Read the contents of the file where our fake pbcopy wrote the data.
         let mut output = String::new();
@@ -89,8 +89,8 @@ mod tests {
             .read_to_string(&mut output)
             .expect("failed to read fake pbcopy output file");
 
-        // Verify that the output contains an actual newline.
-        assert_eq!(output, "Test\nPrompt");
+        // Verify that the output is exactly what was passed in.
+        assert_eq!(output, "let s = \"a\\nb\"\nPrompt");
 
         // Restore the original PATH and clean up the FAKE_PBCOPY_OUTPUT variable.
         env::set_var("PATH", original_path);
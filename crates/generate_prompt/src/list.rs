@@ -0,0 +1,139 @@
+// crates/generate_prompt/src/list.rs
+
+use find_prompt_instruction::MarkerEntry;
+use serde_json::json;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Renders the markers for `generate_prompt list`, one per line, numbered
+/// as `--pick` expects, with paths relative to `root`.
+pub fn render_text(markers: &[MarkerEntry], root: &Path, now: SystemTime) -> String {
+    let mut out = String::new();
+    for (i, marker) in markers.iter().enumerate() {
+        let age = now.duration_since(marker.modified).unwrap_or_default();
+        out.push_str(&format!(
+            "{:>3}. {}:{}",
+            i + 1,
+            relative(&marker.path, root),
+            marker.line
+        ));
+        if let Some(scope) = &marker.scope {
+            out.push_str(&format!(" in {}", scope));
+        }
+        out.push_str(&format!(
+            " ({})\n     {}\n",
+            format_age(age),
+            marker.content.instruction
+        ));
+    }
+    out
+}
+
+/// Renders the markers as a JSON array for scripts and editor integrations.
+pub fn render_json(markers: &[MarkerEntry], root: &Path) -> String {
+    let entries: Vec<_> = markers
+        .iter()
+        .enumerate()
+        .map(|(i, marker)| {
+            let modified = marker
+                .modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            json!({
+                "index": i + 1,
+                "path": relative(&marker.path, root),
+                "line": marker.line,
+                "scope": marker.scope,
+                "modified": modified,
+                "verb": marker.content.verb.keyword(),
+                "group": marker.content.group,
+                "instruction": marker.content.instruction,
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn relative(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use extract_instruction_content::parse_instruction;
+    use std::path::PathBuf;
+    use todo_marker::MarkerGrammar;
+
+    fn marker(path: &str, line: usize, scope: Option<&str>, text: &str) -> MarkerEntry {
+        MarkerEntry {
+            path: PathBuf::from(path),
+            line,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1_000),
+            scope: scope.map(str::to_string),
+            content: parse_instruction(text, &MarkerGrammar::default()),
+        }
+    }
+
+    #[test]
+    fn text_lists_numbered_markers_relative_to_root() {
+        let markers = vec![
+            marker(
+                "/repo/Sources/Feed.swift",
+                12,
+                Some("Feed.reload()"),
+                "// TODO: - a",
+            ),
+            marker("/repo/main.js", 3, None, "// FIXME: - #auth b"),
+        ];
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000 + 7_200);
+
+        assert_eq!(
+            render_text(&markers, Path::new("/repo"), now),
+            "  1. Sources/Feed.swift:12 in Feed.reload() (2h ago)\n     // TODO: - a\n  2. main.js:3 (2h ago)\n     // FIXME: - #auth b\n"
+        );
+    }
+
+    #[test]
+    fn json_carries_every_field() {
+        let markers = vec![marker("/repo/main.js", 3, None, "// FIXME: - #auth b")];
+
+        let value: serde_json::Value =
+            serde_json::from_str(&render_json(&markers, Path::new("/repo"))).unwrap();
+
+        assert_eq!(
+            value,
+            json!([{
+                "index": 1,
+                "path": "main.js",
+                "line": 3,
+                "scope": null,
+                "modified": 1000,
+                "verb": "FIXME",
+                "group": "auth",
+                "instruction": "// FIXME: - #auth b",
+            }])
+        );
+    }
+
+    #[test]
+    fn ages_are_rounded_down_to_one_unit() {
+        assert_eq!(format_age(Duration::from_secs(5)), "just now");
+        assert_eq!(format_age(Duration::from_secs(150)), "2m ago");
+        assert_eq!(format_age(Duration::from_secs(90_000)), "1d ago");
+    }
+}
@@ -5,12 +5,19 @@ use clap::{Arg, Command};
 use std::env;
 use std::path::{Path, PathBuf};
 use std::process::{Command as ProcessCommand, Stdio};
+use std::time::SystemTime;
 
-use generate_prompt_core::instruction_locator;
+use find_prompt_instruction::{
+    list_markers_in_dir_with_options, DiscoveryOptions, DiscoveryStrategy, MarkerPick,
+};
 use generate_prompt_core::prompt_generator::{self, GeneratePromptOptions};
+use generate_prompt_core::{batch, index, instruction_locator};
 use get_git_root::get_git_root;
+use index_cache::IndexCache;
+use lang_support::WalkOptions;
 
 mod clipboard;
+mod list;
 
 fn init_logging(verbose: bool) {
     let mut builder = env_logger::Builder::from_default_env();
@@ -58,6 +65,105 @@ fn main() -> Result<()> {
                 .action(clap::ArgAction::Append)
                 .help("Exclude file(s) whose basename match the given name"),
         )
+        .arg(
+            Arg::new("max_extension_files")
+                .long("max-extension-files")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .help("Cap how many files extending the found types are included"),
+        )
+        .arg(
+            Arg::new("depth")
+                .long("depth")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .default_value("0")
+                .help("Also include the definitions of types the definition files use, this many hops out"),
+        )
+        .arg(
+            Arg::new("max_files_per_hop")
+                .long("max-files-per-hop")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .default_value("10")
+                .help("Cap how many definition files each --depth hop adds"),
+        )
+        .arg(
+            Arg::new("max_tokens")
+                .long("max-tokens")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .help("Leave out the least relevant files until the prompt is estimated to fit this many tokens"),
+        )
+        .arg(
+            Arg::new("skeleton")
+                .long("skeleton")
+                .help("Show files other than the TODO file as declarations and signatures, with bodies elided")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("skeleton_over")
+                .long("skeleton-over")
+                .num_args(1)
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with("skeleton")
+                .help("Show files other than the TODO file as skeletons when they are longer than this many lines"),
+        )
+        .arg(
+            Arg::new("focus")
+                .long("focus")
+                .help("Show only the part of the TODO file around the marker, collapsing the rest to placeholders")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("whole_files")
+                .long("whole-files")
+                .help("Show definition files in full rather than only the declarations the TODO file uses")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("marker_token")
+                .long("marker-token")
+                .num_args(1)
+                .global(true)
+                .help("Look for this marker token instead of \"TODO: -\" (e.g. \"AI: -\")"),
+        )
+        .arg(
+            Arg::new("max_file_size")
+                .long("max-file-size")
+                .num_args(1)
+                .global(true)
+                .value_parser(clap::value_parser!(u64))
+                .help("Skip source files larger than this many bytes (0 for no limit; default 1 MiB)"),
+        )
+        .arg(
+            Arg::new("no_ignore")
+                .long("no-ignore")
+                .global(true)
+                .help("Also search files excluded by .gitignore or .promptignore, and generated files")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no_index")
+                .long("no-index")
+                .global(true)
+                .help("Ignore the index under .git/auto-prompt even if it exists")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pick")
+                .long("pick")
+                .num_args(1)
+                .value_parser(|s: &str| s.parse::<MarkerPick>())
+                .help("Use this marker instead of the most recent one: its number in `list`, or file:line"),
+        )
+        .arg(
+            Arg::new("discovery")
+                .long("discovery")
+                .num_args(1)
+                .value_parser(|s: &str| s.parse::<DiscoveryStrategy>())
+                .help("How the marker is found: auto (changed files first, then newest first), git, recent or full"),
+        )
         .arg(
             Arg::new("verbose")
                 .long("verbose")
@@ -72,6 +178,43 @@ fn main() -> Result<()> {
                 .action(clap::ArgAction::SetTrue)
                 .default_value("false"),
         )
+        .subcommand(
+            Command::new("list")
+                .about("List every marker with its file, line, enclosing scope and age")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the markers as JSON")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write a prompt for every marker, plus an index, to a directory")
+                .arg(
+                    Arg::new("out_dir")
+                        .long("out-dir")
+                        .num_args(1)
+                        .default_value("prompts")
+                        .help("Directory to write the prompts to"),
+                ),
+        )
+        .subcommand(
+            Command::new("index")
+                .about("Manage the index of declarations, words and markers under .git/auto-prompt")
+                .arg(
+                    Arg::new("rebuild")
+                        .long("rebuild")
+                        .help("Create the index, or drop and re-create it")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("stats")
+                        .long("stats")
+                        .help("Show how many files the index holds and how many are stale")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
         .get_matches();
 
     let singular = *matches.get_one::<bool>("singular").unwrap();
@@ -84,20 +227,94 @@ fn main() -> Result<()> {
         .collect();
     let diff_branch = matches.get_one::<String>("diff_with").cloned();
     let targeted = *matches.get_one::<bool>("tgtd").unwrap();
+    let max_extension_files = matches.get_one::<usize>("max_extension_files").copied();
+    let depth = *matches.get_one::<usize>("depth").unwrap();
+    let max_files_per_hop = matches.get_one::<usize>("max_files_per_hop").copied();
+    let max_tokens = matches.get_one::<usize>("max_tokens").copied();
+    let skeleton_over_lines = if *matches.get_one::<bool>("skeleton").unwrap() {
+        Some(0)
+    } else {
+        matches.get_one::<usize>("skeleton_over").copied()
+    };
+    let whole_files = *matches.get_one::<bool>("whole_files").unwrap();
+    let focus = *matches.get_one::<bool>("focus").unwrap();
+    let marker_token = matches.get_one::<String>("marker_token").cloned();
+    let no_ignore = *matches.get_one::<bool>("no_ignore").unwrap();
+    let walk = WalkOptions {
+        max_file_size: match matches.get_one::<u64>("max_file_size") {
+            Some(0) => None,
+            Some(&max) => Some(max),
+            None => Some(WalkOptions::DEFAULT_MAX_FILE_SIZE),
+        },
+        use_ignore_files: !no_ignore,
+        skip_generated: !no_ignore,
+        ..Default::default()
+    };
+    let no_index = *matches.get_one::<bool>("no_index").unwrap();
+    let strategy = matches
+        .get_one::<DiscoveryStrategy>("discovery")
+        .copied()
+        .unwrap_or_default();
+    let pick = matches.get_one::<MarkerPick>("pick").cloned();
     let verbose = *matches.get_one::<bool>("verbose").unwrap();
 
     init_logging(verbose);
 
-    let current_dir = env::current_dir().context("Failed to get current directory")?;
-    println!("--------------------------------------------------");
-    println!("Current directory: {}", current_dir.display());
-
     // Test seam: GET_GIT_ROOT overrides git-root discovery for integration tests.
     let git_root = if let Ok(git_root_override) = env::var("GET_GIT_ROOT") {
         git_root_override
     } else {
         get_git_root().context("Failed to determine Git root")?
     };
+
+    // The index is opt-in: it is used once `index --rebuild` has created it.
+    let index_cache = IndexCache::in_git_dir(Path::new(&git_root));
+    let index = Some(index_cache.clone()).filter(|cache| !no_index && cache.exists());
+    let discovery = DiscoveryOptions {
+        marker_token: marker_token.clone(),
+        walk: walk.clone(),
+        index: index.clone(),
+        strategy,
+    };
+
+    if let Some(index_matches) = matches.subcommand_matches("index") {
+        if *index_matches.get_one::<bool>("rebuild").unwrap() {
+            let options = GeneratePromptOptions {
+                marker_token,
+                walk,
+                ..Default::default()
+            };
+            print!(
+                "{}",
+                index::rebuild_index(&index_cache, Path::new(&git_root), &options)?
+            );
+        } else if index_cache.exists() {
+            print!("{}", index_cache.stats());
+        } else {
+            println!(
+                "No index at {}; create one with `generate_prompt index --rebuild`",
+                index_cache.dir().display()
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(list_matches) = matches.subcommand_matches("list") {
+        let root = Path::new(&git_root);
+        let markers = list_markers_in_dir_with_options(root, &discovery);
+        if *list_matches.get_one::<bool>("json").unwrap() {
+            println!("{}", list::render_json(&markers, root));
+        } else if markers.is_empty() {
+            println!("No markers found under {}", git_root);
+        } else {
+            print!("{}", list::render_text(&markers, root, SystemTime::now()));
+        }
+        return Ok(());
+    }
+
+    let current_dir = env::current_dir().context("Failed to get current directory")?;
+    println!("--------------------------------------------------");
+    println!("Current directory: {}", current_dir.display());
     println!("Git root: {}", git_root);
     println!("--------------------------------------------------");
 
@@ -113,14 +330,64 @@ fn main() -> Result<()> {
         }
     }
 
+    if let Some(export_matches) = matches.subcommand_matches("export") {
+        let out_dir = PathBuf::from(export_matches.get_one::<String>("out_dir").unwrap());
+        let options = GeneratePromptOptions {
+            singular,
+            force_global,
+            include_references,
+            excludes,
+            diff_branch,
+            targeted,
+            max_extension_files,
+            depth,
+            max_files_per_hop,
+            marker_token,
+            walk,
+            index,
+            max_tokens,
+            skeleton_over_lines,
+            whole_files,
+            focus,
+            ..Default::default()
+        };
+        let report = batch::export_prompts(&git_root, &out_dir, &options)?;
+        let failed = report.failures().count();
+        println!(
+            "Wrote {} prompt(s) to {}",
+            report.entries.len() - failed,
+            out_dir.display()
+        );
+        println!("Index: {}", report.index_path.display());
+        if failed > 0 {
+            println!("--------------------------------------------------");
+            println!("{} marker(s) failed:", failed);
+            for entry in report.failures() {
+                if let Err(err) = &entry.result {
+                    println!("{}:{}: {}", entry.relative_path, entry.marker.line, err);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let mut todo_line = None;
     // Test seam: GET_INSTRUCTION_FILE overrides instruction-file discovery for integration tests.
-    let file_path = if let Ok(instruction_override) = env::var("GET_INSTRUCTION_FILE") {
+    let file_path = if let Some(pick) = &pick {
+        let marker =
+            instruction_locator::locate_picked_marker(Path::new(&git_root), pick, &discovery)?;
+        todo_line = Some(marker.line);
+        marker.path
+    } else if let Ok(instruction_override) = env::var("GET_INSTRUCTION_FILE") {
         PathBuf::from(instruction_override)
     } else {
-        instruction_locator::locate_instruction_file(Path::new(&git_root))
+        instruction_locator::locate_instruction_file_with_options(Path::new(&git_root), &discovery)
             .context("Failed to locate the instruction file")?
     };
-    println!("Found exactly one instruction in {}", file_path.display());
+    match todo_line {
+        Some(line) => println!("Picked the instruction at {}:{}", file_path.display(), line),
+        None => println!("Found exactly one instruction in {}", file_path.display()),
+    }
     println!("--------------------------------------------------");
 
     if force_global {
@@ -140,12 +407,32 @@ fn main() -> Result<()> {
             excludes,
             diff_branch,
             targeted,
+            max_extension_files,
+            depth,
+            max_files_per_hop,
+            marker_token,
+            todo_line,
+            walk,
+            index,
+            max_tokens,
+            skeleton_over_lines,
+            whole_files,
+            focus,
+            ..Default::default()
         },
     )?;
 
     println!("Search root: {}", output.search_root.display());
     println!("Instruction content: {}", output.instruction_content);
     println!("--------------------------------------------------");
+    if !output.tasks.is_empty() {
+        println!("Tasks:");
+        for task in &output.tasks {
+            let basename = task.path.file_name().unwrap_or_default().to_string_lossy();
+            println!("{}:{}: {}", basename, task.line, task.content.instruction);
+        }
+        println!("--------------------------------------------------");
+    }
     if !output.types_found.is_empty() {
         println!("Types found:");
         for ty in &output.types_found {
@@ -159,6 +446,10 @@ fn main() -> Result<()> {
         println!("{}", basename);
     }
     println!("--------------------------------------------------");
+    if max_tokens.is_some() {
+        print!("{}", output.token_report);
+        println!("--------------------------------------------------");
+    }
     println!("Success:\n");
     println!("{}", output.instruction_content);
     println!("--------------------------------------------------\n");
@@ -68,7 +68,7 @@ fn test_generate_prompt_include_references_error_for_non_swift() {
         .env("DISABLE_PBCOPY", "1");
 
     cmd.assert().failure().stderr(predicate::str::contains(
-        "--include-references is only supported for Swift files",
+        "--include-references is only supported for Swift, TypeScript, Kotlin, Java and Python files",
     ));
 }
 
@@ -551,3 +551,397 @@ The contents of Definition.swift is as follows:\n\nclass DummyType {}\n\n-------
         clipboard_content
     );
 }
+
+/// --- Test: Extension File Cap ---
+/// Files extending a found type are included, and `--max-extension-files`
+/// limits how many of them are pulled in.
+#[test]
+#[cfg(unix)]
+fn test_generate_prompt_max_extension_files_caps_extensions() {
+    let fake_git_root = TempDir::new().unwrap();
+    let fake_git_root_path = fake_git_root.path().to_str().unwrap();
+
+    let todo_file = format!("{}/TODO.swift", fake_git_root_path);
+    fs::write(&todo_file, "let feed = Feed()\n   // TODO: - Paginate Feed").unwrap();
+    fs::write(fake_git_root.path().join("Feed.swift"), "class Feed {}").unwrap();
+    fs::write(
+        fake_git_root.path().join("Feed+Caching.swift"),
+        "extension Feed {}",
+    )
+    .unwrap();
+    fs::write(
+        fake_git_root.path().join("Feed+Networking.swift"),
+        "extension Feed {}",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("generate_prompt").unwrap();
+    cmd.args(["--max-extension-files", "1"])
+        .env("GET_GIT_ROOT", fake_git_root_path)
+        .env("GET_INSTRUCTION_FILE", &todo_file)
+        .env("DISABLE_PBCOPY", "1");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Feed.swift"))
+        .stdout(predicate::str::contains("Feed+Caching.swift"))
+        .stdout(predicate::str::contains("Feed+Networking.swift").not());
+}
+
+/// --- Test: Custom Marker Token ---
+/// `--marker-token` changes which marker both discovery and extraction look for.
+#[test]
+#[cfg(unix)]
+fn test_generate_prompt_marker_token() {
+    let fake_git_root = TempDir::new().unwrap();
+    let fake_git_root_path = fake_git_root.path().to_str().unwrap();
+    fs::write(
+        fake_git_root.path().join("Notes.swift"),
+        "// TODO: - Not this one",
+    )
+    .unwrap();
+    fs::write(
+        fake_git_root.path().join("tasks.py"),
+        "def run():\n    # AI: - Retry failed tasks\n    pass\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("generate_prompt").unwrap();
+    cmd.args(["--singular", "--marker-token", "AI: -"])
+        .env("GET_GIT_ROOT", fake_git_root_path)
+        .env_remove("GET_INSTRUCTION_FILE")
+        .env("DISABLE_PBCOPY", "1");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("tasks.py"))
+        .stdout(predicate::str::contains("# AI: - Retry failed tasks"));
+}
+
+/// --- Test: list subcommand ---
+/// `list` prints every marker, numbered, and `--json` makes it machine readable.
+#[test]
+fn test_list_prints_every_marker() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    fs::write(
+        root.join("Feed.swift"),
+        "struct Feed {\n    func load() {\n        // TODO: - one\n    }\n}\n// FIXME: - two\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .arg("list")
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  1. Feed.swift:3 in Feed.load() (",
+        ))
+        .stdout(predicate::str::contains("  2. Feed.swift:6 ("))
+        .stdout(predicate::str::contains("Current directory").not());
+
+    let output = Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["list", "--json"])
+        .env("GET_GIT_ROOT", root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.trim_start().starts_with('['), "{stdout}");
+    assert!(stdout.contains("\"instruction\": \"// FIXME: - two\""));
+    assert!(stdout.contains("\"scope\": \"Feed.load()\""));
+}
+
+/// --- Test: --pick ---
+/// Picking a marker by file:line targets it even when its file holds
+/// several markers, which is otherwise ambiguous.
+#[test]
+#[cfg(unix)]
+fn test_pick_targets_a_specific_marker() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    fs::write(root.join("Feed.swift"), "// TODO: - one\n// FIXME: - two\n").unwrap();
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .arg("--singular")
+        .env("GET_GIT_ROOT", root)
+        .env("DISABLE_PBCOPY", "1")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Ambiguous TODO marker"));
+
+    for pick in ["Feed.swift:2", "2"] {
+        Command::cargo_bin("generate_prompt")
+            .unwrap()
+            .args(["--singular", "--pick", pick])
+            .env("GET_GIT_ROOT", root)
+            .env("DISABLE_PBCOPY", "1")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Picked the instruction at"))
+            .stdout(predicate::str::contains(
+                "Instruction content: // FIXME: - two",
+            ));
+    }
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["--pick", "Feed.swift:x"])
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid line number"));
+}
+
+/// --- Test: export subcommand ---
+/// `export` writes one prompt per marker and an index, and reports failures.
+#[test]
+fn test_export_writes_prompts_and_index() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    fs::write(root.join("Feed.swift"), "// TODO: - one\n").unwrap();
+    fs::write(
+        root.join("main.js"),
+        "// FIXME: - two, not // TODO: - this\n",
+    )
+    .unwrap();
+    let out_dir = TempDir::new().unwrap();
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["--singular", "export", "--out-dir"])
+        .arg(out_dir.path())
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote 1 prompt(s) to"))
+        .stdout(predicate::str::contains("1 marker(s) failed:"))
+        .stdout(predicate::str::contains("main.js:1: "));
+
+    let prompt = fs::read_to_string(out_dir.path().join("Feed.swift-1.md")).unwrap();
+    assert!(prompt.contains("// TODO: - one"));
+    let index = fs::read_to_string(out_dir.path().join("index.md")).unwrap();
+    assert!(index.contains("[Feed.swift-1.md](Feed.swift-1.md)"));
+    assert!(index.contains("## Failed"));
+}
+
+/// --- Test: index subcommand ---
+/// `index --rebuild` creates the index under .git, which later runs then use.
+#[test]
+fn test_index_rebuild_and_stats() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join("Feed.swift"), "// TODO: - one\n").unwrap();
+    fs::write(root.join("Cache.swift"), "struct Cache {}\n").unwrap();
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["index", "--stats"])
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No index at"));
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["index", "--rebuild"])
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("symbols.json: 2 file(s), 0 stale"))
+        .stdout(predicate::str::contains(
+            "markers.json: 2 file(s), 1 with markers, 0 stale",
+        ));
+
+    fs::write(root.join("Cache.swift"), "// TODO: - two\n").unwrap();
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .arg("list")
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("// TODO: - two"));
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["index", "--stats"])
+        .env("GET_GIT_ROOT", root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "markers.json: 2 file(s), 2 with markers, 0 stale",
+        ));
+}
+
+/// --- Test: token budget ---
+/// `--max-tokens` leaves out the least relevant files and prints the table.
+#[test]
+#[cfg(unix)]
+fn test_max_tokens_drops_files_and_prints_the_token_table() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    let todo_file = root.join("Feed.swift");
+    fs::write(
+        &todo_file,
+        "struct Feed {\n    let cache: Cache\n    // TODO: - paginate\n}\n",
+    )
+    .unwrap();
+    let cache_body = "    func evict() {}\n".repeat(200);
+    fs::write(
+        root.join("Cache.swift"),
+        format!("struct Cache {{\n{}}}\n", cache_body),
+    )
+    .unwrap();
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["--max-tokens", "300"])
+        .env("GET_GIT_ROOT", root)
+        .env("GET_INSTRUCTION_FILE", &todo_file)
+        .env("DISABLE_PBCOPY", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("TODO file           Feed.swift"))
+        .stdout(predicate::str::contains(
+            "definition          Cache.swift (dropped)",
+        ))
+        .stdout(predicate::str::contains("of 300 tokens"));
+}
+
+/// --- Test: skeletons ---
+/// `--skeleton` elides the bodies of every file but the TODO file.
+#[test]
+#[cfg(unix)]
+fn test_skeleton_elides_bodies_of_context_files() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    let todo_file = root.join("Feed.swift");
+    fs::write(
+        &todo_file,
+        "struct Feed {\n    let cache: Cache\n    // TODO: - paginate\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("Cache.swift"),
+        "struct Cache {\n    func evict() {\n        items.removeAll()\n    }\n}\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("generate_prompt")
+        .unwrap()
+        .args(["--skeleton", "--max-tokens", "100000"])
+        .env("GET_GIT_ROOT", root)
+        .env("GET_INSTRUCTION_FILE", &todo_file)
+        .env("DISABLE_PBCOPY", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cache.swift (skeleton)"));
+}
+
+#[test]
+fn test_definition_files_are_sliced_unless_whole_files() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    let todo_file = root.join("Profile.swift");
+    fs::write(
+        &todo_file,
+        "struct Profile {\n    let user: User\n    // TODO: - show the name\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("Models.swift"),
+        "struct Invoice {\n    let total: Int\n}\n\nstruct User {\n    let name: String\n}\n",
+    )
+    .unwrap();
+
+    let run = |extra: &[&str]| {
+        Command::cargo_bin("generate_prompt")
+            .unwrap()
+            .args(["--max-tokens", "100000"])
+            .args(extra)
+            .env("GET_GIT_ROOT", root)
+            .env("GET_INSTRUCTION_FILE", &todo_file)
+            .env("DISABLE_PBCOPY", "1")
+            .assert()
+            .success()
+    };
+    run(&[]).stdout(predicate::str::contains(
+        "Models.swift (matched declarations)",
+    ));
+    run(&["--whole-files"]).stdout(predicate::str::contains("matched declarations").not());
+}
+
+#[test]
+fn test_focus_collapses_the_todo_file() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    let todo_file = root.join("Feed.swift");
+    fs::write(
+        &todo_file,
+        concat!(
+            "final class FeedModel {\n",
+            "    var page = 0\n",
+            "    var title = \"Feed\"\n",
+            "\n",
+            "    func next() {\n",
+            "        // TODO: - stop at the last page\n",
+            "        page += 1\n",
+            "    }\n",
+            "}\n",
+        ),
+    )
+    .unwrap();
+
+    let run = |extra: &[&str]| {
+        Command::cargo_bin("generate_prompt")
+            .unwrap()
+            .args(["--max-tokens", "100000"])
+            .args(extra)
+            .env("GET_GIT_ROOT", root)
+            .env("GET_INSTRUCTION_FILE", &todo_file)
+            .env("DISABLE_PBCOPY", "1")
+            .assert()
+            .success()
+    };
+    run(&["--focus"]).stdout(predicate::str::contains("Feed.swift (focused)"));
+    run(&[]).stdout(predicate::str::contains("(focused)").not());
+}
+
+#[test]
+fn test_depth_pulls_in_the_types_definitions_use() {
+    let fake_git_root = TempDir::new().unwrap();
+    let root = fake_git_root.path();
+    let todo_file = root.join("Checkout.swift");
+    fs::write(
+        &todo_file,
+        "let service = OrderService()\n// TODO: - Submit the order\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("OrderService.swift"),
+        "class OrderService {\n    func submit(_ order: Order) {}\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("Order.swift"),
+        "struct Order {\n    let id: Int\n}\n",
+    )
+    .unwrap();
+
+    let run = |extra: &[&str]| {
+        Command::cargo_bin("generate_prompt")
+            .unwrap()
+            .args(["--max-tokens", "100000"])
+            .args(extra)
+            .env("GET_GIT_ROOT", root)
+            .env("GET_INSTRUCTION_FILE", &todo_file)
+            .env("DISABLE_PBCOPY", "1")
+            .assert()
+            .success()
+    };
+    run(&["--depth", "1"]).stdout(predicate::str::contains("definition (hop 1)"));
+    run(&[]).stdout(predicate::str::contains("Order.swift").not());
+}
@@ -0,0 +1,233 @@
+// crates/generate_prompt_core/src/batch.rs
+
+//! Batch export: one prompt file per marker in the repository, plus an
+//! `index.md` listing them and the markers whose prompts failed.
+
+use anyhow::{Context, Result};
+use find_prompt_instruction::{list_markers_in_dir_with_options, MarkerEntry};
+use log::{debug, warn};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::file_selector::SourceCache;
+use crate::prompt_generator::{generate_prompt_with_cache, GeneratePromptOptions};
+
+/// What happened to one marker of a batch.
+#[derive(Debug)]
+pub struct BatchEntry {
+    pub marker: MarkerEntry,
+    /// The marker's path relative to the Git root.
+    pub relative_path: String,
+    /// The prompt file written, or why no prompt could be generated (e.g. a
+    /// failed marker-count validation).
+    pub result: std::result::Result<PathBuf, String>,
+}
+
+#[derive(Debug)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+    pub index_path: PathBuf,
+    /// Distinct search roots walked for the whole batch.
+    pub source_walks: usize,
+}
+
+impl BatchReport {
+    pub fn failures(&self) -> impl Iterator<Item = &BatchEntry> {
+        self.entries.iter().filter(|entry| entry.result.is_err())
+    }
+}
+
+/// Generates a prompt for every marker under `git_root` into `out_dir`, as
+/// `<file>-<line>.md` with directories in the path joined by `_`, and writes
+/// `index.md` alongside.  A task group is exported once, from its first
+/// marker.  `options` apply to every prompt; each marker's own directives
+/// are added on top as usual.
+pub fn export_prompts(
+    git_root: &str,
+    out_dir: &Path,
+    options: &GeneratePromptOptions,
+) -> Result<BatchReport> {
+    fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+
+    let root = Path::new(git_root);
+    let mut markers = list_markers_in_dir_with_options(root, &options.discovery());
+    markers.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+    let cache = SourceCache::new(options.walk.clone(), options.index.clone());
+    let mut exported_groups = HashSet::new();
+    let mut entries = Vec::new();
+    for marker in markers {
+        if let Some(group) = &marker.content.group {
+            if !exported_groups.insert(group.clone()) {
+                debug!(
+                    "Skipping {}: task group #{} already exported",
+                    marker.path.display(),
+                    group
+                );
+                continue;
+            }
+        }
+
+        let relative_path = marker
+            .path
+            .strip_prefix(root)
+            .unwrap_or(&marker.path)
+            .display()
+            .to_string();
+        let marker_options = GeneratePromptOptions {
+            todo_line: Some(marker.line),
+            ..options.clone()
+        };
+        let result = generate_prompt_with_cache(git_root, &marker.path, &marker_options, &cache)
+            .and_then(|output| {
+                let path = out_dir.join(prompt_file_name(&relative_path, marker.line));
+                fs::write(&path, output.final_prompt)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                Ok(path)
+            })
+            .map_err(|err| {
+                warn!("{}:{}: {:#}", relative_path, marker.line, err);
+                format!("{:#}", err)
+            });
+
+        entries.push(BatchEntry {
+            marker,
+            relative_path,
+            result,
+        });
+    }
+
+    let index_path = out_dir.join("index.md");
+    fs::write(&index_path, render_index(&entries))
+        .with_context(|| format!("Failed to write {}", index_path.display()))?;
+
+    Ok(BatchReport {
+        entries,
+        index_path,
+        source_walks: cache.walk_count(),
+    })
+}
+
+/// `Sources/Feed.swift` at line 12 becomes `Sources_Feed.swift-12.md`.
+fn prompt_file_name(relative_path: &str, line: usize) -> String {
+    let flat: String = relative_path
+        .chars()
+        .map(|c| if c == '/' || c == '\\' { '_' } else { c })
+        .collect();
+    format!("{}-{}.md", flat, line)
+}
+
+fn render_index(entries: &[BatchEntry]) -> String {
+    let mut index =
+        String::from("# Prompts\n\n| Marker | Location | Prompt |\n| --- | --- | --- |\n");
+    for entry in entries {
+        let location = match &entry.marker.scope {
+            Some(scope) => format!(
+                "`{}` at {}:{}",
+                scope, entry.relative_path, entry.marker.line
+            ),
+            None => format!("{}:{}", entry.relative_path, entry.marker.line),
+        };
+        let prompt = match &entry.result {
+            Ok(path) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                format!("[{}]({})", name, name)
+            }
+            Err(_) => "failed".to_string(),
+        };
+        index.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            entry.marker.content.instruction.replace('|', "\\|"),
+            location,
+            prompt
+        ));
+    }
+
+    let failures: Vec<&BatchEntry> = entries.iter().filter(|e| e.result.is_err()).collect();
+    if !failures.is_empty() {
+        index.push_str("\n## Failed\n\n");
+        for entry in failures {
+            if let Err(err) = &entry.result {
+                index.push_str(&format!(
+                    "- {}:{}: {}\n",
+                    entry.relative_path,
+                    entry.marker.line,
+                    err.replace('\n', " ")
+                ));
+            }
+        }
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn prompt_file_names_flatten_the_path() {
+        assert_eq!(prompt_file_name("Feed.swift", 3), "Feed.swift-3.md");
+        assert_eq!(
+            prompt_file_name("Sources/App/Feed.swift", 12),
+            "Sources_App_Feed.swift-12.md"
+        );
+    }
+
+    #[test]
+    fn exports_one_prompt_per_marker_with_an_index() {
+        let repo = tempdir().unwrap();
+        let out = tempdir().unwrap();
+        let root = repo.path();
+        fs::create_dir_all(root.join("Sources")).unwrap();
+        fs::write(
+            root.join("Sources/Feed.swift"),
+            "struct Feed {\n    func load() {\n        let c = Cache()\n        // TODO: - cache the feed\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("Sources/Cache.swift"), "struct Cache {}\n").unwrap();
+        fs::write(
+            root.join("main.js"),
+            "// FIXME: - #auth one\nfunction a() {}\n// FIXME: - #auth two\n",
+        )
+        .unwrap();
+        // A marker line quoting a second marker fails validation.
+        fs::write(
+            root.join("Broken.swift"),
+            "// TODO: - broken, see // TODO: - above\n",
+        )
+        .unwrap();
+
+        let report =
+            export_prompts(root.to_str().unwrap(), out.path(), &Default::default()).unwrap();
+
+        let written: Vec<&str> = report
+            .entries
+            .iter()
+            .filter_map(|e| e.result.as_ref().ok())
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(written, vec!["Sources_Feed.swift-4.md", "main.js-1.md"]);
+        assert_eq!(report.source_walks, 1);
+
+        let feed = fs::read_to_string(out.path().join("Sources_Feed.swift-4.md")).unwrap();
+        assert!(feed.contains("struct Cache {}"));
+        assert!(!feed.contains("#auth"));
+        let group = fs::read_to_string(out.path().join("main.js-1.md")).unwrap();
+        assert!(group.contains("Can you do the 2 tasks tagged #auth"));
+
+        let failures: Vec<&str> = report
+            .failures()
+            .map(|e| e.relative_path.as_str())
+            .collect();
+        assert_eq!(failures, vec!["Broken.swift"]);
+
+        let index = fs::read_to_string(&report.index_path).unwrap();
+        assert!(index.contains(
+            "| `// TODO: - cache the feed` | `Feed.load()` at Sources/Feed.swift:4 | [Sources_Feed.swift-4.md](Sources_Feed.swift-4.md) |"
+        ));
+        assert!(index.contains("## Failed\n\n- Broken.swift:1: "));
+    }
+}
@@ -0,0 +1,397 @@
+// crates/generate_prompt_core/src/budget.rs
+
+//! Keeping a prompt within a token budget: every file's section is
+//! measured, and when the prompt is too large the least relevant files are
+//! cut down to skeletons, then dropped, and finally the TODO file is cut
+//! down to the function around its marker.
+
+use anyhow::Result;
+use assemble_prompt::{PromptSection, Rendering};
+use log::{debug, warn};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::file_selector::Relevance;
+
+/// Roughly how many tokens a BPE tokenizer splits `text` into, without its
+/// vocabulary: every four characters of a word, every other visible
+/// character and every line break count as one token each.  Code tends to
+/// come out a little over the real count, which is the safe side for a
+/// budget.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word = 0usize;
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word += 1;
+            continue;
+        }
+        tokens += word.div_ceil(4);
+        word = 0;
+        if c == '\n' || !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + word.div_ceil(4)
+}
+
+/// What the budget did with a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetAction {
+    Kept,
+    /// Shown in less detail to save tokens.
+    Downgraded,
+    Dropped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTokens {
+    pub path: PathBuf,
+    pub relevance: Relevance,
+    /// Estimated size of the file's section as it went into the prompt, or
+    /// as it would have had it not been dropped.
+    pub tokens: usize,
+    pub rendering: Rendering,
+    pub action: BudgetAction,
+}
+
+/// Where a prompt's tokens go, file by file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenReport {
+    pub files: Vec<FileTokens>,
+    /// The closing instruction's share.
+    pub instruction_tokens: usize,
+    pub max_tokens: Option<usize>,
+}
+
+impl TokenReport {
+    /// Estimated size of the prompt as generated.
+    pub fn total(&self) -> usize {
+        self.instruction_tokens
+            + self
+                .files
+                .iter()
+                .filter(|file| file.action != BudgetAction::Dropped)
+                .map(|file| file.tokens)
+                .sum::<usize>()
+    }
+
+    pub fn dropped(&self) -> impl Iterator<Item = &FileTokens> {
+        self.files
+            .iter()
+            .filter(|file| file.action == BudgetAction::Dropped)
+    }
+}
+
+/// Measures `sections` and, when `max_tokens` is set and exceeded, shrinks
+/// the prompt until it fits: the least relevant files are rendered as
+/// skeletons, then dropped, and at last TODO files are rendered as their
+/// enclosing function, each re-rendered with `render`.  Files are taken
+/// from the lowest weight up, the larger first among equals.  Returns the
+/// sections to keep, in their original order.
+pub(crate) fn fit_sections(
+    mut sections: Vec<PromptSection>,
+    relevance: &BTreeMap<PathBuf, Relevance>,
+    instruction_tokens: usize,
+    max_tokens: Option<usize>,
+    render: impl Fn(&Path, Rendering) -> Result<Option<PromptSection>>,
+) -> Result<(Vec<PromptSection>, TokenReport)> {
+    let mut files: Vec<FileTokens> = sections
+        .iter()
+        .map(|section| FileTokens {
+            path: section.path.clone(),
+            relevance: relevance
+                .get(&section.path)
+                .copied()
+                .unwrap_or(Relevance::Reference),
+            tokens: estimate_tokens(&section.text),
+            rendering: section.rendering,
+            action: BudgetAction::Kept,
+        })
+        .collect();
+    let mut report = TokenReport {
+        files: Vec::new(),
+        instruction_tokens,
+        max_tokens,
+    };
+
+    if let Some(max) = max_tokens {
+        let mut total = instruction_tokens + files.iter().map(|f| f.tokens).sum::<usize>();
+
+        let mut droppable: Vec<usize> = (0..files.len())
+            .filter(|&i| files[i].relevance != Relevance::TodoFile)
+            .collect();
+        droppable.sort_by_key(|&i| (files[i].relevance.weight(), Reverse(files[i].tokens)));
+        for &i in &droppable {
+            if total <= max {
+                break;
+            }
+            if files[i].rendering == Rendering::Full {
+                total -= downgrade(&mut files, &mut sections, i, Rendering::Skeleton, &render)?;
+            }
+        }
+
+        let mut dropped = Vec::new();
+        for &i in &droppable {
+            if total <= max {
+                break;
+            }
+            total -= files[i].tokens;
+            files[i].action = BudgetAction::Dropped;
+            dropped.push(i);
+        }
+
+        let mut todo_files: Vec<usize> = (0..files.len())
+            .filter(|&i| files[i].relevance == Relevance::TodoFile)
+            .collect();
+        todo_files.sort_by_key(|&i| Reverse(files[i].tokens));
+        for i in todo_files {
+            if total <= max {
+                break;
+            }
+            total -= downgrade(
+                &mut files,
+                &mut sections,
+                i,
+                Rendering::EnclosingFunction,
+                &render,
+            )?;
+        }
+
+        // Dropping stops as soon as the prompt fits, so a small file
+        // dropped before a large one may fit again, as may anything after
+        // a downgrade; the most relevant are put back first.
+        for &i in dropped.iter().rev() {
+            if total + files[i].tokens <= max {
+                total += files[i].tokens;
+                files[i].action = BudgetAction::Kept;
+            }
+        }
+
+        if total > max {
+            warn!(
+                "The prompt needs about {} tokens even after pruning, over the budget of {}",
+                total, max
+            );
+        }
+    }
+
+    let kept = sections
+        .into_iter()
+        .zip(&files)
+        .filter(|(_, file)| file.action != BudgetAction::Dropped)
+        .map(|(section, _)| section)
+        .collect();
+    report.files = files;
+    Ok((kept, report))
+}
+
+/// Re-renders file `i` as `rendering` if that shows it in fewer tokens, and
+/// returns how many it saves.
+fn downgrade(
+    files: &mut [FileTokens],
+    sections: &mut [PromptSection],
+    i: usize,
+    rendering: Rendering,
+    render: &impl Fn(&Path, Rendering) -> Result<Option<PromptSection>>,
+) -> Result<usize> {
+    let Some(section) = render(&files[i].path, rendering)? else {
+        return Ok(0);
+    };
+    let tokens = estimate_tokens(&section.text);
+    if section.rendering != rendering || tokens >= files[i].tokens {
+        return Ok(0);
+    }
+    debug!(
+        "Rendering {} as {:?}: {} tokens instead of {}",
+        files[i].path.display(),
+        rendering,
+        tokens,
+        files[i].tokens
+    );
+    let saved = files[i].tokens - tokens;
+    files[i].tokens = tokens;
+    files[i].rendering = rendering;
+    files[i].action = BudgetAction::Downgraded;
+    sections[i] = section;
+    Ok(saved)
+}
+
+impl fmt::Display for TokenReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>8}  {:<18}  File", "Tokens", "Relevance")?;
+        for file in &self.files {
+            let basename = file.path.file_name().unwrap_or_default().to_string_lossy();
+            let note = match (file.action, file.rendering) {
+                (BudgetAction::Dropped, _) => " (dropped)",
+                (_, Rendering::Full) => "",
+                (_, Rendering::EnclosingFunction) => " (enclosing function only)",
+                (_, Rendering::Skeleton) => " (skeleton)",
+                (_, Rendering::Declarations) => " (matched declarations)",
+                (_, Rendering::Focus) => " (focused)",
+            };
+            writeln!(
+                f,
+                "{:>8}  {:<18}  {}{}",
+                file.tokens,
+                file.relevance.to_string(),
+                basename,
+                note
+            )?;
+        }
+        writeln!(
+            f,
+            "{:>8}  {:<18}  closing instruction",
+            self.instruction_tokens, ""
+        )?;
+        match self.max_tokens {
+            Some(max) => writeln!(f, "Total: ~{} of {} tokens", self.total(), max),
+            None => writeln!(f, "Total: ~{} tokens", self.total()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, tokens: usize, rendering: Rendering) -> PromptSection {
+        PromptSection {
+            path: PathBuf::from(name),
+            rendering,
+            text: "word ".repeat(tokens),
+        }
+    }
+
+    /// Fits files of the given sizes; TODO files shrink to 20 tokens, and,
+    /// with `skeletons`, other files to a quarter of their size.
+    fn fit(
+        files: &[(&str, usize, Relevance)],
+        max_tokens: Option<usize>,
+        skeletons: bool,
+    ) -> (Vec<PathBuf>, TokenReport) {
+        let relevance = files
+            .iter()
+            .map(|(name, _, relevance)| (PathBuf::from(name), *relevance))
+            .collect();
+        let sections = files
+            .iter()
+            .map(|(name, tokens, _)| section(name, *tokens, Rendering::Full))
+            .collect();
+        let render = |path: &Path, rendering| {
+            let name = path.to_str().unwrap();
+            let tokens = files.iter().find(|f| f.0 == name).unwrap().1;
+            Ok(match rendering {
+                Rendering::EnclosingFunction => Some(section(name, 20, rendering)),
+                Rendering::Skeleton if skeletons => Some(section(name, tokens / 4, rendering)),
+                _ => None,
+            })
+        };
+        let (kept, report) = fit_sections(sections, &relevance, 10, max_tokens, render).unwrap();
+        (kept.into_iter().map(|s| s.path).collect(), report)
+    }
+
+    #[test]
+    fn estimates_words_symbols_and_line_breaks() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("let x = 1;\n"), 6);
+        // A long identifier takes a token per four characters.
+        assert_eq!(estimate_tokens("paginationController"), 5);
+        assert_eq!(estimate_tokens("    indented"), 2);
+    }
+
+    #[test]
+    fn without_a_budget_every_file_is_measured_and_kept() {
+        let (kept, report) = fit(
+            &[
+                ("Feed.swift", 100, Relevance::TodoFile),
+                ("Cache.swift", 50, Relevance::Reference),
+            ],
+            None,
+            true,
+        );
+        assert_eq!(kept.len(), 2);
+        assert_eq!(report.total(), 160);
+        assert!(report.to_string().contains("Total: ~160 tokens"));
+    }
+
+    #[test]
+    fn least_relevant_files_are_dropped_first() {
+        let (kept, report) = fit(
+            &[
+                ("Cache.swift", 100, Relevance::Definition { hop: 0 }),
+                ("Feed.swift", 100, Relevance::TodoFile),
+                ("Small.swift", 10, Relevance::Reference),
+                ("Big.swift", 150, Relevance::Reference),
+                ("Ext.swift", 60, Relevance::Extension),
+            ],
+            Some(240),
+            false,
+        );
+        // Both references go first, larger first; dropping the extension
+        // too brings the prompt to 210, after which the small reference fits
+        // again.
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("Cache.swift"),
+                PathBuf::from("Feed.swift"),
+                PathBuf::from("Small.swift")
+            ]
+        );
+        assert_eq!(report.total(), 220);
+        let dropped: Vec<&Path> = report.dropped().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            dropped,
+            vec![Path::new("Big.swift"), Path::new("Ext.swift")]
+        );
+        assert!(report.to_string().contains("Ext.swift (dropped)"));
+    }
+
+    #[test]
+    fn skeletons_come_before_dropping() {
+        let (kept, report) = fit(
+            &[
+                ("Cache.swift", 100, Relevance::Definition { hop: 0 }),
+                ("Feed.swift", 100, Relevance::TodoFile),
+                ("Big.swift", 200, Relevance::Reference),
+            ],
+            Some(200),
+            true,
+        );
+        // The reference as a skeleton (50) is not enough on its own; the
+        // definition is cut down too, and nothing is dropped.
+        assert_eq!(kept.len(), 3);
+        assert_eq!(report.total(), 185);
+        assert!(report
+            .files
+            .iter()
+            .filter(|f| f.relevance != Relevance::TodoFile)
+            .all(|f| f.action == BudgetAction::Downgraded && f.rendering == Rendering::Skeleton));
+        assert!(report.to_string().contains("Big.swift (skeleton)"));
+    }
+
+    #[test]
+    fn the_todo_file_is_downgraded_rather_than_dropped() {
+        let (kept, report) = fit(
+            &[
+                ("Feed.swift", 500, Relevance::TodoFile),
+                ("Cache.swift", 50, Relevance::Definition { hop: 0 }),
+            ],
+            Some(100),
+            false,
+        );
+        // The definition alone cannot save enough; once the TODO file is
+        // cut down, it fits again.
+        assert_eq!(kept.len(), 2);
+        assert_eq!(report.files[0].action, BudgetAction::Downgraded);
+        assert_eq!(report.files[0].tokens, 20);
+        assert_eq!(report.files[1].action, BudgetAction::Kept);
+        assert_eq!(report.total(), 80);
+        assert!(report
+            .to_string()
+            .contains("Feed.swift (enclosing function only)"));
+    }
+}
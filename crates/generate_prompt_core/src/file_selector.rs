@@ -1,33 +1,174 @@
 // crates/generate_prompt_core/src/file_selector.rs
 
 use anyhow::Result;
-use extract_enclosing_type::extract_enclosing_type;
+use extract_enclosing_type::extract_enclosing_type_with_grammar;
 use extract_types::{extract_types_from_file_with_options, ExtractTypesOptions};
-use find_definition_files::find_definition_files_from_sources;
+use find_definition_files::{
+    find_definition_files_from_sources, find_definition_hits, find_extension_files_from_sources,
+    find_imported_definition_files,
+};
 use find_referencing_files::find_files_referencing_from_sources;
-use get_search_roots::get_search_roots;
-use lang_support::walk_source_files;
+use get_search_roots::get_search_roots_with_options;
+use index_cache::IndexCache;
+use lang_support::{walk_source_files_with_options, DeclarationKind, SymbolIndex, WalkOptions};
 use log::{debug, info, warn};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct FileSelectionOptions {
     pub include_references: bool,
     pub targeted: bool,
+    /// Upper bound on how many extension files (Swift `extension Foo`, Obj‑C
+    /// categories) are pulled in for the selected types. `None` means no cap.
+    pub max_extension_files: Option<usize>,
+    /// Token of the TODO marker when it is not the default `TODO: -`.
+    pub marker_token: Option<String>,
+    /// Types whose definitions are looked up even when the TODO file does not
+    /// mention them, also in singular mode.
+    pub include_types: Vec<String>,
+    /// Types whose definitions are never looked up.
+    pub exclude_types: Vec<String>,
+    /// Basenames of files to include regardless of the types found.
+    pub include_files: Vec<String>,
+    /// How many more hops of definitions to follow from the definition files
+    /// of the TODO file's types; 0 stops at those files.
+    pub depth: usize,
+    /// Upper bound on how many new definition files each of those hops adds.
+    /// `None` means no cap.
+    pub max_files_per_hop: Option<usize>,
+}
+
+/// Indexed source walks shared between selections, keyed by search root, so
+/// a batch of prompts over one tree walks, reads and indexes each file once.
+#[derive(Default)]
+pub struct SourceCache {
+    walk: WalkOptions,
+    /// Where per-file symbols persist between runs, if anywhere.
+    index: Option<IndexCache>,
+    walks: RefCell<HashMap<PathBuf, Rc<SymbolIndex>>>,
+}
+
+impl SourceCache {
+    /// A cache whose walks skip files as `walk` says, taking the symbols of
+    /// unchanged files from `index` when one is given.
+    pub fn new(walk: WalkOptions, index: Option<IndexCache>) -> Self {
+        Self {
+            walk,
+            index,
+            walks: RefCell::default(),
+        }
+    }
+
+    /// The symbol index of the supported source files under `search_root`'s
+    /// search roots, walking them on first use.
+    pub fn index_for(&self, search_root: &Path) -> Rc<SymbolIndex> {
+        if let Some(index) = self.walks.borrow().get(search_root) {
+            return Rc::clone(index);
+        }
+        let sources = walk_all_search_roots(search_root, &self.walk);
+        let index = Rc::new(match &self.index {
+            Some(cache) => cache.symbol_index(sources),
+            None => SymbolIndex::new(sources),
+        });
+        self.walks
+            .borrow_mut()
+            .insert(search_root.to_path_buf(), Rc::clone(&index));
+        index
+    }
+
+    /// How many distinct search roots have been walked.
+    pub fn walk_count(&self) -> usize {
+        self.walks.borrow().len()
+    }
 }
 
 #[derive(Debug)]
 pub struct FileSelectionResult {
     pub files: Vec<PathBuf>,
     pub types_found: std::collections::BTreeSet<String>,
+    /// Why each of `files` was selected; a file selected for several
+    /// reasons keeps the most relevant one.
+    pub relevance: BTreeMap<PathBuf, Relevance>,
+    /// Lines (0-based, end-exclusive) of the declarations that brought each
+    /// definition file in; definition files missing here are shown whole.
+    pub declarations: BTreeMap<PathBuf, Vec<Range<usize>>>,
+}
+
+/// Why a file is part of the prompt, most relevant first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relevance {
+    /// The file holding the marker, or another task of its group.
+    TodoFile,
+    /// Named with `include_files`.
+    Requested,
+    /// Defines a type the TODO file uses.  `hop` counts the definitions in
+    /// between: 0 for types the TODO file names itself.
+    Definition { hop: usize },
+    /// Extends a type the TODO file uses.
+    Extension,
+    /// Uses the type enclosing the marker.
+    Reference,
+}
+
+impl Relevance {
+    /// How much the file is worth keeping when the prompt must shrink;
+    /// each hop halves a definition's weight.
+    pub fn weight(self) -> u32 {
+        match self {
+            Relevance::TodoFile => 1000,
+            Relevance::Requested => 90,
+            Relevance::Definition { hop } => 80u32.checked_shr(hop as u32).unwrap_or(0),
+            Relevance::Extension => 50,
+            Relevance::Reference => 30,
+        }
+    }
+}
+
+/// Records that each of `paths` was selected for `relevance`, unless it was
+/// already selected for something weightier.
+pub(crate) fn record_relevance(
+    map: &mut BTreeMap<PathBuf, Relevance>,
+    paths: impl IntoIterator<Item = PathBuf>,
+    relevance: Relevance,
+) {
+    for path in paths {
+        map.entry(path)
+            .and_modify(|current| {
+                if relevance.weight() > current.weight() {
+                    *current = relevance;
+                }
+            })
+            .or_insert(relevance);
+    }
+}
+
+impl fmt::Display for Relevance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Relevance::TodoFile => write!(f, "TODO file"),
+            Relevance::Requested => write!(f, "included"),
+            Relevance::Definition { hop: 0 } => write!(f, "definition"),
+            Relevance::Definition { hop } => write!(f, "definition (hop {})", hop),
+            Relevance::Extension => write!(f, "extension"),
+            Relevance::Reference => write!(f, "reference"),
+        }
+    }
 }
 
 /// Determines the list of files to include in the prompt based on the given parameters.
 ///
 /// - If `singular` is true, only the instruction file (TODO file) is included.
-/// - Otherwise, it extracts types from the instruction file, uses those to find definition files,
-///   appends the instruction file, and applies exclusion filtering.
+/// - Otherwise, it extracts types from the instruction file, uses those to find definition files
+///   (including modules the instruction file imports them from) and files extending those types (capped by `max_extension_files`), appends the instruction
+///   file, and applies exclusion filtering.
 /// - If `include_references` is enabled, it also searches for files referencing the enclosing type.
+/// - `include_types` / `exclude_types` adjust the extracted types, and `include_files` adds files
+///   by basename; exclusion filtering still applies to everything.
 ///
 /// # Arguments
 ///
@@ -47,45 +188,95 @@ pub fn determine_files_to_include_with_options(
     excludes: &[String],
     options: &FileSelectionOptions,
 ) -> Result<FileSelectionResult> {
-    let mut found_files: Vec<PathBuf> = Vec::new();
+    determine_files_to_include_with_cache(
+        file_path,
+        singular,
+        search_root,
+        excludes,
+        options,
+        &SourceCache::default(),
+    )
+}
+
+/// Like [`determine_files_to_include_with_options`], taking the source walk
+/// from `cache` instead of walking the search roots again.
+pub fn determine_files_to_include_with_cache(
+    file_path: &Path,
+    singular: bool,
+    search_root: &Path,
+    excludes: &[String],
+    options: &FileSelectionOptions,
+    cache: &SourceCache,
+) -> Result<FileSelectionResult> {
+    let mut relevance = BTreeMap::new();
     let mut types_found = std::collections::BTreeSet::new();
 
-    let needs_source_walk = !singular || options.include_references;
-    let sources = if needs_source_walk {
-        walk_all_search_roots(search_root)
+    let needs_source_walk = !singular
+        || options.include_references
+        || !options.include_types.is_empty()
+        || !options.include_files.is_empty();
+    let index = if needs_source_walk {
+        cache.index_for(search_root)
     } else {
-        Vec::new()
+        Rc::new(SymbolIndex::new(Vec::new()))
     };
 
     if singular {
         info!("Singular mode enabled: only including the TODO file");
-        found_files.push(file_path.to_path_buf());
+        if !options.include_types.is_empty() {
+            types_found = options.include_types.iter().cloned().collect();
+            record_relevance(
+                &mut relevance,
+                find_definition_files_from_sources(&types_found, &index),
+                Relevance::Definition { hop: 0 },
+            );
+        }
     } else {
-        let types = extract_types_from_file_with_options(
+        let mut types = extract_types_from_file_with_options(
             file_path,
             &ExtractTypesOptions {
                 targeted: options.targeted,
+                marker_token: options.marker_token.clone(),
             },
         )?;
+        types.extend(options.include_types.iter().cloned());
+        for excluded in &options.exclude_types {
+            types.remove(excluded);
+        }
         debug!("Types found:");
         for ty in &types {
             debug!("{}", ty);
         }
         debug!("--------------------------------------------------");
 
-        let def_files_set = find_definition_files_from_sources(&types, &sources);
+        let mut def_files_set = find_definition_files_from_sources(&types, &index);
+        def_files_set.extend(find_imported_definition_files(&types, file_path));
+        let extension_files =
+            select_extension_files(&types, &index, file_path, options.max_extension_files);
         types_found = types;
 
-        for path in def_files_set {
-            found_files.push(path);
+        record_relevance(
+            &mut relevance,
+            def_files_set,
+            Relevance::Definition { hop: 0 },
+        );
+        record_relevance(&mut relevance, extension_files, Relevance::Extension);
+        if options.depth > 0 {
+            follow_definitions(
+                &index,
+                file_path,
+                excludes,
+                options,
+                &mut types_found,
+                &mut relevance,
+            );
         }
-
-        found_files.push(file_path.to_path_buf());
     }
 
     if options.include_references {
         debug!("Including files that reference the enclosing type");
-        let enclosing_type = match extract_enclosing_type(file_path) {
+        let grammar = lang_support::marker_grammar(file_path, options.marker_token.as_deref());
+        let enclosing_type = match extract_enclosing_type_with_grammar(file_path, &grammar) {
             Ok(ty) => ty,
             Err(err) => {
                 warn!("Error extracting enclosing type: {}", err);
@@ -95,23 +286,63 @@ pub fn determine_files_to_include_with_options(
         if !enclosing_type.is_empty() {
             debug!("Enclosing type: {}", enclosing_type);
             debug!("Searching for files referencing {}", enclosing_type);
-            let referencing_files = find_files_referencing_from_sources(&enclosing_type, &sources)?;
-            found_files.extend(referencing_files);
+            let referencing_files = find_files_referencing_from_sources(&enclosing_type, &index)?;
+            record_relevance(&mut relevance, referencing_files, Relevance::Reference);
         } else {
             debug!("No enclosing type found; skipping reference search.");
         }
     }
 
+    if !options.include_files.is_empty() {
+        debug!("Including files named: {:?}", options.include_files);
+        record_relevance(
+            &mut relevance,
+            index
+                .sources()
+                .iter()
+                .filter(|source| {
+                    source
+                        .path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| options.include_files.iter().any(|f| f == name))
+                })
+                .map(|source| source.path.clone()),
+            Relevance::Requested,
+        );
+    }
+    record_relevance(
+        &mut relevance,
+        [file_path.to_path_buf()],
+        Relevance::TodoFile,
+    );
+
     if !excludes.is_empty() {
         debug!("Excluding files matching: {:?}", excludes);
-        found_files.retain(|p| {
+        relevance.retain(|p, _| {
             let basename = p.file_name().unwrap_or_default().to_string_lossy();
             !excludes.contains(&basename.to_string())
         });
     }
 
-    found_files.sort();
-    found_files.dedup();
+    let definition_files = relevance
+        .iter()
+        .filter(|(_, relevance)| matches!(relevance, Relevance::Definition { .. }))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let mut declarations: BTreeMap<PathBuf, Vec<Range<usize>>> = BTreeMap::new();
+    for hit in find_definition_hits(&types_found, &definition_files) {
+        debug!(
+            "{} declares {} on lines {}-{}",
+            hit.path.display(),
+            hit.name,
+            hit.lines.start + 1,
+            hit.lines.end
+        );
+        declarations.entry(hit.path).or_default().push(hit.lines);
+    }
+
+    let found_files: Vec<PathBuf> = relevance.keys().cloned().collect();
     debug!("--------------------------------------------------");
     debug!("Files (final list):");
     for file in &found_files {
@@ -122,25 +353,139 @@ pub fn determine_files_to_include_with_options(
     Ok(FileSelectionResult {
         files: found_files,
         types_found,
+        relevance,
+        declarations,
     })
 }
 
+/// Pulls in the definitions of the types used by the definition files in
+/// `relevance`, breadth-first, for up to `options.depth` hops.
+///
+/// Each hop extracts identifiers from the files the previous hop added and
+/// looks up those the index defines and `types` does not hold yet; the files
+/// defining them are recorded as `Definition { hop }`.  Files already
+/// selected, the TODO file and excluded files are never added again, so
+/// cycles end the walk, as does a hop that adds nothing.  New types are
+/// added to `types` so their declarations are shown too.
+fn follow_definitions(
+    index: &SymbolIndex,
+    todo_file: &Path,
+    excludes: &[String],
+    options: &FileSelectionOptions,
+    types: &mut std::collections::BTreeSet<String>,
+    relevance: &mut BTreeMap<PathBuf, Relevance>,
+) {
+    let mut frontier: Vec<PathBuf> = relevance
+        .iter()
+        .filter(|(_, relevance)| matches!(relevance, Relevance::Definition { hop: 0 }))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let extract_options = ExtractTypesOptions {
+        targeted: false,
+        marker_token: options.marker_token.clone(),
+    };
+
+    for hop in 1..=options.depth {
+        let mut used = std::collections::BTreeSet::new();
+        for file in &frontier {
+            match extract_types_from_file_with_options(file, &extract_options) {
+                Ok(found) => used.extend(found),
+                Err(err) => warn!("Skipping the types of {}: {}", file.display(), err),
+            }
+        }
+        let new_types: std::collections::BTreeSet<String> = used
+            .into_iter()
+            .filter(|ty| !types.contains(ty) && !options.exclude_types.contains(ty))
+            .filter(|ty| {
+                index
+                    .declarations_of(ty)
+                    .any(|(_, kind)| kind != DeclarationKind::Extension)
+            })
+            .collect();
+        if new_types.is_empty() {
+            break;
+        }
+        debug!("Types found at hop {}: {:?}", hop, new_types);
+
+        let mut new_files: Vec<PathBuf> = find_definition_files_from_sources(&new_types, index)
+            .into_iter()
+            .filter(|path| path != todo_file && !relevance.contains_key(path))
+            .filter(|path| {
+                let basename = path.file_name().unwrap_or_default().to_string_lossy();
+                !excludes.iter().any(|excluded| *excluded == basename)
+            })
+            .collect();
+        types.extend(new_types);
+        if let Some(max) = options.max_files_per_hop {
+            if new_files.len() > max {
+                info!(
+                    "Capping definition files at hop {} at {} of {}",
+                    hop,
+                    max,
+                    new_files.len()
+                );
+                new_files.truncate(max);
+            }
+        }
+        debug!("Definition files found at hop {}: {}", hop, new_files.len());
+        if new_files.is_empty() {
+            break;
+        }
+        record_relevance(
+            relevance,
+            new_files.iter().cloned(),
+            Relevance::Definition { hop },
+        );
+        frontier = new_files;
+    }
+}
+
+/// Finds files that extend any of `types`, other than the TODO file itself.
+///
+/// When `max_files` is set, only the first `max_files` of them (in path order)
+/// are kept.
+fn select_extension_files(
+    types: &std::collections::BTreeSet<String>,
+    index: &SymbolIndex,
+    todo_file: &Path,
+    max_files: Option<usize>,
+) -> Vec<PathBuf> {
+    let extension_files: Vec<PathBuf> = find_extension_files_from_sources(types, index)
+        .into_iter()
+        .filter(|path| path != todo_file)
+        .collect();
+    debug!("Extension files found: {}", extension_files.len());
+
+    match max_files {
+        Some(max) if extension_files.len() > max => {
+            info!(
+                "Capping extension files at {} of {}",
+                max,
+                extension_files.len()
+            );
+            extension_files.into_iter().take(max).collect()
+        }
+        _ => extension_files,
+    }
+}
+
 /// Walks all search roots once to produce a single source-file collection.
 ///
 /// Mirrors what `find_definition_files` did internally: resolve search roots
-/// via `get_search_roots`, then walk each one. Because `walk_source_files`
+/// via `get_search_roots_with_options`, then walk each one. Because the walk
 /// recurses and roots may overlap, duplicates are removed by path.
-fn walk_all_search_roots(search_root: &Path) -> Vec<lang_support::SourceFile> {
-    let roots = get_search_roots(search_root).unwrap_or_else(|_| vec![search_root.to_path_buf()]);
+fn walk_all_search_roots(search_root: &Path, walk: &WalkOptions) -> Vec<lang_support::SourceFile> {
+    let roots = get_search_roots_with_options(search_root, walk)
+        .unwrap_or_else(|_| vec![search_root.to_path_buf()]);
 
     if roots.len() == 1 {
-        return walk_source_files(&roots[0]);
+        return walk_source_files_with_options(&roots[0], walk);
     }
 
     let mut seen = std::collections::BTreeSet::new();
     let mut all = Vec::new();
     for root in &roots {
-        for sf in walk_source_files(root) {
+        for sf in walk_source_files_with_options(root, walk) {
             if seen.insert(sf.path.clone()) {
                 all.push(sf);
             }
@@ -176,6 +521,7 @@ mod tests {
             &FileSelectionOptions {
                 include_references: false,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("Failed in singular mode");
@@ -215,12 +561,26 @@ mod tests {
             &FileSelectionOptions {
                 include_references: false,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("Non-singular without references failed");
         assert!(result.files.contains(&instr_path));
         assert!(result.files.contains(&def_path));
         assert_eq!(result.files.len(), 2);
+        // The instruction file also defines TypeA, but is recorded for its
+        // more relevant role.
+        assert_eq!(result.relevance[&instr_path], Relevance::TodoFile);
+        assert_eq!(
+            result.relevance[&def_path],
+            Relevance::Definition { hop: 0 }
+        );
+        // Only the definition file is sliced to its declarations.
+        let type_a = 0..1;
+        assert_eq!(
+            result.declarations,
+            BTreeMap::from([(def_path, vec![type_a])])
+        );
     }
 
     #[test]
@@ -259,6 +619,7 @@ mod tests {
             &FileSelectionOptions {
                 include_references: false,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("Explicit non-targeted selection failed");
@@ -306,6 +667,7 @@ mod tests {
             &FileSelectionOptions {
                 include_references: false,
                 targeted: true,
+                ..Default::default()
             },
         )
         .expect("Explicit targeted selection failed");
@@ -315,6 +677,171 @@ mod tests {
         assert!(result.files.contains(&inner_def_path));
     }
 
+    /// Files that only extend a found type are included alongside its definition.
+    #[test]
+    fn test_determine_files_includes_extension_files() {
+        let temp_dir = tempdir().unwrap();
+        let search_root = temp_dir.path().to_path_buf();
+
+        let instr_path = temp_dir.path().join("Instruction.swift");
+        fs_write(&instr_path, "let feed = Feed()\n// TODO: - Paginate Feed\n");
+        let def_path = temp_dir.path().join("Feed.swift");
+        fs_write(&def_path, "class Feed {}\n");
+        let ext_path = temp_dir.path().join("Feed+Networking.swift");
+        fs_write(&ext_path, "extension Feed {\n    func fetch() {}\n}\n");
+
+        let result = determine_files_to_include_with_options(
+            &instr_path,
+            false,
+            &search_root,
+            &[],
+            &FileSelectionOptions::default(),
+        )
+        .expect("Extension selection failed");
+
+        assert_eq!(result.files, vec![ext_path, def_path, instr_path]);
+    }
+
+    #[test]
+    fn test_max_extension_files_caps_extensions_in_path_order() {
+        let temp_dir = tempdir().unwrap();
+        let search_root = temp_dir.path().to_path_buf();
+
+        let instr_path = temp_dir.path().join("Instruction.swift");
+        fs_write(&instr_path, "let feed = Feed()\n// TODO: - Paginate Feed\n");
+        let first_ext = temp_dir.path().join("Feed+A.swift");
+        fs_write(&first_ext, "extension Feed {}\n");
+        let second_ext = temp_dir.path().join("Feed+B.swift");
+        fs_write(&second_ext, "extension Feed {}\n");
+
+        let result = determine_files_to_include_with_options(
+            &instr_path,
+            false,
+            &search_root,
+            &[],
+            &FileSelectionOptions {
+                max_extension_files: Some(1),
+                ..Default::default()
+            },
+        )
+        .expect("Capped extension selection failed");
+
+        assert!(result.files.contains(&first_ext));
+        assert!(!result.files.contains(&second_ext));
+    }
+
+    #[test]
+    fn test_depth_follows_definitions_breadth_first() {
+        let temp_dir = tempdir().unwrap();
+        let search_root = temp_dir.path().to_path_buf();
+
+        let instr_path = temp_dir.path().join("Checkout.swift");
+        fs_write(
+            &instr_path,
+            "let service = OrderService()\n// TODO: - Submit the order\n",
+        );
+        let service = temp_dir.path().join("OrderService.swift");
+        fs_write(
+            &service,
+            "class OrderService {\n    func submit(_ order: Order) {}\n}\n",
+        );
+        let order = temp_dir.path().join("Order.swift");
+        fs_write(
+            &order,
+            "struct Order {\n    let payment: PaymentMethod\n    let service: OrderService\n}\n",
+        );
+        let payment = temp_dir.path().join("PaymentMethod.swift");
+        fs_write(&payment, "enum PaymentMethod {\n    case card\n}\n");
+
+        let select = |depth: usize| {
+            determine_files_to_include_with_options(
+                &instr_path,
+                false,
+                &search_root,
+                &[],
+                &FileSelectionOptions {
+                    depth,
+                    ..Default::default()
+                },
+            )
+            .expect("Transitive selection failed")
+        };
+
+        assert_eq!(select(0).files, vec![instr_path.clone(), service.clone()]);
+        let result = select(3);
+        assert_eq!(result.relevance.len(), 4);
+        // Order refers back to OrderService, which keeps its first hop.
+        assert_eq!(result.relevance[&service], Relevance::Definition { hop: 0 });
+        assert_eq!(result.relevance[&order], Relevance::Definition { hop: 1 });
+        assert_eq!(result.relevance[&payment], Relevance::Definition { hop: 2 });
+        assert!(result.types_found.contains("PaymentMethod"));
+        assert!(result.declarations.contains_key(&payment));
+    }
+
+    #[test]
+    fn test_max_files_per_hop_caps_each_hop_in_path_order() {
+        let temp_dir = tempdir().unwrap();
+        let search_root = temp_dir.path().to_path_buf();
+
+        let instr_path = temp_dir.path().join("Instruction.swift");
+        fs_write(
+            &instr_path,
+            "let cart = Cart()\n// TODO: - Total the cart\n",
+        );
+        fs_write(
+            &temp_dir.path().join("Cart.swift"),
+            "struct Cart {\n    let items: [Item]\n    let coupon: Coupon\n}\n",
+        );
+        let coupon = temp_dir.path().join("Coupon.swift");
+        fs_write(&coupon, "struct Coupon {}\n");
+        let item = temp_dir.path().join("Item.swift");
+        fs_write(&item, "struct Item {}\n");
+
+        let result = determine_files_to_include_with_options(
+            &instr_path,
+            false,
+            &search_root,
+            &[],
+            &FileSelectionOptions {
+                depth: 1,
+                max_files_per_hop: Some(1),
+                ..Default::default()
+            },
+        )
+        .expect("Capped transitive selection failed");
+
+        assert_eq!(result.relevance[&coupon], Relevance::Definition { hop: 1 });
+        assert!(!result.files.contains(&item));
+    }
+
+    #[test]
+    fn test_index_cache_serves_repeated_selections() {
+        let temp_dir = tempdir().unwrap();
+        let instr_path = temp_dir.path().join("Instruction.swift");
+        fs_write(&instr_path, "let feed = Feed()\n// TODO: - Paginate Feed\n");
+        let def_path = temp_dir.path().join("Feed.swift");
+        fs_write(&def_path, "class Feed {}\n");
+        let index = IndexCache::new(temp_dir.path().join("index"));
+
+        for _ in 0..2 {
+            let result = determine_files_to_include_with_cache(
+                &instr_path,
+                false,
+                temp_dir.path(),
+                &[],
+                &FileSelectionOptions::default(),
+                &SourceCache::new(WalkOptions::default(), Some(index.clone())),
+            )
+            .expect("Cached selection failed");
+            assert_eq!(result.files, vec![def_path.clone(), instr_path.clone()]);
+        }
+        assert_eq!(index.stats().symbols.files, 2);
+    }
+
+    fn fs_write(path: &Path, content: &str) {
+        std::fs::write(path, content).unwrap();
+    }
+
     /// In non-singular mode with references enabled, if the instruction file declares "RefType"
     /// and a definition file as well as a referencing file both match, all should be returned.
     #[test]
@@ -352,6 +879,7 @@ mod tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("Non-singular with references failed");
@@ -374,6 +902,7 @@ mod tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("Missing file should only skip reference lookup");
@@ -381,6 +910,35 @@ mod tests {
         assert_eq!(result.files, vec![missing_instruction]);
     }
 
+    #[test]
+    fn test_include_references_uses_type_enclosing_the_todo() {
+        let temp_dir = tempdir().unwrap();
+        let instr_path = temp_dir.path().join("Feed+Refresh.swift");
+        fs_write(
+            &instr_path,
+            "class Helper {}\nextension Feed {\n    func refresh() {\n        // TODO: - Refresh\n    }\n}\n",
+        );
+        let feed_ref = temp_dir.path().join("FeedUser.swift");
+        fs_write(&feed_ref, "let feed = Feed()\n");
+        let helper_ref = temp_dir.path().join("HelperUser.swift");
+        fs_write(&helper_ref, "let helper = Helper()\n");
+
+        let result = determine_files_to_include_with_options(
+            &instr_path,
+            true,
+            temp_dir.path(),
+            &[],
+            &FileSelectionOptions {
+                include_references: true,
+                ..Default::default()
+            },
+        )
+        .expect("Reference selection failed");
+
+        assert!(result.files.contains(&feed_ref));
+        assert!(!result.files.contains(&helper_ref));
+    }
+
     /// Test exclusion filtering in non-singular mode with references enabled.
     /// In this test, we modify the instruction file to declare "TypeA" so that the enclosing type becomes "TypeA".
     /// Then we create a definition file and a referencing file both mentioning "TypeA". With "Def.swift" excluded,
@@ -421,6 +979,7 @@ mod tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("Exclusion test failed");
@@ -477,6 +1036,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("merged references + definitions failed");
@@ -510,6 +1070,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("exclusion across both paths failed");
@@ -538,6 +1099,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("without exclude failed");
@@ -554,6 +1116,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("with exclude failed");
@@ -585,6 +1148,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: false,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("no-references failed");
@@ -612,6 +1176,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: false,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("types_found test failed");
@@ -638,6 +1203,7 @@ mod walk_unification_characterization_tests {
             &FileSelectionOptions {
                 include_references: true,
                 targeted: false,
+                ..Default::default()
             },
         )
         .expect("singular + references failed");
@@ -648,4 +1214,52 @@ mod walk_unification_characterization_tests {
             "references should still be found in singular + include_references mode"
         );
     }
+
+    #[test]
+    fn forced_types_and_files_adjust_the_selection() {
+        let (dir, instr_path) = swift_project(
+            &[
+                ("Client.swift", "class Client {}\n"),
+                ("Logger.swift", "class Logger {}\n"),
+                ("Fixtures.swift", "let fixtures = 1\n"),
+            ],
+            "let logger = Logger()\n// TODO: - Fix it\n",
+            "Instruction.swift",
+        );
+
+        let result = determine_files_to_include_with_options(
+            &instr_path,
+            false,
+            dir.path(),
+            &[],
+            &FileSelectionOptions {
+                include_types: vec!["Client".to_string()],
+                exclude_types: vec!["Logger".to_string()],
+                include_files: vec!["Fixtures.swift".to_string()],
+                ..Default::default()
+            },
+        )
+        .expect("forced selection failed");
+
+        assert!(result.files.contains(&dir.path().join("Client.swift")));
+        assert!(result.files.contains(&dir.path().join("Fixtures.swift")));
+        assert!(!result.files.contains(&dir.path().join("Logger.swift")));
+        assert!(!result.types_found.contains("Logger"));
+
+        let singular = determine_files_to_include_with_options(
+            &instr_path,
+            true,
+            dir.path(),
+            &[],
+            &FileSelectionOptions {
+                include_types: vec!["Client".to_string()],
+                ..Default::default()
+            },
+        )
+        .expect("forced singular selection failed");
+        assert_eq!(
+            singular.files,
+            vec![dir.path().join("Client.swift"), instr_path.clone()]
+        );
+    }
 }
@@ -0,0 +1,64 @@
+// crates/generate_prompt_core/src/index.rs
+
+//! Filling the persistent index ahead of time, for `generate_prompt index`.
+
+use anyhow::Result;
+use find_prompt_instruction::{list_markers_in_dir_with_options, DiscoveryOptions};
+use index_cache::{IndexCache, IndexStats};
+use lang_support::walk_source_files_with_options;
+use log::debug;
+use std::path::Path;
+
+use crate::prompt_generator::GeneratePromptOptions;
+
+/// Empties `cache` and indexes everything under `git_root` again: the
+/// symbols of every source file, and its marker lines for `options`' marker
+/// token.  Walks follow `options.walk`; `options.index` is not consulted.
+pub fn rebuild_index(
+    cache: &IndexCache,
+    git_root: &Path,
+    options: &GeneratePromptOptions,
+) -> Result<IndexStats> {
+    cache.clear()?;
+
+    let discovery = DiscoveryOptions {
+        index: Some(cache.clone()),
+        ..options.discovery()
+    };
+    let markers = list_markers_in_dir_with_options(git_root, &discovery);
+    debug!("Indexed {} marker(s)", markers.len());
+
+    cache.symbol_index(walk_source_files_with_options(git_root, &options.walk));
+    Ok(cache.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn rebuild_indexes_symbols_and_markers_of_the_whole_tree() {
+        let repo = tempdir().unwrap();
+        let root = repo.path();
+        fs::create_dir_all(root.join("Sources")).unwrap();
+        fs::write(
+            root.join("Sources/Feed.swift"),
+            "struct Feed {\n    // TODO: - paginate\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("Sources/Cache.swift"), "struct Cache {}\n").unwrap();
+        let cache = IndexCache::in_git_dir(root);
+        fs::create_dir_all(cache.dir()).unwrap();
+        fs::write(cache.dir().join("symbols.json"), "not json").unwrap();
+
+        let stats = rebuild_index(&cache, root, &GeneratePromptOptions::default()).unwrap();
+
+        assert_eq!(stats.symbols.files, 2);
+        assert_eq!(stats.symbols.stale, 0);
+        assert_eq!(stats.markers.len(), 1);
+        assert_eq!(stats.markers[0].store.files, 2);
+        assert_eq!(stats.markers[0].files_with_markers, 1);
+    }
+}
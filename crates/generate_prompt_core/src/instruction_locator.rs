@@ -1,7 +1,11 @@
 // crates/generate_prompt_core/src/instruction_locator.rs
 
 use anyhow::{Context, Result};
-use find_prompt_instruction::find_prompt_instruction_in_dir;
+use find_prompt_instruction::{
+    find_prompt_instruction_in_dir, find_prompt_instruction_in_dir_with_options,
+    find_prompt_instruction_in_dir_with_token, list_markers_in_dir_with_options, pick_marker,
+    DiscoveryOptions, MarkerEntry, MarkerPick,
+};
 use std::path::{Path, PathBuf};
 
 /// Locates the TODO instruction file by searching the provided directory.
@@ -21,6 +25,38 @@ pub fn locate_instruction_file(search_dir: &Path) -> Result<PathBuf> {
     find_prompt_instruction_in_dir(search_dir).context("Failed to locate the TODO instruction")
 }
 
+/// Like [`locate_instruction_file`], looking for `marker_token` (e.g. `AI: -`)
+/// instead of the default `TODO: -` when one is given.
+pub fn locate_instruction_file_with_token(
+    search_dir: &Path,
+    marker_token: Option<&str>,
+) -> Result<PathBuf> {
+    find_prompt_instruction_in_dir_with_token(search_dir, marker_token)
+        .context("Failed to locate the TODO instruction")
+}
+
+/// Like [`locate_instruction_file`], searching as `options` say.
+pub fn locate_instruction_file_with_options(
+    search_dir: &Path,
+    options: &DiscoveryOptions,
+) -> Result<PathBuf> {
+    find_prompt_instruction_in_dir_with_options(search_dir, options)
+        .context("Failed to locate the TODO instruction")
+}
+
+/// Locates the marker chosen with `--pick`, numbered as in
+/// `generate_prompt list`.
+pub fn locate_picked_marker(
+    search_dir: &Path,
+    pick: &MarkerPick,
+    options: &DiscoveryOptions,
+) -> Result<MarkerEntry> {
+    let markers = list_markers_in_dir_with_options(search_dir, options);
+    pick_marker(&markers, pick)
+        .cloned()
+        .context("Failed to locate the picked TODO instruction")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +85,33 @@ mod tests {
         let result = locate_instruction_file(dir.path());
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_locate_instruction_file_with_custom_token() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("instruction.py");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "# AI: - Do something important").unwrap();
+
+        assert!(locate_instruction_file(dir.path()).is_err());
+        let result = locate_instruction_file_with_token(dir.path(), Some("AI: -")).unwrap();
+        assert_eq!(result, file_path);
+    }
+
+    #[test]
+    fn test_locate_picked_marker() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("instruction.swift");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "// TODO: - One\n// TODO: - Two").unwrap();
+
+        assert!(locate_instruction_file(dir.path()).is_err());
+        let pick = "instruction.swift:2".parse().unwrap();
+        let marker = locate_picked_marker(dir.path(), &pick, &DiscoveryOptions::default()).unwrap();
+        assert_eq!(marker.path, file_path);
+        assert_eq!(marker.content.instruction, "// TODO: - Two");
+
+        let pick = MarkerPick::Index(3);
+        assert!(locate_picked_marker(dir.path(), &pick, &DiscoveryOptions::default()).is_err());
+    }
 }
@@ -1,6 +1,9 @@
 // crates/generate_prompt_core/src/lib.rs
 
+pub mod batch;
+pub mod budget;
 pub mod file_selector;
+pub mod index;
 pub mod instruction_locator;
 pub mod prompt_generator;
 pub mod prompt_validation;
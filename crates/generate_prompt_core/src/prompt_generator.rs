@@ -2,12 +2,21 @@
 
 use anyhow::{anyhow, Context, Result};
 use log::{debug, info};
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
-use crate::file_selector;
+use crate::budget::{self, BudgetAction, TokenReport};
+use crate::file_selector::{self, record_relevance, SourceCache};
 use crate::search_root;
-use extract_instruction_content::extract_instruction_content;
+use extract_instruction_content::{
+    extract_instruction_at_line_with_grammar, extract_instruction_content_with_grammar, Directives,
+};
+use find_prompt_instruction::{find_task_group_in_dir, DiscoveryOptions, TaskMarker};
+use index_cache::IndexCache;
+use lang_support::WalkOptions;
+use todo_marker::{MarkerGrammar, MarkerVerb};
 
 #[derive(Debug, Clone, Default)]
 pub struct GeneratePromptOptions {
@@ -17,6 +26,105 @@ pub struct GeneratePromptOptions {
     pub excludes: Vec<String>,
     pub diff_branch: Option<String>,
     pub targeted: bool,
+    pub max_extension_files: Option<usize>,
+    /// Hops of definitions to follow beyond the TODO file's own types: the
+    /// types those definitions use, then the types theirs use, and so on.
+    pub depth: usize,
+    /// Cap on the definition files each of those hops adds.
+    pub max_files_per_hop: Option<usize>,
+    /// Marker token to look for instead of `TODO: -`, e.g. `AI: -`; the
+    /// comment syntax around it still follows the TODO file's language.
+    pub marker_token: Option<String>,
+    /// Types whose definitions are included even if the TODO file does not
+    /// mention them.
+    pub include_types: Vec<String>,
+    /// Types whose definitions are never included.
+    pub exclude_types: Vec<String>,
+    /// Basenames of files to include regardless of the types found.
+    pub include_files: Vec<String>,
+    /// 1-based line of the marker to use, for a marker picked from a file
+    /// holding several; defaults to the file's first marker.
+    pub todo_line: Option<usize>,
+    /// Which files are searched for markers, definitions and references.
+    pub walk: WalkOptions,
+    /// Persistent index to read and update; `None` indexes every file from
+    /// scratch.
+    pub index: Option<IndexCache>,
+    /// Estimated tokens the prompt may take; the least relevant files are
+    /// left out until it fits.
+    pub max_tokens: Option<usize>,
+    /// Files other than the TODO file longer than this many lines are shown
+    /// as skeletons; `Some(0)` shows all of them that way.
+    pub skeleton_over_lines: Option<usize>,
+    /// Show definition files whole instead of only the declarations the
+    /// TODO file needs and their imports.
+    pub whole_files: bool,
+    /// Show the TODO file focused on the marker: the enclosing function,
+    /// the members of its type that function uses, and placeholders for the
+    /// rest.
+    pub focus: bool,
+}
+
+impl GeneratePromptOptions {
+    /// How markers are searched for under these options.
+    pub fn discovery(&self) -> DiscoveryOptions {
+        DiscoveryOptions {
+            marker_token: self.marker_token.clone(),
+            walk: self.walk.clone(),
+            index: self.index.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// These options with the TODO line's directives added; directives can
+    /// only switch behaviour on or extend the lists, never undo a flag.
+    pub fn with_directives(&self, directives: &Directives) -> Self {
+        let extend = |base: &[String], extra: &[String]| -> Vec<String> {
+            let mut merged = base.to_vec();
+            merged.extend(extra.iter().filter(|x| !base.contains(x)).cloned());
+            merged
+        };
+        Self {
+            singular: self.singular || directives.singular,
+            force_global: self.force_global || directives.force_global,
+            include_references: self.include_references || directives.include_references,
+            targeted: self.targeted || directives.targeted,
+            excludes: extend(&self.excludes, &directives.exclude_files),
+            include_types: extend(&self.include_types, &directives.include_types),
+            exclude_types: extend(&self.exclude_types, &directives.exclude_types),
+            include_files: extend(&self.include_files, &directives.include_files),
+            ..self.clone()
+        }
+    }
+}
+
+/// Context a marker verb asks for on top of the caller's options.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ContextPolicy {
+    targeted: bool,
+    include_references: bool,
+}
+
+impl ContextPolicy {
+    /// Tests and reviews centre on the block around the marker; explanations
+    /// and reviews also want the code that uses the enclosing type.
+    fn for_verb(verb: MarkerVerb) -> Self {
+        match verb {
+            MarkerVerb::Todo | MarkerVerb::Fixme => Self::default(),
+            MarkerVerb::Test => Self {
+                targeted: true,
+                include_references: false,
+            },
+            MarkerVerb::Explain => Self {
+                targeted: false,
+                include_references: true,
+            },
+            MarkerVerb::Review => Self {
+                targeted: true,
+                include_references: true,
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -26,12 +134,35 @@ pub struct GeneratePromptOutput {
     pub search_root: PathBuf,
     pub found_files: Vec<PathBuf>,
     pub types_found: BTreeSet<String>,
+    /// The verb of the marker the prompt was built for.
+    pub verb: MarkerVerb,
+    /// Every marker of the instruction's task group when it has more than
+    /// one; empty for a single-task prompt.
+    pub tasks: Vec<TaskMarker>,
+    /// Estimated tokens per file, and what `max_tokens` left out.
+    pub token_report: TokenReport,
 }
 
 pub fn generate_prompt_with_options(
     git_root: &str,
     file_path: &Path,
     options: &GeneratePromptOptions,
+) -> Result<GeneratePromptOutput> {
+    generate_prompt_with_cache(
+        git_root,
+        file_path,
+        options,
+        &SourceCache::new(options.walk.clone(), options.index.clone()),
+    )
+}
+
+/// Like [`generate_prompt_with_options`], reusing the source walks in
+/// `cache` so several prompts over one tree read it only once.
+pub fn generate_prompt_with_cache(
+    git_root: &str,
+    file_path: &Path,
+    options: &GeneratePromptOptions,
+    cache: &SourceCache,
 ) -> Result<GeneratePromptOutput> {
     let todo_file_basename = file_path
         .file_name()
@@ -39,73 +170,294 @@ pub fn generate_prompt_with_options(
         .unwrap_or("")
         .to_string();
 
-    if options.include_references {
-        let extension = file_path.extension().and_then(|s| s.to_str()).unwrap_or("");
-        let supports_references = lang_support::for_extension(extension)
-            .is_some_and(|lang| lang.supports_enclosing_type());
-        if !supports_references {
-            return Err(anyhow!(
-                "--include-references is only supported for Swift files"
-            ));
-        }
+    let grammar = lang_support::marker_grammar(file_path, options.marker_token.as_deref());
+    let instruction = match options.todo_line {
+        Some(line) => extract_instruction_at_line_with_grammar(file_path, line, &grammar),
+        None => extract_instruction_content_with_grammar(file_path, &grammar),
+    }
+    .context("Failed to extract instruction content")?;
+    let instruction_content = instruction.instruction.trim().to_string();
+    debug!("Instruction content: {}", instruction_content);
+    if !instruction.directives.is_empty() {
+        debug!("Directives: {:?}", instruction.directives);
     }
+    debug!("--------------------------------------------------");
 
     let base_dir = PathBuf::from(git_root);
 
-    let search_root_path = if options.force_global {
-        info!("Force global enabled: using Git root for context");
-        base_dir.clone()
-    } else {
-        search_root::determine_search_root(&base_dir, file_path)
+    let tasks = match instruction.group.as_deref() {
+        Some(tag) => {
+            let tasks = find_task_group_in_dir(&base_dir, tag, &options.discovery())?;
+            debug!("Task group #{}: {} marker(s)", tag, tasks.len());
+            if tasks.len() > 1 {
+                tasks
+            } else {
+                Vec::new()
+            }
+        }
+        None => Vec::new(),
     };
+    let options = &tasks.iter().fold(
+        options.with_directives(&instruction.directives),
+        |merged, task| merged.with_directives(&task.content.directives),
+    );
+
+    let supports_references = |path: &Path| {
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+        lang_support::for_extension(extension).is_some_and(|lang| lang.supports_enclosing_type())
+    };
+    let todo_supports_references = supports_references(file_path);
+    if options.include_references && !todo_supports_references {
+        return Err(anyhow!(
+            "--include-references is only supported for Swift, TypeScript, Kotlin, Java and Python files"
+        ));
+    }
+
+    let search_root_for = |path: &Path| {
+        if options.force_global {
+            base_dir.clone()
+        } else {
+            search_root::determine_search_root_with_options(&base_dir, path, &options.walk)
+        }
+    };
+    if options.force_global {
+        info!("Force global enabled: using Git root for context");
+    }
+    let search_root_path = search_root_for(file_path);
     debug!("Search root: {}", search_root_path.display());
 
-    let instruction_content =
-        extract_instruction_content(file_path).context("Failed to extract instruction content")?;
-    debug!("Instruction content: {}", instruction_content.trim());
-    debug!("--------------------------------------------------");
+    let verb = grammar.verb_of(&instruction_content).unwrap_or_default();
+    let select = |path: &Path, verb: MarkerVerb| {
+        let policy = ContextPolicy::for_verb(verb);
+        debug!("Marker verb: {} ({:?})", verb, policy);
+        file_selector::determine_files_to_include_with_cache(
+            path,
+            options.singular,
+            &search_root_for(path),
+            &options.excludes,
+            &file_selector::FileSelectionOptions {
+                include_references: options.include_references
+                    || (policy.include_references && supports_references(path)),
+                targeted: options.targeted || policy.targeted,
+                max_extension_files: options.max_extension_files,
+                marker_token: options.marker_token.clone(),
+                include_types: options.include_types.clone(),
+                exclude_types: options.exclude_types.clone(),
+                include_files: options.include_files.clone(),
+                depth: options.depth,
+                max_files_per_hop: options.max_files_per_hop,
+            },
+            cache,
+        )
+    };
 
-    let selection = file_selector::determine_files_to_include_with_options(
-        file_path,
-        options.singular,
-        &search_root_path,
-        &options.excludes,
-        &file_selector::FileSelectionOptions {
-            include_references: options.include_references,
-            targeted: options.targeted,
-        },
-    )?;
+    let mut selection = select(file_path, verb)?;
+    let mut task_files: Vec<&Path> = Vec::new();
+    for task in &tasks {
+        let path = task.path.as_path();
+        if path == file_path || task_files.contains(&path) {
+            continue;
+        }
+        task_files.push(path);
+        let task_selection = select(path, task.content.verb)?;
+        selection.files.extend(task_selection.files);
+        selection.types_found.extend(task_selection.types_found);
+        for (path, relevance) in task_selection.relevance {
+            record_relevance(&mut selection.relevance, [path], relevance);
+        }
+        for (path, lines) in task_selection.declarations {
+            selection
+                .declarations
+                .entry(path)
+                .or_default()
+                .extend(lines);
+        }
+    }
+    selection.files.sort();
+    selection.files.dedup();
 
+    let todo_location = match options.todo_line {
+        Some(line) => Some(format!(
+            "the {} is {}",
+            verb,
+            describe_line(file_path, &todo_file_basename, line)
+        )),
+        None => describe_todo_location(file_path, &todo_file_basename, &grammar, verb),
+    };
+    let task_group = instruction
+        .group
+        .clone()
+        .filter(|_| !tasks.is_empty())
+        .map(|tag| assemble_prompt::TaskGroup {
+            tag,
+            tasks: tasks.iter().map(summarize_task).collect(),
+        });
+    let parsed_markers = std::iter::once(&instruction).chain(tasks.iter().map(|t| &t.content));
+    let marker_rewrites = parsed_markers
+        .clone()
+        .filter(|marker| marker.raw != marker.instruction)
+        .map(|marker| (marker.raw.clone(), marker.instruction.clone()))
+        .collect();
     let assembly_options = assemble_prompt::AssemblyOptions {
         todo_file_basename: Some(todo_file_basename),
         diff_branch: options.diff_branch.clone(),
+        todo_location,
+        marker_grammar: grammar.clone(),
+        verb,
+        task_group,
+        skeleton_over_lines: options.skeleton_over_lines,
+        declarations: sliced_declarations(&selection, options.whole_files),
+        focus: options.focus,
+        marker_rewrites,
+        ..Default::default()
     };
-    let assembled_prompt =
-        assemble_prompt::assemble_prompt_with_options(&selection.files, &assembly_options)
-            .context("Failed to assemble prompt")?;
+    let sections = assemble_prompt::assemble_sections(&selection.files, &assembly_options)
+        .context("Failed to assemble prompt")?;
+    let instruction_tokens =
+        budget::estimate_tokens(&assemble_prompt::closing_text(&assembly_options));
+    let (sections, token_report) = budget::fit_sections(
+        sections,
+        &selection.relevance,
+        instruction_tokens,
+        options.max_tokens,
+        |path, rendering| {
+            let excerpt_options = assemble_prompt::AssemblyOptions {
+                renderings: [(path.to_path_buf(), rendering)].into(),
+                ..assembly_options.clone()
+            };
+            Ok(assemble_prompt::assemble_sections(&[path.to_path_buf()], &excerpt_options)?.pop())
+        },
+    )?;
+    let found_files: Vec<PathBuf> = token_report
+        .files
+        .iter()
+        .filter(|file| file.action != BudgetAction::Dropped)
+        .map(|file| file.path.clone())
+        .collect();
+    let assembled_prompt = assemble_prompt::join_sections(&sections, &assembly_options);
 
     let diff_enabled = options.diff_branch.is_some();
 
-    let final_prompt = post_processing::scrub_extra_todo_markers(
+    let mut primary_markers: Vec<&str> = Vec::new();
+    for marker in parsed_markers {
+        let line = marker.instruction.trim();
+        if !primary_markers.contains(&line) {
+            primary_markers.push(line);
+        }
+    }
+    let final_prompt = post_processing::scrub_extra_todo_markers_keeping(
         &assembled_prompt,
         diff_enabled,
-        instruction_content.trim(),
+        &primary_markers,
+        &grammar,
     )?;
 
-    crate::prompt_validation::validate_marker_count(&final_prompt, diff_enabled)?;
+    crate::prompt_validation::validate_task_marker_count_with_grammar(
+        &final_prompt,
+        diff_enabled,
+        tasks.len().max(1),
+        &grammar,
+    )?;
 
     Ok(GeneratePromptOutput {
         final_prompt,
-        instruction_content: instruction_content.trim().to_string(),
+        instruction_content,
         search_root: search_root_path,
-        found_files: selection.files,
+        found_files,
         types_found: selection.types_found,
+        verb,
+        tasks,
+        token_report,
+    })
+}
+
+/// The declarations to show of each file selected only as a definition; a
+/// file that is also a task file, or was asked for by name, stays whole.
+fn sliced_declarations(
+    selection: &file_selector::FileSelectionResult,
+    whole_files: bool,
+) -> HashMap<PathBuf, Vec<Range<usize>>> {
+    if whole_files {
+        return HashMap::new();
+    }
+    selection
+        .declarations
+        .iter()
+        .filter(|(path, _)| {
+            matches!(
+                selection.relevance.get(*path),
+                Some(file_selector::Relevance::Definition { .. })
+            )
+        })
+        .map(|(path, lines)| (path.clone(), lines.clone()))
+        .collect()
+}
+
+/// The closing-instruction entry for one task of a group, e.g. a TODO "in
+/// `login()` at Login.swift:12".
+fn summarize_task(task: &TaskMarker) -> assemble_prompt::TaskSummary {
+    let file = task
+        .path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_string();
+    assemble_prompt::TaskSummary {
+        verb: task.content.verb,
+        task: task.content.task.clone(),
+        location: describe_line(&task.path, &file, task.line),
+        file,
+    }
+}
+
+/// Where 1-based `line` of a file sits, e.g. "in `login()` at Login.swift:12",
+/// or "at Login.swift:12" when no enclosing scope is known.
+fn describe_line(path: &Path, basename: &str, line: usize) -> String {
+    let scope = fs::read_to_string(path).ok().and_then(|content| {
+        let offset: usize = content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let extension = path.extension().and_then(|s| s.to_str())?;
+        lang_support::for_extension(extension)?.enclosing_scope(&content, offset)
+    });
+    match scope {
+        Some(scope) => format!("in `{}` at {}:{}", scope, basename, line),
+        None => format!("at {}:{}", basename, line),
+    }
+}
+
+/// Describes where the marker sits for the closing instruction, e.g.
+/// "the TODO is in `Outer.Inner.reload()` at Foo.swift:120", or just
+/// "the TEST is at Foo.swift:120" when no enclosing scope is known.
+fn describe_todo_location(
+    file_path: &Path,
+    basename: &str,
+    grammar: &MarkerGrammar,
+    verb: MarkerVerb,
+) -> Option<String> {
+    let content = fs::read_to_string(file_path).ok()?;
+    let line = grammar.todo_index(&content)? + 1;
+
+    let scope =
+        match extract_enclosing_type::extract_enclosing_scope_with_grammar(file_path, grammar) {
+            Ok(scope) => scope,
+            Err(err) => {
+                debug!("No enclosing scope for {}: {}", file_path.display(), err);
+                None
+            }
+        };
+    Some(match scope {
+        Some(scope) => format!("the {} is in `{}` at {}:{}", verb, scope, basename, line),
+        None => format!("the {} is at {}:{}", verb, basename, line),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::file_selector::Relevance;
     use std::env;
     use std::fs::File;
     use std::io::Write;
@@ -120,6 +472,53 @@ mod tests {
         file_path
     }
 
+    #[test]
+    fn test_final_instruction_names_todo_scope_and_line() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "struct Outer {\n    class Inner {\n        func reload() {\n            // TODO: - Refresh\n        }\n    }\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                singular: true,
+                ..Default::default()
+            },
+        )
+        .expect("prompt generation failed");
+
+        assert!(output
+            .final_prompt
+            .trim_end()
+            .ends_with("For reference, the TODO is in `Outer.Inner.reload()` at Feed.swift:4."));
+    }
+
+    #[test]
+    fn test_todo_location_without_scope_names_only_the_line() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let file = write_temp_file(
+            temp_dir.path(),
+            "main.js",
+            "const a = 1;\n// TODO: - Do it\n",
+        );
+
+        assert_eq!(
+            describe_todo_location(
+                &file,
+                "main.js",
+                &MarkerGrammar::default(),
+                MarkerVerb::Todo
+            )
+            .as_deref(),
+            Some("the TODO is at main.js:2")
+        );
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_generate_prompt_with_options_targeted_ignores_env() {
@@ -160,6 +559,7 @@ func testFunction() {
                 excludes: vec![],
                 diff_branch: None,
                 targeted: true,
+                ..Default::default()
             },
         );
 
@@ -195,6 +595,7 @@ func testFunction() {
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
         assert!(
@@ -224,6 +625,7 @@ class Dummy {}
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
         assert!(
@@ -251,17 +653,576 @@ class Dummy {}
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
 
         let err = result.expect_err("Expected non-Swift include_references to return an error");
         assert!(
             err.to_string()
-                .contains("--include-references is only supported for Swift files"),
+                .contains("--include-references is only supported for Swift, TypeScript, Kotlin, Java and Python files"),
             "Unexpected error: {err}"
         );
     }
 
+    #[test]
+    fn test_generate_prompt_include_references_for_typescript() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "feed.service.ts",
+            "export class FeedService {\n  reload(): void {\n    // TODO: - Retry\n  }\n}\n",
+        );
+        let consumer = write_temp_file(
+            temp_dir.path(),
+            "feed.component.ts",
+            "const service = new FeedService();\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                singular: true,
+                include_references: true,
+                ..Default::default()
+            },
+        )
+        .expect("TypeScript reference search should be supported");
+
+        assert!(output.found_files.contains(&consumer));
+        assert!(output
+            .final_prompt
+            .contains("the TODO is in `FeedService.reload()` at feed.service.ts:3"));
+    }
+
+    #[test]
+    fn test_generate_prompt_include_references_for_python() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "feed.py",
+            "class FeedService:\n    def reload(self):\n        # TODO: - Retry\n        pass\n",
+        );
+        let consumer = write_temp_file(
+            temp_dir.path(),
+            "views.py",
+            "from .feed import FeedService\n\nservice = FeedService()\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                singular: true,
+                include_references: true,
+                ..Default::default()
+            },
+        )
+        .expect("Python reference search should be supported");
+
+        assert!(output.found_files.contains(&consumer));
+        assert!(output
+            .final_prompt
+            .contains("the TODO is in `FeedService.reload()` at feed.py:3"));
+    }
+
+    #[test]
+    fn test_generate_prompt_python_marker_and_closing_instruction() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        write_temp_file(
+            temp_dir.path(),
+            "legacy.py",
+            "def old():\n    # TODO: - stray marker\n    pass\n",
+        );
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "feed.py",
+            "from .legacy import old\n\ndef reload():\n    # TODO: - Retry\n    old()\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions::default(),
+        )
+        .expect("prompt generation failed");
+
+        assert_eq!(output.instruction_content, "# TODO: - Retry");
+        assert!(output.final_prompt.contains("def old():"));
+        assert!(!output.final_prompt.contains("stray marker"));
+        assert!(output.final_prompt.contains("marked by \"# TODO: - \""));
+        assert!(output
+            .final_prompt
+            .contains("the TODO is in `reload()` at feed.py:4"));
+    }
+
+    #[test]
+    fn test_generate_prompt_with_custom_marker_token() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "struct Feed {\n    func reload() {\n        // TODO: - unrelated note\n        // AI: - Retry\n    }\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                singular: true,
+                marker_token: Some("AI: -".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("prompt generation failed");
+
+        assert_eq!(output.instruction_content, "// AI: - Retry");
+        assert!(output.final_prompt.contains("// TODO: - unrelated note"));
+        assert!(output.final_prompt.contains("marked by \"// AI: - \""));
+        assert!(output
+            .final_prompt
+            .contains("the TODO is in `Feed.reload()` at Feed.swift:4"));
+    }
+
+    #[test]
+    fn test_verbs_pick_their_context_policy() {
+        assert_eq!(
+            ContextPolicy::for_verb(MarkerVerb::Fixme),
+            ContextPolicy::default()
+        );
+        assert!(ContextPolicy::for_verb(MarkerVerb::Test).targeted);
+        assert!(ContextPolicy::for_verb(MarkerVerb::Explain).include_references);
+        assert!(!ContextPolicy::for_verb(MarkerVerb::Explain).targeted);
+    }
+
+    #[test]
+    fn test_generate_prompt_for_explain_marker() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "class Feed {\n    func reload() {\n        // EXPLAIN: - Why is this retried?\n    }\n}\n",
+        );
+        let consumer = write_temp_file(
+            temp_dir.path(),
+            "FeedView.swift",
+            "struct FeedView {\n    let feed: Feed\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                singular: true,
+                ..Default::default()
+            },
+        )
+        .expect("prompt generation failed");
+
+        assert_eq!(output.verb, MarkerVerb::Explain);
+        assert_eq!(
+            output.instruction_content,
+            "// EXPLAIN: - Why is this retried?"
+        );
+        assert!(output.found_files.contains(&consumer));
+        assert!(output.final_prompt.contains("Please don't change any code"));
+        assert!(output
+            .final_prompt
+            .contains("the EXPLAIN is in `Feed.reload()` at Feed.swift:3"));
+    }
+
+    #[test]
+    fn test_generate_prompt_for_test_marker_scrubs_other_verbs() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "main.js",
+            "function load() {\n    // TEST: - Cover the empty case\n}\n// TODO: - stray\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions::default(),
+        )
+        .expect("prompt generation failed");
+
+        assert_eq!(output.verb, MarkerVerb::Test);
+        assert!(!output.final_prompt.contains("// TODO: - stray"));
+        assert!(output
+            .final_prompt
+            .contains("Can you write tests for the function enclosing the TEST:-"));
+    }
+
+    #[test]
+    fn test_generate_prompt_for_task_group_unions_contexts() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let login = write_temp_file(
+            temp_dir.path(),
+            "Login.swift",
+            "struct Login {\n    func login() {\n        let token = Token()\n        // TODO: - #auth make login async\n    }\n}\n",
+        );
+        let session = write_temp_file(
+            temp_dir.path(),
+            "Session.swift",
+            "struct Session {\n    func refresh() {\n        let cache = Cache()\n        // FIXME: - [+Keychain] #auth expire old tokens\n    }\n}\n",
+        );
+        write_temp_file(temp_dir.path(), "Token.swift", "struct Token {}\n");
+        write_temp_file(
+            temp_dir.path(),
+            "Cache.swift",
+            "struct Cache {}\n// TODO: - #ui restyle\n",
+        );
+        write_temp_file(temp_dir.path(), "Keychain.swift", "struct Keychain {}\n");
+        write_temp_file(temp_dir.path(), "Unrelated.swift", "struct Unrelated {}\n");
+
+        let output =
+            generate_prompt_with_options(git_root, &login, &GeneratePromptOptions::default())
+                .expect("prompt generation failed");
+
+        let tasks: Vec<&Path> = output.tasks.iter().map(|t| t.path.as_path()).collect();
+        assert_eq!(tasks, vec![login.as_path(), session.as_path()]);
+        let names: Vec<String> = output
+            .found_files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        for expected in [
+            "Login.swift",
+            "Session.swift",
+            "Token.swift",
+            "Cache.swift",
+            "Keychain.swift",
+        ] {
+            assert!(
+                names.contains(&expected.to_string()),
+                "{expected} missing: {names:?}"
+            );
+        }
+        assert!(!names.contains(&"Unrelated.swift".to_string()));
+
+        let prompt = &output.final_prompt;
+        assert!(prompt.contains("        // TODO: - #auth make login async"));
+        assert!(prompt.contains("        // FIXME: - #auth expire old tokens"));
+        assert!(!prompt.contains("[+Keychain]"));
+        assert!(!prompt.contains("#ui restyle"));
+        assert!(prompt.trim_end().ends_with(
+            "1. TODO: make login async (in `Login.login()` at Login.swift:4)\n2. FIXME: expire old tokens (in `Session.refresh()` at Session.swift:4)"
+        ));
+    }
+
+    #[test]
+    fn test_generate_prompt_for_picked_line() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "struct Feed {\n    func load() {\n        // TODO: - first\n    }\n    func save() {\n        // FIXME: - second\n    }\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &file,
+            &GeneratePromptOptions {
+                singular: true,
+                todo_line: Some(6),
+                ..Default::default()
+            },
+        )
+        .expect("prompt generation failed");
+
+        assert_eq!(output.instruction_content, "// FIXME: - second");
+        assert_eq!(output.verb, MarkerVerb::Fixme);
+        assert!(!output.final_prompt.contains("// TODO: - first"));
+        assert!(output
+            .final_prompt
+            .trim_end()
+            .ends_with("the FIXME is in `Feed.save()` at Feed.swift:6."));
+    }
+
+    #[test]
+    fn test_directives_extend_options() {
+        let options = GeneratePromptOptions {
+            excludes: vec!["Old.swift".to_string()],
+            ..Default::default()
+        };
+        let merged = options.with_directives(&Directives {
+            singular: true,
+            exclude_files: vec!["Old.swift".to_string(), "Legacy.swift".to_string()],
+            include_types: vec!["Client".to_string()],
+            ..Default::default()
+        });
+
+        assert!(merged.singular);
+        assert!(!merged.include_references);
+        assert_eq!(merged.excludes, vec!["Old.swift", "Legacy.swift"]);
+        assert_eq!(merged.include_types, vec!["Client"]);
+    }
+
+    #[test]
+    fn test_generate_prompt_applies_and_strips_directives() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let client = write_temp_file(
+            temp_dir.path(),
+            "NetworkClient.swift",
+            "class NetworkClient {}\n",
+        );
+        let legacy = write_temp_file(temp_dir.path(), "Legacy.swift", "class Legacy {}\n");
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "class Feed {\n    let legacy = Legacy()\n    func load() {\n        // TODO: - [singular, exclude=Legacy.swift, +NetworkClient] make this async\n    }\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions::default(),
+        )
+        .expect("prompt generation failed");
+
+        assert_eq!(output.instruction_content, "// TODO: - make this async");
+        assert_eq!(output.found_files, vec![instruction_file, client]);
+        assert!(!output.found_files.contains(&legacy));
+        assert!(output
+            .final_prompt
+            .contains("        // TODO: - make this async\n"));
+        assert!(!output.final_prompt.contains("[singular"));
+    }
+
+    #[test]
+    fn test_generate_prompt_strips_directives_only_from_the_todo_file() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        write_temp_file(
+            temp_dir.path(),
+            "NetworkClient.swift",
+            "class NetworkClient {\n    let sample = \"// TODO: - [+NetworkClient] make this async\"\n}\n",
+        );
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "class Feed {\n    // TODO: - [+NetworkClient] make this async\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions::default(),
+        )
+        .expect("prompt generation failed");
+
+        assert!(output
+            .final_prompt
+            .contains("    // TODO: - make this async\n"));
+        // The other file's copy is only defused, its bracket kept.
+        assert!(output
+            .final_prompt
+            .contains("let sample = \"// TODO(other): [+NetworkClient] make this async\""));
+    }
+
+    #[test]
+    fn test_generate_prompt_keeps_escapes_in_source() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Lines.swift",
+            r#"let joined = parts.joined(separator: "\n")
+// TODO: - Split on "\r\n" too
+"#,
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                singular: true,
+                ..Default::default()
+            },
+        )
+        .expect("prompt generation failed");
+
+        assert!(output
+            .final_prompt
+            .contains(r#"parts.joined(separator: "\n")"#));
+        assert_eq!(
+            output.instruction_content,
+            r#"// TODO: - Split on "\r\n" too"#
+        );
+    }
+
+    #[test]
+    fn test_generate_prompt_keeps_code_on_stray_marker_lines() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        write_temp_file(
+            temp_dir.path(),
+            "Cache.swift",
+            "class Cache {\n    let store = compute() // TODO: - cache this\n    // TODO: - drop me\n}\n",
+        );
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "let cache = Cache()\n// TODO: - Use the cache\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions::default(),
+        )
+        .expect("prompt generation failed");
+
+        assert!(output
+            .final_prompt
+            .contains("    let store = compute() // TODO(other): cache this\n"));
+        assert!(!output.final_prompt.contains("drop me"));
+    }
+
+    #[test]
+    fn test_generate_prompt_fits_the_token_budget() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "struct Feed {\n    let cache: Cache\n\n    func load() {\n        // TODO: - paginate\n    }\n}\n",
+        );
+        let cache_file = write_temp_file(
+            temp_dir.path(),
+            "Cache.swift",
+            &format!(
+                "struct Cache {{\n{}}}\n",
+                "    func evict() {}\n".repeat(100)
+            ),
+        );
+        let generate = |max_tokens| {
+            generate_prompt_with_options(
+                git_root,
+                &instruction_file,
+                &GeneratePromptOptions {
+                    max_tokens,
+                    ..Default::default()
+                },
+            )
+            .expect("generate_prompt failed")
+        };
+
+        let unbounded = generate(None);
+        assert_eq!(unbounded.found_files.len(), 2);
+        let total = unbounded.token_report.total();
+        let cache_tokens = unbounded.token_report.files[0].tokens;
+        assert_eq!(unbounded.token_report.files[0].path, cache_file);
+        assert_eq!(
+            unbounded.token_report.files[0].relevance,
+            Relevance::Definition { hop: 0 }
+        );
+
+        let budgeted = generate(Some(total - 1));
+        assert_eq!(budgeted.found_files, vec![instruction_file.clone()]);
+        assert!(!budgeted.final_prompt.contains("struct Cache"));
+        assert_eq!(budgeted.token_report.total(), total - cache_tokens);
+
+        // Too small even for the TODO file: it is cut down to its function.
+        let squeezed = generate(Some(10));
+        assert!(squeezed.final_prompt.contains("    func load() {"));
+        assert!(!squeezed.final_prompt.contains("let cache: Cache"));
+        assert_eq!(
+            squeezed.token_report.files[1].action,
+            BudgetAction::Downgraded
+        );
+    }
+
+    #[test]
+    fn test_generate_prompt_shows_long_files_as_skeletons() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Feed.swift",
+            "struct Feed {\n    let cache: Cache\n\n    func load() {\n        cache.warm()\n        // TODO: - paginate\n    }\n}\n",
+        );
+        write_temp_file(
+            temp_dir.path(),
+            "Cache.swift",
+            "struct Cache {\n    func warm() {\n        preload()\n    }\n}\n",
+        );
+
+        let output = generate_prompt_with_options(
+            git_root,
+            &instruction_file,
+            &GeneratePromptOptions {
+                skeleton_over_lines: Some(4),
+                ..Default::default()
+            },
+        )
+        .expect("generate_prompt failed");
+
+        assert!(output.final_prompt.contains("    func warm() { … }"));
+        assert!(!output.final_prompt.contains("preload()"));
+        assert!(output.final_prompt.contains("        cache.warm()\n"));
+        assert_eq!(
+            output.token_report.files[0].rendering,
+            assemble_prompt::Rendering::Skeleton
+        );
+        assert_eq!(output.token_report.files[0].action, BudgetAction::Kept);
+    }
+
+    #[test]
+    fn test_generate_prompt_shows_only_the_matched_declarations() {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let git_root = temp_dir.path().to_str().unwrap();
+        let instruction_file = write_temp_file(
+            temp_dir.path(),
+            "Profile.swift",
+            "struct Profile {\n    let user: User\n    // TODO: - show the name\n}\n",
+        );
+        write_temp_file(
+            temp_dir.path(),
+            "Models.swift",
+            "import Foundation\n\nstruct Invoice {\n    let total: Int\n}\n\nstruct User {\n    let name: String\n}\n",
+        );
+
+        let generate = |whole_files| {
+            generate_prompt_with_options(
+                git_root,
+                &instruction_file,
+                &GeneratePromptOptions {
+                    whole_files,
+                    ..Default::default()
+                },
+            )
+            .expect("generate_prompt failed")
+        };
+
+        let sliced = generate(false);
+        assert!(sliced
+            .final_prompt
+            .contains("import Foundation\n\n\n// ...\n\nstruct User {\n    let name: String\n}\n"));
+        assert!(!sliced.final_prompt.contains("Invoice"));
+        assert_eq!(
+            sliced.token_report.files[0].rendering,
+            assemble_prompt::Rendering::Declarations
+        );
+        // The TODO file is never sliced.
+        assert!(sliced
+            .final_prompt
+            .contains("struct Profile {\n    let user: User\n"));
+
+        assert!(generate(true).final_prompt.contains("struct Invoice {"));
+    }
+
     #[test]
     fn test_generate_prompt_force_global() {
         let temp_dir = tempdir().expect("Failed to create temp dir");
@@ -283,6 +1244,7 @@ class Dummy {}
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
         assert!(
@@ -312,6 +1274,7 @@ class Dummy {}
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
         assert!(
@@ -368,6 +1331,7 @@ mod enclosing_block_pipeline_characterization_tests {
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
 
@@ -423,6 +1387,7 @@ mod enclosing_block_pipeline_characterization_tests {
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
 
@@ -472,6 +1437,7 @@ mod enclosing_block_pipeline_characterization_tests {
                 excludes: vec![],
                 diff_branch: None,
                 targeted: false,
+                ..Default::default()
             },
         );
 
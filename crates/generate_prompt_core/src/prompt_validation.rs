@@ -1,12 +1,20 @@
 // crates/generate_prompt_core/src/prompt_validation.rs
 
 use anyhow::{anyhow, Result};
-use todo_marker::TODO_MARKER;
+use todo_marker::MarkerGrammar;
 
 /// Validates that the given prompt contains the correct number of markers.
 ///
-/// * When `diff_enabled` is **true**, the prompt must have **2 or 3** marker lines.
-/// * When `diff_enabled` is **false**, the prompt must have **exactly 2** marker lines.
+/// * When `diff_enabled` is **false**, the prompt must have **exactly 2**
+///   markers: the TODO itself and the closing instruction.
+/// * When `diff_enabled` is **true**, the TODO may also show up in the diff,
+///   so **2 or 3** markers are accepted; a task group of `n` tasks accepts
+///   anything from `n + 1` to `2n + 1` (see
+///   [`validate_task_marker_count_with_grammar`]).
+///
+/// Markers are counted one by one, so code lines that still carry a marker
+/// count too; stray markers neutralised by the scrubber (`// TODO(other):`)
+/// do not.
 ///
 /// # Arguments
 ///
@@ -18,26 +26,51 @@ use todo_marker::TODO_MARKER;
 /// * `Ok(())` if the marker count is as expected.
 /// * `Err` with an explanatory message if the count is wrong.
 pub fn validate_marker_count(prompt: &str, diff_enabled: bool) -> Result<()> {
-    let marker = TODO_MARKER;
+    validate_marker_count_with_grammar(prompt, diff_enabled, &MarkerGrammar::default())
+}
 
-    let marker_lines: Vec<&str> = prompt
-        .lines()
-        .filter(|line| line.contains(marker))
-        .collect();
+/// Like [`validate_marker_count`], counting markers of `grammar`'s tokens in
+/// any comment syntax.
+pub fn validate_marker_count_with_grammar(
+    prompt: &str,
+    diff_enabled: bool,
+    grammar: &MarkerGrammar,
+) -> Result<()> {
+    validate_task_marker_count_with_grammar(prompt, diff_enabled, 1, grammar)
+}
+
+/// Like [`validate_marker_count_with_grammar`], for a prompt covering
+/// `task_count` markers of one task group: one marker per task plus the
+/// closing instruction.  With diff enabled each task's marker may also
+/// appear in its own file's diff, so up to `task_count` more are allowed.
+pub fn validate_task_marker_count_with_grammar(
+    prompt: &str,
+    diff_enabled: bool,
+    task_count: usize,
+    grammar: &MarkerGrammar,
+) -> Result<()> {
+    let marker = grammar.marker();
+    let expected = task_count + 1;
 
-    let count = marker_lines.len();
+    let count: usize = prompt
+        .lines()
+        .map(|line| grammar.count_markers_in_any_syntax(line))
+        .sum();
 
     if diff_enabled {
-        if count != 2 && count != 3 {
+        if !(expected..=expected + task_count).contains(&count) {
             return Err(anyhow!(
-                "Expected 2 or 3 {} markers (with diff enabled), but found {}.",
+                "Expected {} to {} {} markers (with diff enabled), but found {}.",
+                expected,
+                expected + task_count,
                 marker,
                 count
             ));
         }
-    } else if count != 2 {
+    } else if count != expected {
         return Err(anyhow!(
-            "Expected exactly 2 {} markers, but found {}.",
+            "Expected exactly {} {} markers, but found {}.",
+            expected,
             marker,
             count
         ));
@@ -79,4 +112,59 @@ mod tests {
         let prompt = "// TODO: -\nSome code here";
         assert!(validate_marker_count(prompt, true).is_err());
     }
+
+    #[test]
+    fn test_validate_marker_count_counts_markers_not_lines() {
+        let prompt = "let x = 1 // TODO: - a // TODO: - b\nclosing \"// TODO: - \"";
+        assert!(validate_marker_count(prompt, false).is_err());
+
+        let prompt = "// TODO: - a\nlet y = 2 // TODO(other): b\nclosing \"// TODO: - \"";
+        assert!(validate_marker_count(prompt, false).is_ok());
+    }
+
+    #[test]
+    fn test_validate_marker_count_with_hash_grammar() {
+        let grammar = MarkerGrammar::HASH;
+        let prompt = "# TODO: - Cache it\nSome code here\nmarked by \"# TODO: - \"";
+        assert!(validate_marker_count_with_grammar(prompt, false, &grammar).is_ok());
+
+        let err =
+            validate_marker_count_with_grammar("# TODO: - Cache it", false, &grammar).unwrap_err();
+        assert!(err.to_string().contains("2 # TODO: - markers"));
+    }
+
+    #[test]
+    fn test_validate_task_marker_count_expects_one_marker_per_task() {
+        let grammar = MarkerGrammar::default();
+        let prompt = "// TODO: - #auth a
+# FIXME: - #auth b
+marked by \"// TODO: - #auth\"";
+        assert!(validate_task_marker_count_with_grammar(prompt, false, 2, &grammar).is_ok());
+
+        let err = validate_task_marker_count_with_grammar(prompt, false, 3, &grammar).unwrap_err();
+        assert!(err.to_string().contains("Expected exactly 4"));
+        assert!(validate_task_marker_count_with_grammar(prompt, true, 2, &grammar).is_ok());
+    }
+
+    #[test]
+    fn test_validate_task_marker_count_allows_each_task_in_the_diff() {
+        let grammar = MarkerGrammar::default();
+        let prompt = "// TODO: - #auth a
+// FIXME: - #auth b
++    // TODO: - #auth a
++    // FIXME: - #auth b
+marked by \"// TODO: - #auth\"";
+        assert!(validate_task_marker_count_with_grammar(prompt, true, 2, &grammar).is_ok());
+
+        let err = validate_task_marker_count_with_grammar(prompt, false, 2, &grammar).unwrap_err();
+        assert!(err.to_string().contains("Expected exactly 3"));
+        let err = validate_task_marker_count_with_grammar(
+            &format!("{prompt}\n// TODO: - c"),
+            true,
+            2,
+            &grammar,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Expected 3 to 5"));
+    }
 }
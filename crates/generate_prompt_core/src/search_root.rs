@@ -1,6 +1,7 @@
 // crates/generate_prompt_core/src/search_root.rs
 
-use get_search_roots::get_search_roots;
+use get_search_roots::get_search_roots_with_options;
+use lang_support::WalkOptions;
 use std::path::{Path, PathBuf};
 
 /// Determines the search root directory for the prompt generation.
@@ -17,8 +18,18 @@ use std::path::{Path, PathBuf};
 ///
 /// The chosen search root as a `PathBuf`.
 pub fn determine_search_root(base_dir: &Path, file_path: &Path) -> PathBuf {
-    let candidate_roots =
-        get_search_roots(base_dir).unwrap_or_else(|_| vec![base_dir.to_path_buf()]);
+    determine_search_root_with_options(base_dir, file_path, &WalkOptions::default())
+}
+
+/// Like [`determine_search_root`], ignoring packages that source walks
+/// under `walk` would skip.
+pub fn determine_search_root_with_options(
+    base_dir: &Path,
+    file_path: &Path,
+    walk: &WalkOptions,
+) -> PathBuf {
+    let candidate_roots = get_search_roots_with_options(base_dir, walk)
+        .unwrap_or_else(|_| vec![base_dir.to_path_buf()]);
     if candidate_roots.len() == 1 {
         candidate_roots[0].clone()
     } else {
@@ -1,15 +1,21 @@
 // crates/get_search_roots/src/lib.rs
 
 use anyhow::{anyhow, Result};
+use lang_support::{walk_files, WalkOptions};
 use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
 /// Returns a list of directories that are potential Swift package roots.
 /// - If the provided root itself contains a "Package.swift", returns just that directory.
 /// - Otherwise, returns the provided root (if its basename isn't ".build") along with
 ///   any subdirectories (excluding those under any ".build" directories) that contain "Package.swift".
 pub fn get_search_roots(root: &Path) -> Result<Vec<PathBuf>> {
+    get_search_roots_with_options(root, &WalkOptions::default())
+}
+
+/// Like [`get_search_roots`], skipping the directories and ignore-file
+/// entries that `options` skip in source walks.
+pub fn get_search_roots_with_options(root: &Path, options: &WalkOptions) -> Result<Vec<PathBuf>> {
     if !root.exists() || !root.is_dir() {
         return Err(anyhow!(
             "Error: '{}' is not a valid directory.",
@@ -27,16 +33,9 @@ pub fn get_search_roots(root: &Path) -> Result<Vec<PathBuf>> {
         found_dirs.insert(root.to_path_buf());
     }
 
-    for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
-        if entry.file_type().is_file() && entry.file_name() == "Package.swift" {
-            if entry
-                .path()
-                .components()
-                .any(|comp| comp.as_os_str() == ".build")
-            {
-                continue;
-            }
-            if let Some(parent) = entry.path().parent() {
+    for path in walk_files(root, options) {
+        if path.file_name().is_some_and(|name| name == "Package.swift") {
+            if let Some(parent) = path.parent() {
                 found_dirs.insert(parent.to_path_buf());
             }
         }
@@ -145,6 +144,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_skips_ignored_packages() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repo_path = tmp_dir.path();
+        for dir in ["Carthage/Checkouts/Dep", "Fixtures/Sample", "Core"] {
+            fs::create_dir_all(repo_path.join(dir)).unwrap();
+            File::create(repo_path.join(dir).join("Package.swift")).unwrap();
+        }
+        fs::write(repo_path.join(".promptignore"), "Fixtures/\n").unwrap();
+
+        let roots = get_search_roots(repo_path).unwrap();
+        assert_eq!(roots, vec![repo_path.to_path_buf(), repo_path.join("Core")]);
+    }
+
     #[test]
     fn test_invalid_directory() {
         let non_existent = Path::new("non_existent_directory");
@@ -0,0 +1,278 @@
+// crates/index_cache/src/lib.rs
+
+//! An optional on-disk cache of what each source file declares, which words
+//! it uses and which of its lines are markers, so repeated runs over a large
+//! tree only re-read the files that changed.
+//!
+//! Records are kept as JSON under `.git/auto-prompt/`.  A record is reused
+//! while its file has the size and mtime it had when indexed; when only the
+//! mtime differs, a matching content hash still reuses it.
+
+mod store;
+
+pub use store::StoreStats;
+
+use anyhow::{Context, Result};
+use lang_support::{FileSymbols, SourceFile, SymbolIndex};
+use log::warn;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use store::{content_hash, Store};
+
+const SYMBOLS_FILE: &str = "symbols.json";
+const MARKERS_PREFIX: &str = "markers";
+
+/// The cache directory of one repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexCache {
+    dir: PathBuf,
+}
+
+/// What the cache holds, for `generate_prompt index --stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStats {
+    pub dir: PathBuf,
+    pub symbols: StoreStats,
+    /// One entry per marker token the cache has been used with.
+    pub markers: Vec<MarkerStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerStats {
+    pub file_name: String,
+    pub store: StoreStats,
+    /// How many of the indexed files hold at least one marker.
+    pub files_with_markers: usize,
+}
+
+impl IndexCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache of the repository at `git_root`, in `.git/auto-prompt`.
+    pub fn in_git_dir(git_root: &Path) -> Self {
+        Self::new(git_root.join(".git").join("auto-prompt"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether the cache directory has been created, e.g. by
+    /// `generate_prompt index --rebuild`.
+    pub fn exists(&self) -> bool {
+        self.dir.is_dir()
+    }
+
+    /// A symbol index over `sources`, taking each file's declarations and
+    /// words from the cache while they are fresh and storing the rest.
+    pub fn symbol_index(&self, sources: Vec<SourceFile>) -> SymbolIndex {
+        let mut store = Store::load(self.dir.join(SYMBOLS_FILE));
+        let symbols = store.refresh(&sources, FileSymbols::scan);
+        save(&store);
+        SymbolIndex::with_symbols(sources, symbols)
+    }
+
+    /// The 1-based marker lines of each of `sources`, in order, with `find`
+    /// run only on files that changed since they were indexed.  Lines are
+    /// kept per marker token, as another token finds other lines.
+    pub fn marker_lines(
+        &self,
+        sources: &[SourceFile],
+        marker_token: Option<&str>,
+        find: impl Fn(&SourceFile) -> Vec<usize> + Sync,
+    ) -> Vec<Vec<usize>> {
+        let mut store = Store::load(self.dir.join(markers_file(marker_token)));
+        let lines = store.refresh(sources, find);
+        save(&store);
+        lines
+    }
+
+    pub fn stats(&self) -> IndexStats {
+        let symbols = Store::<FileSymbols>::load(self.dir.join(SYMBOLS_FILE)).stats();
+
+        let mut marker_files: Vec<String> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with(MARKERS_PREFIX) && name.ends_with(".json"))
+            .collect();
+        marker_files.sort();
+        let markers = marker_files
+            .into_iter()
+            .map(|file_name| {
+                let store = Store::<Vec<usize>>::load(self.dir.join(&file_name));
+                MarkerStats {
+                    store: store.stats(),
+                    files_with_markers: store.records().filter(|lines| !lines.is_empty()).count(),
+                    file_name,
+                }
+            })
+            .collect();
+
+        IndexStats {
+            dir: self.dir.clone(),
+            symbols,
+            markers,
+        }
+    }
+
+    /// Drops every record, leaving an empty cache directory behind.
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)
+                .with_context(|| format!("Failed to remove {}", self.dir.display()))?;
+        }
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))
+    }
+}
+
+/// `markers.json` for the default token, a file per custom token otherwise.
+fn markers_file(marker_token: Option<&str>) -> String {
+    match marker_token {
+        None => format!("{}.json", MARKERS_PREFIX),
+        Some(token) => format!("{}-{:016x}.json", MARKERS_PREFIX, content_hash(token)),
+    }
+}
+
+/// A cache that cannot be written only costs the next run its speed-up.
+fn save<T>(store: &Store<T>)
+where
+    T: Clone + Send + Sync + serde::Serialize + serde::de::DeserializeOwned,
+{
+    if let Err(err) = store.save() {
+        warn!("Failed to update the index cache: {:#}", err);
+    }
+}
+
+impl fmt::Display for IndexStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Index: {}", self.dir.display())?;
+        writeln!(
+            f,
+            "  {}: {} file(s), {} stale, {} KiB",
+            SYMBOLS_FILE,
+            self.symbols.files,
+            self.symbols.stale,
+            self.symbols.bytes / 1024
+        )?;
+        for markers in &self.markers {
+            writeln!(
+                f,
+                "  {}: {} file(s), {} with markers, {} stale, {} KiB",
+                markers.file_name,
+                markers.store.files,
+                markers.files_with_markers,
+                markers.store.stale,
+                markers.store.bytes / 1024
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::{set_file_mtime, FileTime};
+    use lang_support::walk_source_files;
+    use std::collections::BTreeSet;
+    use tempfile::tempdir;
+
+    fn write(path: &Path, content: &str, mtime: i64) {
+        fs::write(path, content).unwrap();
+        set_file_mtime(path, FileTime::from_unix_time(mtime, 0)).unwrap();
+    }
+
+    fn defining(cache: &IndexCache, root: &Path, name: &str) -> BTreeSet<PathBuf> {
+        cache
+            .symbol_index(walk_source_files(root))
+            .files_defining(&[name.to_string()])
+    }
+
+    #[test]
+    fn records_are_reused_until_size_or_content_change() {
+        let repo = tempdir().unwrap();
+        let src = repo.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        let feed = src.join("Feed.swift");
+        write(&feed, "struct Feed {}\n", 1000);
+        let cache = IndexCache::in_git_dir(repo.path());
+
+        assert_eq!(
+            defining(&cache, &src, "Feed"),
+            BTreeSet::from([feed.clone()])
+        );
+        assert!(cache.dir().join(SYMBOLS_FILE).is_file());
+
+        // Same size and mtime: the record is trusted without reading the file.
+        write(&feed, "struct Fede {}\n", 1000);
+        assert_eq!(
+            defining(&cache, &src, "Feed"),
+            BTreeSet::from([feed.clone()])
+        );
+
+        // A new mtime makes the hash decide, and the content now differs.
+        write(&feed, "struct Fede {}\n", 2000);
+        assert!(defining(&cache, &src, "Feed").is_empty());
+        assert_eq!(
+            defining(&cache, &src, "Fede"),
+            BTreeSet::from([feed.clone()])
+        );
+
+        // Touched but unchanged: the hash matches and the mtime is updated.
+        write(&feed, "struct Fede {}\n", 3000);
+        assert_eq!(defining(&cache, &src, "Fede"), BTreeSet::from([feed]));
+        assert_eq!(cache.stats().symbols.stale, 0);
+    }
+
+    #[test]
+    fn marker_lines_are_kept_per_token() {
+        let repo = tempdir().unwrap();
+        let path = repo.path().join("Feed.swift");
+        write(&path, "// TODO: - a\n// AI: - b\n", 1000);
+        let cache = IndexCache::in_git_dir(repo.path());
+        let sources = walk_source_files(repo.path());
+
+        let find = |token: &'static str| {
+            move |source: &SourceFile| -> Vec<usize> {
+                source
+                    .content()
+                    .lines()
+                    .enumerate()
+                    .filter(|(_, line)| line.contains(token))
+                    .map(|(i, _)| i + 1)
+                    .collect()
+            }
+        };
+        assert_eq!(
+            cache.marker_lines(&sources, None, find("TODO")),
+            vec![vec![1]]
+        );
+        assert_eq!(
+            cache.marker_lines(&sources, Some("AI: -"), find("AI")),
+            vec![vec![2]]
+        );
+        // Fresh records are returned without running `find`.
+        assert_eq!(
+            cache.marker_lines(&sources, None, |_| unreachable!()),
+            vec![vec![1]]
+        );
+
+        let stats = cache.stats();
+        assert_eq!(stats.markers.len(), 2);
+        assert!(stats.markers.iter().all(|m| m.files_with_markers == 1));
+        assert!(stats.markers[0].file_name.starts_with("markers-"));
+        assert!(stats
+            .to_string()
+            .contains("markers.json: 1 file(s), 1 with markers"));
+
+        cache.clear().unwrap();
+        assert!(cache.exists());
+        assert_eq!(cache.stats().symbols, StoreStats::default());
+        assert!(cache.stats().markers.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+// crates/index_cache/src/store.rs
+
+//! One cache file: a record per source file, keyed by path and stamped with
+//! the size, mtime and content hash of the file it was computed from.
+
+use anyhow::{Context, Result};
+use lang_support::SourceFile;
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever a record's shape or meaning changes; files of another
+/// version are discarded on load.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry<T> {
+    size: u64,
+    /// Nanoseconds since the Unix epoch.
+    mtime: u64,
+    hash: u64,
+    data: T,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stored<E> {
+    version: u32,
+    entries: E,
+}
+
+/// How many records a cache file holds, and how many of them no longer
+/// match their file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    pub files: usize,
+    /// Records whose file changed size or mtime, or is gone.
+    pub stale: usize,
+    /// Size of the cache file on disk.
+    pub bytes: u64,
+}
+
+pub(crate) struct Store<T> {
+    path: PathBuf,
+    entries: HashMap<PathBuf, Entry<T>>,
+    dirty: bool,
+}
+
+impl<T> Store<T>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned,
+{
+    /// Reads the cache file at `path`.  A missing, unreadable or outdated
+    /// file loads as an empty store.
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let entries = fs::read(&path)
+            .ok()
+            .and_then(|bytes| {
+                serde_json::from_slice::<Stored<HashMap<PathBuf, Entry<T>>>>(&bytes).ok()
+            })
+            .filter(|stored| stored.version == FORMAT_VERSION)
+            .map(|stored| stored.entries)
+            .unwrap_or_default();
+        Self {
+            path,
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// The record of each of `sources`, in order.  Records of unchanged
+    /// files are reused; the rest are computed with `compute` and stored.
+    pub(crate) fn refresh(
+        &mut self,
+        sources: &[SourceFile],
+        compute: impl Fn(&SourceFile) -> T + Sync,
+    ) -> Vec<T> {
+        let resolved: Vec<(T, Option<Entry<T>>)> = sources
+            .par_iter()
+            .map(|source| self.resolve(source, &compute))
+            .collect();
+
+        let mut records = Vec::with_capacity(resolved.len());
+        for (source, (data, update)) in sources.iter().zip(resolved) {
+            if let Some(entry) = update {
+                self.entries.insert(source.path.clone(), entry);
+                self.dirty = true;
+            }
+            records.push(data);
+        }
+        records
+    }
+
+    /// The record for `source`, and the entry to store for it when the
+    /// stored one is missing or out of date.
+    fn resolve(
+        &self,
+        source: &SourceFile,
+        compute: &(impl Fn(&SourceFile) -> T + Sync),
+    ) -> (T, Option<Entry<T>>) {
+        let Some((size, mtime)) = stamp(&source.path) else {
+            return (compute(source), None);
+        };
+        let cached = self.entries.get(&source.path).filter(|e| e.size == size);
+        if let Some(entry) = cached.filter(|e| e.mtime == mtime) {
+            return (entry.data.clone(), None);
+        }
+
+        // Only the mtime moved, as after a checkout or a touch: the content
+        // may be what was indexed.
+        let hash = content_hash(source.content());
+        if let Some(entry) = cached.filter(|e| e.hash == hash) {
+            let touched = Entry {
+                mtime,
+                ..entry.clone()
+            };
+            return (entry.data.clone(), Some(touched));
+        }
+
+        let data = compute(source);
+        let entry = Entry {
+            size,
+            mtime,
+            hash,
+            data: data.clone(),
+        };
+        (data, Some(entry))
+    }
+
+    /// Writes the store back if anything changed, through a temporary file
+    /// so a concurrent run never reads half a cache.
+    pub(crate) fn save(&self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let stored = Stored {
+            version: FORMAT_VERSION,
+            entries: &self.entries,
+        };
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&stored)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to replace {}", self.path.display()))
+    }
+
+    pub(crate) fn records(&self) -> impl Iterator<Item = &T> {
+        self.entries.values().map(|entry| &entry.data)
+    }
+
+    pub(crate) fn stats(&self) -> StoreStats {
+        StoreStats {
+            files: self.entries.len(),
+            stale: self
+                .entries
+                .iter()
+                .filter(|(path, e)| stamp(path) != Some((e.size, e.mtime)))
+                .count(),
+            bytes: fs::metadata(&self.path).map_or(0, |meta| meta.len()),
+        }
+    }
+}
+
+/// A file's size and mtime, or `None` when it cannot be stat'ed.
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((meta.len(), mtime.as_nanos() as u64))
+}
+
+/// 64-bit FNV-1a: stable across runs and toolchains, unlike `std`'s
+/// hasher, which is what a hash kept on disk needs.
+pub(crate) fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
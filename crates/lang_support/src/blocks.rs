@@ -0,0 +1,598 @@
+// crates/lang_support/src/blocks.rs
+
+//! Comment‑ and string‑aware block matching.
+//!
+//! Block extraction (the "enclosing function context" and targeted type
+//! extraction) used to count every `{` and `}` character, so a brace inside
+//! a string, regex literal or comment threw the whole block off.  Here a
+//! small lexer walks the source once, skipping comments and literals as
+//! described by the language's [`LexicalRules`], and records only the
+//! structural braces.  Indentation-delimited languages (Python) get the same
+//! [`BlockMap`], built from `:`-terminated headers and their indented bodies.
+
+/// A string interpolation whose contents are code again, e.g. Swift's
+/// `"\(value)"` or a JavaScript template's `` `${value}` ``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interpolation {
+    /// Quote character of the literals that support interpolation.
+    pub quote: u8,
+    /// Sequence that opens the interpolated code.
+    pub open: &'static str,
+    /// Bracket that closes it once all nested brackets are balanced.
+    pub close: u8,
+}
+
+/// The lexical conventions a block scanner must know to skip comments and
+/// literals.  All delimiters are ASCII, so the scanner works on bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LexicalRules {
+    /// Line comment introducers, e.g. `//`.
+    pub line_comments: &'static [&'static str],
+    /// Block comment delimiters, e.g. `/*` … `*/`.
+    pub block_comment: Option<(&'static str, &'static str)>,
+    /// Block comments nest (Swift) rather than ending at the first
+    /// terminator (C).
+    pub nested_block_comments: bool,
+    /// Characters that open a string or character literal closed by the
+    /// same character, with backslash escapes.  Backtick literals may span
+    /// lines; others end at the line break if left unterminated.
+    pub quotes: &'static [u8],
+    /// Tripling a quote character opens a multi-line literal (`"""`).
+    pub triple_quoted_strings: bool,
+    /// `#"…"#` raw strings, where any number of `#` may wrap the literal.
+    pub raw_hash_strings: bool,
+    /// Interpolated code inside string literals.
+    pub interpolation: Option<Interpolation>,
+    /// `/…/` regular expression literals, recognised where an operand is
+    /// expected.
+    pub regex_literals: bool,
+}
+
+impl LexicalRules {
+    /// C‑family defaults: `//` and `/* */` comments, `"` strings and `'`
+    /// character literals.
+    pub const C_LIKE: LexicalRules = LexicalRules {
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        nested_block_comments: false,
+        quotes: b"\"'",
+        triple_quoted_strings: false,
+        raw_hash_strings: false,
+        interpolation: None,
+        regex_literals: false,
+    };
+}
+
+impl Default for LexicalRules {
+    fn default() -> Self {
+        Self::C_LIKE
+    }
+}
+
+/// How a language delimits its blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockDelimiting {
+    /// `{` … `}` pairs.
+    #[default]
+    Braces,
+    /// A header ending in `:` followed by more deeply indented lines.
+    Indentation,
+}
+
+/// Structural blocks of a source text, paired up.  For brace-delimited
+/// sources a pair is a `{` and its `}`; for indentation-delimited ones it is
+/// the header's `:` and the line break (or end of text) after the body's
+/// last line, so in both cases the body lies strictly between the two.
+#[derive(Debug, Clone, Default)]
+pub struct BlockMap {
+    /// `(open, close)` byte offsets of every balanced pair, ordered by the
+    /// opening brace.
+    pairs: Vec<(usize, usize)>,
+    /// Opening braces that are never closed.
+    unclosed: Vec<usize>,
+}
+
+impl BlockMap {
+    /// Lexes `src` once with `rules` and pairs up its structural braces.
+    pub fn new(src: &str, rules: &LexicalRules) -> Self {
+        let mut scanner = Scanner::new(src, rules);
+        scanner.code(None, true);
+
+        let mut map = BlockMap::default();
+        let mut stack: Vec<usize> = Vec::new();
+        for (offset, open) in scanner.braces {
+            if open {
+                stack.push(offset);
+            } else if let Some(start) = stack.pop() {
+                map.pairs.push((start, offset));
+            }
+        }
+        map.unclosed = stack;
+        map.pairs.sort_unstable();
+        map
+    }
+
+    /// Lexes `src` once with `rules` and pairs every `:`-terminated header
+    /// line with the end of the more deeply indented lines below it.  Blank
+    /// lines, comments and the continuation lines of bracketed expressions
+    /// do not end a body.
+    pub fn indented(src: &str, rules: &LexicalRules) -> Self {
+        let blanked = blank_comments_and_literals(src, rules);
+        let mut map = BlockMap::default();
+        // Open headers as `(indent, colon offset)`, innermost last.
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        // End of the last non-blank line seen so far.
+        let mut last_end = 0;
+        // Indentation and bracket depth of the logical line being read.
+        let mut logical: Option<usize> = None;
+        let mut depth = 0usize;
+
+        let mut line_start = 0;
+        for line in blanked.split_inclusive('\n') {
+            let text = line.trim_end();
+            let line_end = line_start + line.trim_end_matches(['\n', '\r']).len();
+            if !text.trim_start().is_empty() {
+                let indent = *logical.get_or_insert_with(|| {
+                    let indent = text.len() - text.trim_start().len();
+                    while open.last().is_some_and(|&(outer, _)| outer >= indent) {
+                        let (_, colon) = open.pop().expect("checked above");
+                        close_indented(&mut map, colon, last_end);
+                    }
+                    indent
+                });
+
+                for byte in text.bytes() {
+                    match byte {
+                        b'(' | b'[' | b'{' => depth += 1,
+                        b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+                last_end = line_end;
+
+                if depth == 0 && !text.ends_with('\\') {
+                    if text.ends_with(':') {
+                        open.push((indent, line_start + text.len() - 1));
+                    }
+                    logical = None;
+                }
+            }
+            line_start += line.len();
+        }
+        while let Some((_, colon)) = open.pop() {
+            close_indented(&mut map, colon, last_end);
+        }
+
+        map.pairs.sort_unstable();
+        map
+    }
+
+    /// Every balanced block, ordered by its opening offset, as the byte
+    /// offsets of its `{` and `}`.
+    pub fn pairs(&self) -> &[(usize, usize)] {
+        &self.pairs
+    }
+
+    /// Balanced blocks whose braces surround `offset`, outermost first, as
+    /// the byte offsets of their `{` and `}`.
+    pub fn containing(&self, offset: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.pairs
+            .iter()
+            .filter(move |(open, close)| *open < offset && offset < *close)
+            .copied()
+    }
+
+    /// The innermost balanced block whose braces surround `offset`, as the
+    /// byte offsets of its `{` and `}`.
+    pub fn innermost_containing(&self, offset: usize) -> Option<(usize, usize)> {
+        self.containing(offset).last()
+    }
+
+    /// The first block opened at or after `from`, as the byte offsets of its
+    /// `{` and matching `}`.  The closing offset is `None` when the block is
+    /// never closed.
+    pub fn first_from(&self, from: usize) -> Option<(usize, Option<usize>)> {
+        let closed = self
+            .pairs
+            .iter()
+            .find(|(open, _)| *open >= from)
+            .map(|&(open, close)| (open, Some(close)));
+        let unclosed = self
+            .unclosed
+            .iter()
+            .find(|open| **open >= from)
+            .map(|&open| (open, None));
+
+        match (closed, unclosed) {
+            (Some(c), Some(u)) => Some(if c.0 < u.0 { c } else { u }),
+            (c, u) => c.or(u),
+        }
+    }
+}
+
+/// Records the block opened by the `:` at `colon`, or marks it unclosed when
+/// no line follows its header.
+fn close_indented(map: &mut BlockMap, colon: usize, body_end: usize) {
+    if body_end > colon + 1 {
+        map.pairs.push((colon, body_end));
+    } else {
+        map.unclosed.push(colon);
+    }
+}
+
+/// Returns `src` with every comment and literal replaced by spaces (line
+/// breaks are kept), so byte offsets and line numbers still line up with the
+/// original while only code remains.
+pub(crate) fn blank_comments_and_literals(src: &str, rules: &LexicalRules) -> String {
+    let mut scanner = Scanner::new(src, rules);
+    scanner.code(None, false);
+
+    let mut bytes = src.as_bytes().to_vec();
+    for (start, end) in scanner.skipped {
+        for byte in &mut bytes[start..end] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    }
+    // Whole literals are blanked byte by byte, so no multi-byte character is
+    // ever left half replaced.
+    String::from_utf8(bytes).expect("blanking keeps UTF-8 valid")
+}
+
+struct Scanner<'a> {
+    src: &'a [u8],
+    pos: usize,
+    rules: &'a LexicalRules,
+    /// `(offset, is_open)` for every structural brace seen.
+    braces: Vec<(usize, bool)>,
+    /// Byte ranges of the comments and literals skipped.
+    skipped: Vec<(usize, usize)>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(src: &'a str, rules: &'a LexicalRules) -> Self {
+        Scanner {
+            src: src.as_bytes(),
+            pos: 0,
+            rules,
+            braces: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    fn rest(&self) -> &[u8] {
+        &self.src[self.pos..]
+    }
+
+    fn starts_with(&self, token: &str) -> bool {
+        self.rest().starts_with(token.as_bytes())
+    }
+
+    /// Scans code until the end of input or, inside an interpolation, until
+    /// `close` is met with all nested brackets balanced.  Braces are only
+    /// recorded when `record` is set.
+    fn code(&mut self, close: Option<u8>, record: bool) {
+        let mut depth = 0usize;
+        // Last significant code byte, used to tell a regex literal from a
+        // division operator.
+        let mut prev: Option<u8> = None;
+
+        while self.pos < self.src.len() {
+            let start = self.pos;
+            if self.skip_comment() {
+                self.skipped.push((start, self.pos));
+                continue;
+            }
+            if self.skip_literal(prev) {
+                self.skipped.push((start, self.pos));
+                prev = Some(b'"');
+                continue;
+            }
+
+            let byte = self.src[self.pos];
+            match byte {
+                b'(' | b'[' | b'{' => {
+                    if byte == b'{' && record {
+                        self.braces.push((self.pos, true));
+                    }
+                    depth += 1;
+                }
+                b')' | b']' | b'}' => {
+                    if depth == 0 && close == Some(byte) {
+                        self.pos += 1;
+                        return;
+                    }
+                    if byte == b'}' && record {
+                        self.braces.push((self.pos, false));
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                _ => {}
+            }
+            if !byte.is_ascii_whitespace() {
+                prev = Some(byte);
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) -> bool {
+        if self
+            .rules
+            .line_comments
+            .iter()
+            .any(|token| self.starts_with(token))
+        {
+            while self.pos < self.src.len() && self.src[self.pos] != b'\n' {
+                self.pos += 1;
+            }
+            return true;
+        }
+
+        let Some((open, close)) = self.rules.block_comment else {
+            return false;
+        };
+        if !self.starts_with(open) {
+            return false;
+        }
+        self.pos += open.len();
+        let mut depth = 1;
+        while self.pos < self.src.len() {
+            if self.starts_with(close) {
+                self.pos += close.len();
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            } else if self.rules.nested_block_comments && self.starts_with(open) {
+                self.pos += open.len();
+                depth += 1;
+            } else {
+                self.pos += 1;
+            }
+        }
+        true
+    }
+
+    fn skip_literal(&mut self, prev: Option<u8>) -> bool {
+        let byte = self.src[self.pos];
+
+        if self.rules.raw_hash_strings && byte == b'#' {
+            let hashes = self.rest().iter().take_while(|b| **b == b'#').count();
+            if self.src.get(self.pos + hashes) == Some(&b'"') {
+                self.pos += hashes;
+                self.raw_string(hashes);
+                return true;
+            }
+            return false;
+        }
+
+        if self.rules.quotes.contains(&byte) {
+            let triple = [byte; 3];
+            if self.rules.triple_quoted_strings && self.rest().starts_with(&triple) {
+                self.pos += 3;
+                self.string(byte, Some(&triple));
+            } else {
+                self.pos += 1;
+                self.string(byte, None);
+            }
+            return true;
+        }
+
+        if self.rules.regex_literals && byte == b'/' && Self::operand_expected(prev) {
+            self.regex();
+            return true;
+        }
+        false
+    }
+
+    /// Skips the body of a literal opened by `quote` (or by `terminator`
+    /// for multi-line literals), descending into interpolations.
+    fn string(&mut self, quote: u8, terminator: Option<&[u8]>) {
+        let interpolation = self
+            .rules
+            .interpolation
+            .filter(|interpolation| interpolation.quote == quote);
+
+        while self.pos < self.src.len() {
+            if let Some(interpolation) = interpolation {
+                if self.starts_with(interpolation.open) {
+                    self.pos += interpolation.open.len();
+                    self.code(Some(interpolation.close), false);
+                    continue;
+                }
+            }
+
+            let byte = self.src[self.pos];
+            match terminator {
+                Some(terminator) if self.rest().starts_with(terminator) => {
+                    self.pos += terminator.len();
+                    return;
+                }
+                None if byte == quote => {
+                    self.pos += 1;
+                    return;
+                }
+                None if byte == b'\n' && quote != b'`' => return,
+                _ => {}
+            }
+            self.pos += if byte == b'\\' { 2 } else { 1 };
+        }
+        self.pos = self.pos.min(self.src.len());
+    }
+
+    /// Skips a raw string positioned at its opening quote, wrapped in
+    /// `hashes` `#` characters.
+    fn raw_string(&mut self, hashes: usize) {
+        let multiline = self.rest().starts_with(b"\"\"\"");
+        let quotes = if multiline { 3 } else { 1 };
+        self.pos += quotes;
+
+        let mut terminator = vec![b'"'; quotes];
+        terminator.extend(std::iter::repeat_n(b'#', hashes));
+        while self.pos < self.src.len() {
+            if self.rest().starts_with(&terminator) {
+                self.pos += terminator.len();
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn regex(&mut self) {
+        self.pos += 1;
+        let mut in_class = false;
+        while self.pos < self.src.len() {
+            match self.src[self.pos] {
+                b'\\' => self.pos += 1,
+                b'[' => in_class = true,
+                b']' => in_class = false,
+                b'/' if !in_class => {
+                    self.pos += 1;
+                    return;
+                }
+                b'\n' => return,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        self.pos = self.pos.min(self.src.len());
+    }
+
+    /// `true` when a `/` after `prev` starts an operand (a regex literal)
+    /// rather than being a division operator.
+    fn operand_expected(prev: Option<u8>) -> bool {
+        match prev {
+            None => true,
+            Some(byte) => b"(,=:[!&|?{};+-*%<>~^".contains(&byte),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWIFT_LIKE: LexicalRules = LexicalRules {
+        nested_block_comments: true,
+        quotes: b"\"",
+        triple_quoted_strings: true,
+        raw_hash_strings: true,
+        interpolation: Some(Interpolation {
+            quote: b'"',
+            open: "\\(",
+            close: b')',
+        }),
+        ..LexicalRules::C_LIKE
+    };
+
+    const JS_LIKE: LexicalRules = LexicalRules {
+        quotes: b"\"'`",
+        interpolation: Some(Interpolation {
+            quote: b'`',
+            open: "${",
+            close: b'}',
+        }),
+        regex_literals: true,
+        ..LexicalRules::C_LIKE
+    };
+
+    fn block_text<'a>(src: &'a str, rules: &LexicalRules, needle: &str) -> &'a str {
+        let offset = src.find(needle).unwrap();
+        let (open, close) = BlockMap::new(src, rules)
+            .innermost_containing(offset)
+            .expect("block");
+        &src[open..=close]
+    }
+
+    #[test]
+    fn braces_in_comments_and_strings_are_ignored() {
+        let src = "func a() {\n    let s = \"}\"\n    // }\n    /* } */\n    X\n}\n";
+        assert_eq!(
+            block_text(src, &LexicalRules::C_LIKE, "X"),
+            &src[9..src.len() - 1]
+        );
+    }
+
+    #[test]
+    fn character_literals_are_skipped() {
+        let src = "void f() { char c = '}'; X; }";
+        assert_eq!(
+            block_text(src, &LexicalRules::C_LIKE, "X"),
+            "{ char c = '}'; X; }"
+        );
+    }
+
+    #[test]
+    fn swift_multiline_raw_and_interpolated_strings_are_skipped() {
+        let src = r####"func a() {
+    let m = """
+    } still text {
+    """
+    let r = #"raw } "quoted" {"#
+    let i = "\(dict["}"]) done"
+    /* outer /* nested } */ still comment { */
+    X
+}"####;
+        let block = block_text(src, &SWIFT_LIKE, "X");
+        assert!(block.starts_with("{\n    let m"));
+        assert!(block.ends_with("X\n}"));
+    }
+
+    #[test]
+    fn javascript_regex_and_template_literals_are_skipped() {
+        let src = "function f() {\n  const re = /}/g;\n  const t = `${ {a: 1}.a } }`;\n  const d = a / b / c;\n  X\n}";
+        let block = block_text(src, &JS_LIKE, "X");
+        assert!(block.starts_with("{\n  const re"));
+        assert!(block.ends_with("X\n}"));
+    }
+
+    #[test]
+    fn blanking_keeps_offsets_and_line_breaks() {
+        let src = "let s = \"é}\" // ü {\n/* a\nb */ x";
+        let blanked = blank_comments_and_literals(src, &LexicalRules::C_LIKE);
+        assert_eq!(blanked.len(), src.len());
+        let lines: Vec<&str> = blanked.lines().map(str::trim).collect();
+        assert_eq!(lines, vec!["let s =", "", "x"]);
+    }
+
+    const PYTHON_LIKE: LexicalRules = LexicalRules {
+        line_comments: &["#"],
+        block_comment: None,
+        triple_quoted_strings: true,
+        ..LexicalRules::C_LIKE
+    };
+
+    #[test]
+    fn indented_blocks_end_at_the_first_dedent() {
+        let src = "class A:\n    def f(self,\n  x):\n        s = \"\"\"\nnot: code\n\"\"\"\n\n        # comment:\n        X\n    y = 1\nz = 2\n";
+        let map = BlockMap::indented(src, &PYTHON_LIKE);
+        let x = src.find('X').unwrap();
+        let blocks: Vec<&str> = map
+            .containing(x)
+            .map(|(open, close)| &src[open + 1..close])
+            .collect();
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].ends_with("    y = 1"));
+        assert!(blocks[1].starts_with("\n        s = "));
+        assert!(blocks[1].ends_with("        X"));
+    }
+
+    #[test]
+    fn indented_blocks_ignore_colons_inside_brackets_and_one_liners() {
+        let src = "d = {\n  'a': 1,\n}\nif x: y()\nfor i in r:\n    pass";
+        let map = BlockMap::indented(src, &PYTHON_LIKE);
+        let colon = src.rfind(':').unwrap();
+        assert_eq!(map.first_from(0), Some((colon, Some(src.len()))));
+    }
+
+    #[test]
+    fn first_from_reports_unclosed_blocks() {
+        let src = "a { b } c { d";
+        let map = BlockMap::new(src, &LexicalRules::C_LIKE);
+        assert_eq!(map.first_from(0), Some((2, Some(6))));
+        assert_eq!(map.first_from(7), Some((10, None)));
+        assert_eq!(map.first_from(11), None);
+    }
+}
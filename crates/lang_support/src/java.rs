@@ -0,0 +1,230 @@
+// crates/lang_support/src/java.rs
+
+//!
+//! Java support, regex-driven like the Kotlin helper:
+//! * **extract_identifiers** -- method call-sites, `new Foo(` instantiations
+//!   and declared type names.
+//! * **declarations**       -- `class`, `interface`, `enum`, `record` and
+//!   `@interface` declarations and methods with a body.
+//! * **enclosing scopes**   -- type blocks and the method or constructor
+//!   around the TODO, for `--include-references`.
+
+use super::scope::{brace_scope, last_type_before_marker, BlockHeader};
+use super::{
+    Declaration, DeclarationKind, EnclosingScope, LanguageSupport, LexicalRules, ScopeKind,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use todo_marker::MarkerGrammar;
+
+pub(super) struct JavaSupport;
+pub(super) const JAVA: JavaSupport = JavaSupport;
+
+// ---------------------------------------------------------------------------
+//  Regexes
+// ---------------------------------------------------------------------------
+
+static CALL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b([A-Za-z_$][\w$]*)\s*\(").unwrap());
+
+// `class Foo`, `@interface Foo`, `record Foo`
+static TYPE_NAME_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|[^\w@])(@interface|class|interface|enum|record)\s+([A-Za-z_$][\w$]*)")
+        .unwrap()
+});
+
+const MODIFIERS: &str = r"(?:(?:public|private|protected|static|final|abstract|synchronized|native|default|strictfp)\s+)*";
+
+// Method or constructor header: modifiers, optional type parameters and
+// return type, then the name and parameter list.
+static METHOD_HEADER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"^{MODIFIERS}(?:<[^>]*>\s*)?(?:([\w$.<>\[\]?,]+)\s+)?([A-Za-z_$][\w$]*)\s*\(.*\)\s*(?:throws\s+[\w$.,\s]+)?$"
+    ))
+    .unwrap()
+});
+
+// Definitions, capturing the name: `class Foo`, `@interface Foo`, `record
+// Foo` …, and methods with a body, e.g. `void load(int id) throws IOException {`.
+static TYPE_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:class|interface|enum|record)\s+(\w+)").unwrap());
+
+static METHOD_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\w$>\]]\s+(\w+)\s*\([^)]*\)\s*(?:throws\s+[\w$.,\s]+)?\{").unwrap());
+
+static JAVA_METHOD_LINE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"^\s*(?:@\w+(?:\([^)]*\))?\s+)*{MODIFIERS}(?:<[^>]*>\s*)?(?:([\w$.<>\[\]?,]+)\s+)?([A-Za-z_$][\w$]*)\s*\([^)]*\)\s*(?:throws\s+[\w$.,\s]+)?\{{"
+    ))
+    .unwrap()
+});
+
+static JAVA_TYPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:[a-z]+\s+)*(?:class|interface|enum|record|@interface)\s+[\w$]+.*\{")
+        .unwrap()
+});
+
+// Keywords that can precede `(` or sit where a return type would.
+static KEYWORDS: &[&str] = &[
+    "catch",
+    "do",
+    "else",
+    "for",
+    "if",
+    "new",
+    "return",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "try",
+    "while",
+];
+
+// `"…"` strings, `'c'` characters and `"""` text blocks.
+static JAVA_LEXICAL_RULES: LexicalRules = LexicalRules {
+    triple_quoted_strings: true,
+    ..LexicalRules::C_LIKE
+};
+
+fn push_unique(out: &mut Vec<String>, ident: &str) {
+    if !out.iter().any(|existing| existing == ident) {
+        out.push(ident.to_string());
+    }
+}
+
+/// The name of the method or constructor declared by `header`, rejecting
+/// control flow (`if (…)`, `} else if (…)`) and anonymous class bodies.
+fn method_name(caps: &regex::Captures) -> Option<String> {
+    let name = &caps[2];
+    let return_type = caps.get(1).map(|m| m.as_str());
+    if KEYWORDS.contains(&name) || return_type.is_some_and(|ty| KEYWORDS.contains(&ty)) {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+fn type_declaration(text: &str) -> Option<(ScopeKind, String)> {
+    let caps = TYPE_NAME_RE.captures(text)?;
+    let kind = match &caps[1] {
+        "interface" | "@interface" => ScopeKind::Interface,
+        "enum" => ScopeKind::Enum,
+        _ => ScopeKind::Class,
+    };
+    Some((kind, caps[2].to_string()))
+}
+
+fn classify_header(header: &str) -> Option<BlockHeader> {
+    let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Some((kind, name)) = type_declaration(&header) {
+        return Some(BlockHeader::Type(kind, name));
+    }
+    if header.contains("new ") {
+        return None;
+    }
+    let caps = METHOD_HEADER_RE.captures(&header)?;
+    method_name(&caps).map(|name| BlockHeader::Function(format!("{name}()")))
+}
+
+// ---------------------------------------------------------------------------
+//  Trait implementation
+// ---------------------------------------------------------------------------
+
+impl LanguageSupport for JavaSupport {
+    fn extract_identifiers(&self, src: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        for cap in CALL_RE.captures_iter(src) {
+            let ident = &cap[1];
+            if !KEYWORDS.contains(&ident) {
+                push_unique(&mut out, ident);
+            }
+        }
+        for line in src.lines() {
+            if let Some((_, name)) = type_declaration(line) {
+                push_unique(&mut out, &name);
+            }
+        }
+        out
+    }
+
+    fn declarations(&self, content: &str) -> Vec<Declaration> {
+        let types = TYPE_DEFINITION_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Type));
+        let methods = METHOD_DEFINITION_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Function));
+        types.chain(methods).collect()
+    }
+
+    fn is_function_candidate(&self, line: &str) -> bool {
+        !line.contains("new ")
+            && JAVA_METHOD_LINE_RE
+                .captures(line)
+                .is_some_and(|caps| method_name(&caps).is_some())
+    }
+
+    fn is_type_candidate(&self, line: &str) -> bool {
+        JAVA_TYPE_RE.is_match(line)
+    }
+
+    fn extract_type_name(&self, line: &str) -> Option<String> {
+        type_declaration(line).map(|(_, name)| name)
+    }
+
+    fn supports_enclosing_type(&self) -> bool {
+        true
+    }
+
+    fn extract_enclosing_type_name(
+        &self,
+        content: &str,
+        grammar: &MarkerGrammar,
+    ) -> Option<String> {
+        last_type_before_marker(content, grammar, |line| self.extract_type_name(line))
+    }
+
+    fn enclosing_scope(&self, content: &str, offset: usize) -> Option<EnclosingScope> {
+        brace_scope(content, offset, &JAVA_LEXICAL_RULES, |header| {
+            classify_header(header).map(|kind| (0, kind))
+        })
+    }
+
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        &JAVA_LEXICAL_RULES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_candidates_cover_methods_and_constructors() {
+        assert!(JAVA.is_function_candidate("    public void onCreate(Bundle state) {"));
+        assert!(JAVA.is_function_candidate(
+            "    @Override protected <T> List<T> load(int id) throws IOException {"
+        ));
+        assert!(JAVA.is_function_candidate("    public FeedAdapter(Context context) {"));
+        assert!(!JAVA.is_function_candidate("        if (ready) {"));
+        assert!(!JAVA.is_function_candidate("        } else if (ready) {"));
+        assert!(!JAVA.is_function_candidate("        return new Runnable() {"));
+    }
+
+    #[test]
+    fn type_declarations() {
+        assert_eq!(
+            type_declaration("public final class Feed extends Base {"),
+            Some((ScopeKind::Class, "Feed".to_string()))
+        );
+        assert_eq!(
+            type_declaration("public @interface Bind {"),
+            Some((ScopeKind::Interface, "Bind".to_string()))
+        );
+        assert_eq!(
+            type_declaration("record Point(int x, int y) {"),
+            Some((ScopeKind::Class, "Point".to_string()))
+        );
+        assert!(JAVA.is_type_candidate("enum Mode {"));
+    }
+}
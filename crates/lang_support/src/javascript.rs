@@ -4,13 +4,14 @@
 //! The goals mirror the Swift support:
 //! * **extract_identifiers** -- pull out function call-sites *and* class names
 //!   so that helpers referenced by the TODO file are included.
-//! * **file_defines_any**   -- true if the file contains a matching declaration
-//!   (`function foo`, `class Bar`, `exports.foo = ...`, etc.).
+//! * **declarations**       -- the names the file declares (`function foo`,
+//!   `class Bar`, `exports.foo = ...`, etc.), which `file_defines_any` and
+//!   the symbol index match against.
 //! * **resolve_dependency_path** -- best-effort: when we encounter an
 //!   `import` or `require` line, resolve the relative path so the caller
 //!   can include that file immediately.
 
-use super::LanguageSupport;
+use super::{Declaration, DeclarationKind, Interpolation, LanguageSupport, LexicalRules};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::path::{Path, PathBuf};
@@ -43,6 +44,40 @@ static PARSE_CLOUD_RE: Lazy<Regex> = Lazy::new(|| {
     .unwrap()
 });
 
+// `'`/`"` strings, `${…}` template literals and `/…/` regex literals.
+static JS_LEXICAL_RULES: LexicalRules = LexicalRules {
+    quotes: b"\"'`",
+    interpolation: Some(Interpolation {
+        quote: b'`',
+        open: "${",
+        close: b'}',
+    }),
+    regex_literals: true,
+    ..LexicalRules::C_LIKE
+};
+
+// Declarations, each capturing the declared name: `function foo`
+// (including `export` / `async` forms), `const foo = function`, `const foo =
+// (…) =>`, CommonJS exports and `class Foo`.
+static DECLARATION_RES: Lazy<Vec<(Regex, DeclarationKind)>> = Lazy::new(|| {
+    [
+        (r"\bfunction\s+([\w$]+)", DeclarationKind::Function),
+        (
+            r"\b(?:const|let|var)\s+([\w$]+)\s*=\s*(?:async\s+)?(?:function\b|\()",
+            DeclarationKind::Function,
+        ),
+        (r"\bmodule\.exports\s*=\s*([\w$]+)", DeclarationKind::Value),
+        (r"\bexports\.([\w$]+)\s*=", DeclarationKind::Value),
+        (r"\bclass\s+([\w$]+)", DeclarationKind::Type),
+    ]
+    .into_iter()
+    .map(|(pattern, kind)| (Regex::new(pattern).unwrap(), kind))
+    .collect()
+});
+
+// `export { a, b as c }`, capturing the list.
+static EXPORT_LIST_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bexport\s+\{([^}]*)\}").unwrap());
+
 // ---------------------------------------------------------------------------
 //  Reserved words we don't want as identifiers
 // ---------------------------------------------------------------------------
@@ -83,49 +118,22 @@ impl LanguageSupport for JavaScriptSupport {
         out
     }
 
-    fn file_defines_any(&self, content: &str, idents: &[String]) -> bool {
-        for ident in idents {
-            let fn_decl = format!(r"\bfunction\s+{}\b", regex::escape(ident));
-            if Regex::new(&fn_decl).unwrap().is_match(content) {
-                return true;
-            }
-
-            let assign = format!(
-                r"\b(?:const|let|var)\s+{}\s*=\s*(?:async\s+)?(?:function\b|\()",
-                regex::escape(ident)
-            );
-            if Regex::new(&assign).unwrap().is_match(content) {
-                return true;
-            }
-
-            let es_export_fn = format!(
-                r"\bexport\s+(?:async\s+)?function\s+{}\b",
-                regex::escape(ident)
-            );
-            if Regex::new(&es_export_fn).unwrap().is_match(content) {
-                return true;
-            }
-            let es_export_name =
-                format!(r"\bexport\s+\{{[^}}]*\b{}\b[^}}]*\}}", regex::escape(ident));
-            if Regex::new(&es_export_name).unwrap().is_match(content) {
-                return true;
-            }
-
-            let cjs_default = format!(r"\bmodule\.exports\s*=\s*{}\b", regex::escape(ident));
-            if Regex::new(&cjs_default).unwrap().is_match(content) {
-                return true;
-            }
-            let cjs_named = format!(r"\bexports\.{}\s*=", regex::escape(ident));
-            if Regex::new(&cjs_named).unwrap().is_match(content) {
-                return true;
+    fn declarations(&self, content: &str) -> Vec<Declaration> {
+        let mut out = Vec::new();
+        for (re, kind) in DECLARATION_RES.iter() {
+            for caps in re.captures_iter(content) {
+                out.push(Declaration::new(&caps[1], *kind));
             }
-
-            let class_decl = format!(r"\bclass\s+{}\b", regex::escape(ident));
-            if Regex::new(&class_decl).unwrap().is_match(content) {
-                return true;
+        }
+        // Every name in `export { a, b as c }` counts as declared here.
+        for caps in EXPORT_LIST_RE.captures_iter(content) {
+            for name in caps[1].split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')) {
+                if !name.is_empty() {
+                    out.push(Declaration::new(name, DeclarationKind::Value));
+                }
             }
         }
-        false
+        out
     }
 
     fn is_function_candidate(&self, line: &str) -> bool {
@@ -151,6 +159,10 @@ impl LanguageSupport for JavaScriptSupport {
 
         None
     }
+
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        &JS_LEXICAL_RULES
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,238 @@
+// crates/lang_support/src/kotlin.rs
+
+//!
+//! Kotlin (`.kt`, `.kts`) support, regex-driven like the JavaScript helper:
+//! * **extract_identifiers** -- call-sites (`load(`, `FeedAdapter(`) and
+//!   declared `class` / `interface` / `object` names.
+//! * **declarations**       -- `class`, `data class`, `sealed class`,
+//!   `enum class`, `interface`, `object`, `typealias` and `fun` declarations,
+//!   including extension functions such as `fun View.show()`.
+//! * **enclosing scopes**   -- class, interface and object blocks and the
+//!   `fun` around the TODO, for `--include-references`.
+
+use super::scope::{brace_scope, last_type_before_marker, BlockHeader};
+use super::{
+    Declaration, DeclarationKind, EnclosingScope, Interpolation, LanguageSupport, LexicalRules,
+    ScopeKind,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use todo_marker::MarkerGrammar;
+
+pub(super) struct KotlinSupport;
+pub(super) const KOTLIN: KotlinSupport = KotlinSupport;
+
+// ---------------------------------------------------------------------------
+//  Regexes
+// ---------------------------------------------------------------------------
+
+// Call-site `foo(`, `Foo(` or `foo<Bar>(`; a trailing lambda `foo {` is not
+// distinguishable from a block and is left out.
+static CALL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b([A-Za-z_]\w*)\s*(?:<[\w\s,.?<>*]*>)?\s*\(").unwrap());
+
+// `class Foo`, `enum class Foo`, `interface Foo`, `object Foo`
+static DECL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:class|interface|object|typealias)\s+([A-Z]\w*)").unwrap());
+
+// Modifiers and the declaration keyword of a type, capturing the keyword(s)
+// and the name. `companion object` may omit the name.
+static TYPE_DECL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b((?:(?:data|sealed|enum|abstract|open|inner|annotation|value|fun|companion)\s+)*)(class|interface|object)\b(?:\s+([A-Za-z_]\w*))?",
+    )
+    .unwrap()
+});
+
+// `fun load(`, `fun <T> List<T>.second(`, `suspend fun View.show(`
+static FUN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\bfun\s+(?:<[^>]*>\s*)?(?:[\w.<>?*, ]+\.)?([A-Za-z_]\w*)\s*\(").unwrap()
+});
+
+// Definitions, capturing the name: `class Foo` (any modifiers), `interface
+// Foo`, `object Foo`, `typealias Foo`, and `fun foo(` including extension
+// functions such as `fun <T> List<T>.second(`.
+static TYPE_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:class|interface|object|typealias)\s+(\w+)").unwrap());
+
+static FUN_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bfun\s+(?:<[^>]*>\s*)?(?:[\w.<>?*, ]+\.)?(\w+)\s*[(<]").unwrap());
+
+static KOTLIN_FUNCTION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:@\w+(?:\([^)]*\))?\s+)*(?:[a-z]+\s+)*fun\s+.*\)\s*(?::\s*[^{=]+)?\{")
+        .unwrap()
+});
+
+static KOTLIN_TYPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:[a-z]+\s+)*(?:class|interface|object)\s+[A-Za-z_]\w*.*\{").unwrap()
+});
+
+static RESERVED: &[&str] = &[
+    "catch",
+    "for",
+    "fun",
+    "if",
+    "return",
+    "super",
+    "synchronized",
+    "this",
+    "throw",
+    "when",
+    "while",
+];
+
+// Nested block comments, `"""` raw strings, `'c'` characters and `${…}`
+// string templates.
+static KOTLIN_LEXICAL_RULES: LexicalRules = LexicalRules {
+    nested_block_comments: true,
+    triple_quoted_strings: true,
+    interpolation: Some(Interpolation {
+        quote: b'"',
+        open: "${",
+        close: b'}',
+    }),
+    ..LexicalRules::C_LIKE
+};
+
+fn push_unique(out: &mut Vec<String>, ident: &str) {
+    if !out.iter().any(|existing| existing == ident) {
+        out.push(ident.to_string());
+    }
+}
+
+/// The kind and name of a type declared by `text`, if any.
+fn type_declaration(text: &str) -> Option<(ScopeKind, String)> {
+    let caps = TYPE_DECL_RE.captures(text)?;
+    let modifiers = &caps[1];
+    let kind = match &caps[2] {
+        "interface" => ScopeKind::Interface,
+        "object" => ScopeKind::Object,
+        _ if modifiers.contains("enum") => ScopeKind::Enum,
+        _ => ScopeKind::Class,
+    };
+    let name = match caps.get(3) {
+        Some(name) => name.as_str().to_string(),
+        None if modifiers.contains("companion") => "Companion".to_string(),
+        None => return None,
+    };
+    Some((kind, name))
+}
+
+/// What `header` declares and where the declaration starts in it; Kotlin has
+/// no semicolons, so a preceding property can share the header.
+fn classify_header(header: &str) -> Option<(usize, BlockHeader)> {
+    if let Some(caps) = FUN_RE.captures(header) {
+        let name = format!("{}()", &caps[1]);
+        return Some((caps.get(0)?.start(), BlockHeader::Function(name)));
+    }
+    let start = TYPE_DECL_RE.find(header)?.start();
+    let (kind, name) = type_declaration(header)?;
+    Some((start, BlockHeader::Type(kind, name)))
+}
+
+// ---------------------------------------------------------------------------
+//  Trait implementation
+// ---------------------------------------------------------------------------
+
+impl LanguageSupport for KotlinSupport {
+    fn extract_identifiers(&self, src: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        for cap in CALL_RE.captures_iter(src) {
+            let ident = &cap[1];
+            if !RESERVED.contains(&ident) {
+                push_unique(&mut out, ident);
+            }
+        }
+        for cap in DECL_RE.captures_iter(src) {
+            push_unique(&mut out, &cap[1]);
+        }
+        out
+    }
+
+    fn declarations(&self, content: &str) -> Vec<Declaration> {
+        let types = TYPE_DEFINITION_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Type));
+        let functions = FUN_DEFINITION_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Function));
+        types.chain(functions).collect()
+    }
+
+    fn is_function_candidate(&self, line: &str) -> bool {
+        KOTLIN_FUNCTION_RE.is_match(line)
+    }
+
+    fn is_type_candidate(&self, line: &str) -> bool {
+        KOTLIN_TYPE_RE.is_match(line)
+    }
+
+    fn extract_type_name(&self, line: &str) -> Option<String> {
+        type_declaration(line).map(|(_, name)| name)
+    }
+
+    fn supports_enclosing_type(&self) -> bool {
+        true
+    }
+
+    fn extract_enclosing_type_name(
+        &self,
+        content: &str,
+        grammar: &MarkerGrammar,
+    ) -> Option<String> {
+        last_type_before_marker(content, grammar, |line| self.extract_type_name(line))
+    }
+
+    fn enclosing_scope(&self, content: &str, offset: usize) -> Option<EnclosingScope> {
+        brace_scope(content, offset, &KOTLIN_LEXICAL_RULES, classify_header)
+    }
+
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        &KOTLIN_LEXICAL_RULES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_declarations_cover_kotlin_class_flavours() {
+        for (line, kind, name) in [
+            (
+                "data class User(val id: String) {",
+                ScopeKind::Class,
+                "User",
+            ),
+            ("sealed class Result<out T> {", ScopeKind::Class, "Result"),
+            ("enum class Mode {", ScopeKind::Enum, "Mode"),
+            ("fun interface Listener {", ScopeKind::Interface, "Listener"),
+            ("internal object Registry {", ScopeKind::Object, "Registry"),
+            ("companion object {", ScopeKind::Object, "Companion"),
+        ] {
+            assert_eq!(
+                type_declaration(line),
+                Some((kind, name.to_string())),
+                "{line}"
+            );
+        }
+        assert_eq!(type_declaration("val x = object : Runnable {"), None);
+    }
+
+    #[test]
+    fn function_candidates() {
+        assert!(KOTLIN.is_function_candidate("    override fun onCreate(state: Bundle?) {"));
+        assert!(KOTLIN.is_function_candidate("suspend fun <T> load(id: T): Result<T> {"));
+        assert!(KOTLIN.is_function_candidate("    @Composable fun Feed() {"));
+        assert!(!KOTLIN.is_function_candidate("fun sum(a: Int, b: Int) = a + b"));
+        assert!(!KOTLIN.is_function_candidate("if (ready) {"));
+    }
+
+    #[test]
+    fn headers_name_extension_functions() {
+        assert!(matches!(
+            classify_header("fun View.show(animated: Boolean)"),
+            Some((_, BlockHeader::Function(name))) if name == "show()"
+        ));
+    }
+}
@@ -3,19 +3,18 @@
 //! workspace free of giant `match ext { … }` chains.
 //!
 //!  * **Zero business‑logic deps** – the crate only knows about source
-//!    text, file paths and `regex`.
+//!    text, file paths, `regex` and (for Swift) a tree‑sitter grammar.
 //!  * **One trait** – `LanguageSupport` – implemented once per language
-//!    (Swift, JavaScript, Obj‑C …).  Adding a new language means adding
-//!    a single file in this crate.
+//!    (Swift, JavaScript, TypeScript, Kotlin, Java, Python, Obj‑C …).
+//!    Adding a new language means adding a single file in this crate.
 //!  * **Thin adapter API** – other crates call `lang_support::for_ext()`
 //!    and forward the work.
 
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::fs;
-use std::path::{Component, Path, PathBuf};
-use todo_marker::TODO_MARKER;
-use walkdir::WalkDir;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use todo_marker::MarkerGrammar;
 
 /// Abstracts the minimum the rest of the tool‑chain needs from a language‑
 /// specific helper.
@@ -23,8 +22,32 @@ pub trait LanguageSupport: Sync + Send {
     /// Extracts *candidate* identifiers from a chunk of source code.
     fn extract_identifiers(&self, source: &str) -> Vec<String>;
 
+    /// Every name `file_content` declares, with what kind of declaration it
+    /// is.  The basis of [`SymbolIndex`] and of the `file_*_any` checks.
+    fn declarations(&self, file_content: &str) -> Vec<Declaration>;
+
     /// Returns `true` if `file_content` defines **any** of the identifiers.
-    fn file_defines_any(&self, file_content: &str, idents: &[String]) -> bool;
+    fn file_defines_any(&self, file_content: &str, idents: &[String]) -> bool {
+        self.declarations(file_content)
+            .iter()
+            .any(|decl| decl.defines() && idents.contains(&decl.name))
+    }
+
+    /// Returns `true` if `file_content` adds members to **any** of the
+    /// identifiers without being its primary definition, e.g. a Swift
+    /// `extension Foo` or an Obj‑C category `@interface Foo (Networking)`.
+    fn file_extends_any(&self, file_content: &str, idents: &[String]) -> bool {
+        self.declarations(file_content)
+            .iter()
+            .any(|decl| decl.kind == DeclarationKind::Extension && idents.contains(&decl.name))
+    }
+
+    /// Returns `true` when `line` starts an import statement.  Defaults to
+    /// `import`, `from … import`, `@import`, `#import` and `#include`, with
+    /// attributes such as Swift's `@testable` allowed in front.
+    fn is_import(&self, line: &str) -> bool {
+        IMPORT_RE.is_match(line)
+    }
 
     /// Best‑effort extraction of a dependency path from a source line.
     fn resolve_dependency_path(&self, _line: &str, _current_dir: &Path) -> Option<PathBuf> {
@@ -61,20 +84,92 @@ pub trait LanguageSupport: Sync + Send {
     }
 
     /// Best-effort extraction of the name of the type that encloses the TODO
-    /// marker, scanning `content` up to the first TODO marker line.
+    /// marker, scanning `content` up to the first line holding an
+    /// instruction in `grammar`.
     ///
     /// Returns the last type declaration seen before the marker, or `None` when
     /// no type precedes it (or the language does not support enclosing types).
-    fn extract_enclosing_type_name(&self, _content: &str) -> Option<String> {
+    fn extract_enclosing_type_name(
+        &self,
+        _content: &str,
+        _grammar: &MarkerGrammar,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Structured description of the declarations that enclose byte `offset`
+    /// of `content`: nested type path, innermost type kind and function.
+    ///
+    /// Returns `None` when nothing encloses the offset, the source cannot be
+    /// parsed reliably, or the language does not model scopes.
+    fn enclosing_scope(&self, _content: &str, _offset: usize) -> Option<EnclosingScope> {
         None
     }
+
+    /// Comment and literal syntax used by the brace‑matching block scanner
+    /// ([`BlockMap`]).  Defaults to C‑family rules.
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        &LexicalRules::C_LIKE
+    }
+
+    /// How TODO and substring markers are written in this language's
+    /// comments.  Defaults to `//` and `/* */`.
+    fn marker_grammar(&self) -> MarkerGrammar {
+        MarkerGrammar::C_LIKE
+    }
+
+    /// Whether blocks are delimited by braces or by indentation.
+    fn block_delimiting(&self) -> BlockDelimiting {
+        BlockDelimiting::Braces
+    }
+
+    /// Byte ranges of the function, method and accessor bodies a
+    /// [`skeleton`] leaves out, outermost only and in order.  A
+    /// brace-delimited body's range covers its braces; an indented body's
+    /// starts after its header's `:` (and after a docstring) and ends with
+    /// its last line.  Defaults to the blocks opened on lines that
+    /// [`is_function_candidate`](Self::is_function_candidate).
+    fn body_ranges(&self, content: &str) -> Vec<Range<usize>> {
+        skeleton::function_bodies(self, content)
+    }
+
+    /// Lines (0-based, end-exclusive) of the declarations of `name` in
+    /// `content`, outermost only and in order, each from its doc comments
+    /// and attributes to its last line.  Defaults to the blocks opened on
+    /// lines whose [`extract_type_name`](Self::extract_type_name) is
+    /// `name` or that declare a function of that name, as far as
+    /// [`is_function_candidate`](Self::is_function_candidate) tells.
+    fn declaration_lines(&self, content: &str, name: &str) -> Vec<Range<usize>> {
+        slices::declarations_named(self, content, name)
+    }
+
+    /// The structural blocks of `source`, matched according to
+    /// [`block_delimiting`](Self::block_delimiting) and
+    /// [`lexical_rules`](Self::lexical_rules).
+    fn block_map(&self, source: &str) -> BlockMap {
+        match self.block_delimiting() {
+            BlockDelimiting::Braces => BlockMap::new(source, self.lexical_rules()),
+            BlockDelimiting::Indentation => BlockMap::indented(source, self.lexical_rules()),
+        }
+    }
 }
 
+// `import Foo`, `@testable import Foo`, `from app import Foo`, `#include <x>`
+static IMPORT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:(?:@\w+\s+)*import\b|from\s+\S+\s+import\b|[@#]import\b|#include\b)")
+        .unwrap()
+});
+
 /// Returns the language helper for a given file extension.
 pub fn for_extension(ext: &str) -> Option<&'static dyn LanguageSupport> {
     match ext.to_lowercase().as_str() {
         "swift" => Some(&swift::SWIFT),
         "js" | "jsx" | "mjs" | "cjs" => Some(&javascript::JS),
+        "ts" | "mts" | "cts" => Some(&typescript::TS),
+        "tsx" => Some(&typescript::TSX),
+        "kt" | "kts" => Some(&kotlin::KOTLIN),
+        "java" => Some(&java::JAVA),
+        "py" | "pyi" => Some(&python::PYTHON),
         "h" | "m" => Some(&objc::OBJC),
         _ => None,
     }
@@ -82,14 +177,40 @@ pub fn for_extension(ext: &str) -> Option<&'static dyn LanguageSupport> {
 
 /// All file extensions recognised by `for_extension`.
 pub fn supported_extensions() -> &'static [&'static str] {
-    &["swift", "js", "jsx", "mjs", "cjs", "h", "m"]
+    &[
+        "swift", "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "kt", "kts", "java", "py",
+        "pyi", "h", "m",
+    ]
+}
+
+/// The marker grammar for `path`: its language's comment syntax (C‑like for
+/// unknown extensions) with the user's `token`, if one was chosen, in place
+/// of `TODO: -`.
+pub fn marker_grammar(path: &Path, token: Option<&str>) -> MarkerGrammar {
+    let grammar = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(for_extension)
+        .map_or_else(MarkerGrammar::default, |lang| lang.marker_grammar());
+    match token {
+        Some(token) => grammar.with_token(token),
+        None => grammar,
+    }
 }
 
 /// Returns `true` if `line` matches any language's function-candidate pattern.
 ///
 /// Use when the file extension is unknown or when checking across all languages.
 pub fn is_function_candidate_any_lang(line: &str) -> bool {
-    static ALL: &[&dyn LanguageSupport] = &[&swift::SWIFT, &javascript::JS, &objc::OBJC];
+    static ALL: &[&dyn LanguageSupport] = &[
+        &swift::SWIFT,
+        &javascript::JS,
+        &typescript::TS,
+        &kotlin::KOTLIN,
+        &java::JAVA,
+        &python::PYTHON,
+        &objc::OBJC,
+    ];
     ALL.iter().any(|lang| lang.is_function_candidate(line))
 }
 
@@ -99,18 +220,19 @@ static GENERIC_SIMPLE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Z][A-Za-z0
 /// Extracts language-agnostic, capitalized type-name candidates from source text.
 ///
 /// This is the generic token pass shared across every language: it collects
-/// PascalCase tokens while skipping imports and non-TODO comments. It is the
-/// sole source of identifiers for languages whose `extract_identifiers` yields
-/// nothing (e.g. Obj-C) and for files with an unrecognized extension.
+/// PascalCase tokens while skipping imports and comments in `grammar`'s
+/// syntax, other than the instruction itself. It is the sole source of
+/// identifiers for languages whose `extract_identifiers` yields nothing
+/// (e.g. Obj-C) and for files with an unrecognized extension.
 ///
 /// Bracketed forms such as `[MyType]` are handled implicitly: `generic_tokens`
 /// strips the brackets so the inner name is matched as a plain PascalCase token.
 ///
 /// Results are returned in order of first appearance with duplicates removed.
-pub fn extract_generic_identifiers(source: &str) -> Vec<String> {
+pub fn extract_generic_identifiers(source: &str, grammar: &MarkerGrammar) -> Vec<String> {
     let mut out = Vec::new();
     for line in source.lines() {
-        for token in generic_tokens(line) {
+        for token in generic_tokens(line, grammar) {
             if GENERIC_SIMPLE_RE.is_match(&token) && !out.contains(&token) {
                 out.push(token);
             }
@@ -120,25 +242,35 @@ pub fn extract_generic_identifiers(source: &str) -> Vec<String> {
 }
 
 /// Splits a single source line into whitespace-separated tokens, after
-/// stripping non-alphanumeric characters. Import directives and non-TODO
-/// comments produce no tokens; a `TODO_MARKER` prefix is removed so the text
+/// stripping non-alphanumeric characters. Import directives and comments in
+/// `grammar`'s syntax produce no tokens, except for a comment that starts
+/// with a marker of any verb: the marker is removed so the instruction
 /// following it is still scanned.
-fn generic_tokens(line: &str) -> Vec<String> {
+fn generic_tokens(line: &str, grammar: &MarkerGrammar) -> Vec<String> {
     let trimmed = line.trim();
 
     if trimmed.is_empty()
         || trimmed.starts_with("import ")
         || trimmed.starts_with("#import")
         || trimmed.starts_with("#include")
-        || (trimmed.starts_with("//") && !trimmed.starts_with(TODO_MARKER))
     {
         return Vec::new();
     }
 
-    let content = if trimmed.starts_with(TODO_MARKER) {
-        trimmed.trim_start_matches(TODO_MARKER).trim_start()
-    } else {
-        trimmed
+    let content = match grammar.find_marker(trimmed) {
+        Some((0, verb)) => {
+            let token = grammar.token_for(verb);
+            let after = trimmed.find(token).map_or(0, |i| i + token.len());
+            &trimmed[after..]
+        }
+        _ if grammar
+            .comments()
+            .iter()
+            .any(|comment| trimmed.starts_with(comment.open)) =>
+        {
+            return Vec::new();
+        }
+        _ => trimmed,
     };
 
     let cleaned: String = content
@@ -149,56 +281,33 @@ fn generic_tokens(line: &str) -> Vec<String> {
     cleaned.split_whitespace().map(String::from).collect()
 }
 
-pub struct SourceFile {
-    pub path: PathBuf,
-    pub content: String,
-    pub language: &'static dyn LanguageSupport,
-}
-
-/// Walks a root directory and returns readable files supported by `lang_support`.
-///
-/// Files inside generated/vendor directories are skipped so definition and
-/// reference searches share the same source-file policy.
-pub fn walk_source_files(root: impl AsRef<Path>) -> Vec<SourceFile> {
-    WalkDir::new(root)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|entry| entry.file_type().is_file())
-        .filter_map(|entry| {
-            let path = entry.into_path();
-            if has_ignored_component(&path) {
-                return None;
-            }
-
-            let ext = path.extension().and_then(|s| s.to_str())?;
-            let language = for_extension(ext)?;
-            let content = fs::read_to_string(&path).ok()?;
-
-            Some(SourceFile {
-                path,
-                content,
-                language,
-            })
-        })
-        .collect()
-}
-
-fn has_ignored_component(path: &Path) -> bool {
-    path.components().any(|component| match component {
-        Component::Normal(name) => {
-            let name = name.to_string_lossy();
-            name == ".build" || name == "Pods"
-        }
-        _ => false,
-    })
-}
+mod blocks;
+mod scope;
+mod skeleton;
+mod slices;
+mod symbols;
+mod walk;
+
+pub use blocks::{BlockDelimiting, BlockMap, Interpolation, LexicalRules};
+pub use scope::{EnclosingScope, FunctionScope, ScopeKind};
+pub use skeleton::{skeleton, ELIDED_BODY};
+pub use slices::{focus_lines, import_lines};
+pub use symbols::{Declaration, DeclarationKind, FileSymbols, SymbolIndex};
+pub use walk::{
+    walk_files, walk_source_files, walk_source_files_with_options, walk_source_paths,
+    IgnoreMatcher, SourceFile, WalkOptions,
+};
 
 // ---------------------------------------------------------------------------
 //  One sub‑module per language
 // ---------------------------------------------------------------------------
+mod java;
 mod javascript;
+mod kotlin;
 mod objc;
+mod python;
 mod swift;
+mod typescript;
 
 // Re‑export the trait so callers can `use lang_support::LanguageSupport;`
 pub use self::LanguageSupport as _;
@@ -212,9 +321,29 @@ mod tests {
 
     #[test]
     fn ignored_component_policy_matches_source_searches() {
-        assert!(has_ignored_component(Path::new("Root/.build/File.swift")));
-        assert!(has_ignored_component(Path::new("Root/Pods/File.swift")));
-        assert!(!has_ignored_component(Path::new("Root/Sources/File.swift")));
+        let policy = WalkOptions::default();
+        let ignores = |path: &str| policy.ignores(Path::new("Root"), Path::new(path));
+        assert!(ignores("Root/.build/File.swift"));
+        assert!(ignores("Root/Pods/File.swift"));
+        assert!(ignores("Root/node_modules/pkg/index.js"));
+        assert!(!ignores("Root/Sources/File.swift"));
+    }
+
+    #[test]
+    fn function_candidates_are_recognised_in_any_language() {
+        for line in [
+            "func load() {",
+            "function load() {",
+            "- (void)load {",
+            "  private async load(id: string): Promise<void> {",
+            "    fun load(id: Int): Feed {",
+            "    public List<Feed> load(int id) {",
+            "def load(self):",
+            "    async def fetch(url):",
+        ] {
+            assert!(is_function_candidate_any_lang(line), "{line}");
+        }
+        assert!(!is_function_candidate_any_lang("let feed = Feed()"));
     }
 
     #[test]
@@ -226,13 +355,61 @@ mod tests {
                 "{ext} unexpectedly supports enclosing types"
             );
             assert_eq!(
-                lang.extract_enclosing_type_name("class Foo {}\n// TODO: - x"),
+                lang.extract_enclosing_type_name(
+                    "class Foo {}\n// TODO: - x",
+                    &lang.marker_grammar()
+                ),
                 None,
                 "{ext} unexpectedly extracted an enclosing type"
             );
         }
     }
 
+    #[test]
+    fn typescript_extensions_support_enclosing_type_via_dispatch() {
+        for ext in ["ts", "tsx", "mts", "cts"] {
+            let lang = for_extension(ext).expect("typescript should resolve");
+            assert!(lang.supports_enclosing_type(), "{ext}");
+        }
+    }
+
+    #[test]
+    fn jvm_extensions_support_enclosing_type_via_dispatch() {
+        for ext in ["kt", "kts", "java"] {
+            let lang = for_extension(ext).expect("language should resolve");
+            assert!(lang.supports_enclosing_type(), "{ext}");
+        }
+    }
+
+    #[test]
+    fn python_extensions_use_indentation_blocks() {
+        for ext in ["py", "pyi"] {
+            let lang = for_extension(ext).expect("python should resolve");
+            assert!(lang.supports_enclosing_type(), "{ext}");
+            assert_eq!(lang.block_delimiting(), BlockDelimiting::Indentation);
+        }
+        assert_eq!(
+            for_extension("swift").unwrap().block_delimiting(),
+            BlockDelimiting::Braces
+        );
+    }
+
+    #[test]
+    fn marker_grammar_follows_language_and_token() {
+        assert_eq!(
+            marker_grammar(Path::new("a/b.py"), None).marker(),
+            "# TODO: -"
+        );
+        assert_eq!(
+            marker_grammar(Path::new("Feed.swift"), Some("AI: -")).marker(),
+            "// AI: -"
+        );
+        assert_eq!(
+            marker_grammar(Path::new("notes.txt"), None).marker(),
+            "// TODO: -"
+        );
+    }
+
     #[test]
     fn swift_supports_enclosing_type_via_dispatch() {
         let lang = for_extension("swift").expect("swift should resolve");
@@ -272,7 +449,7 @@ mod tests {
         );
         assert!(files.iter().any(|source_file| source_file
             .language
-            .file_defines_any(&source_file.content, &["Model".to_string()])));
+            .file_defines_any(source_file.content(), &["Model".to_string()])));
     }
 }
 
@@ -288,25 +465,33 @@ mod generic_identifier_tests {
         idents.into_iter().collect()
     }
 
+    fn generic(source: &str) -> Vec<String> {
+        extract_generic_identifiers(source, &MarkerGrammar::C_LIKE)
+    }
+
+    fn tokens(line: &str) -> Vec<String> {
+        generic_tokens(line, &MarkerGrammar::C_LIKE)
+    }
+
     #[test]
     fn simple_pascalcase_token_is_extracted() {
-        assert_eq!(extract_generic_identifiers("MyType"), vec!["MyType"]);
+        assert_eq!(generic("MyType"), vec!["MyType"]);
     }
 
     #[test]
     fn generic_tokens_returns_empty_for_non_eligible_lines() {
-        assert!(generic_tokens("").is_empty());
-        assert!(generic_tokens("   ").is_empty());
-        assert!(generic_tokens("import Foundation").is_empty());
-        assert!(generic_tokens("#import <Foundation/Foundation.h>").is_empty());
-        assert!(generic_tokens("#include <stdio.h>").is_empty());
-        assert!(generic_tokens("// comment").is_empty());
+        assert!(tokens("").is_empty());
+        assert!(tokens("   ").is_empty());
+        assert!(tokens("import Foundation").is_empty());
+        assert!(tokens("#import <Foundation/Foundation.h>").is_empty());
+        assert!(tokens("#include <stdio.h>").is_empty());
+        assert!(tokens("// comment").is_empty());
     }
 
     #[test]
     fn generic_tokens_splits_and_cleans_input() {
         assert_eq!(
-            generic_tokens("MyClass,struct MyStruct"),
+            tokens("MyClass,struct MyStruct"),
             vec!["MyClass", "struct", "MyStruct"]
         );
     }
@@ -314,16 +499,44 @@ mod generic_identifier_tests {
     #[test]
     fn generic_tokens_strips_todo_marker_prefix() {
         assert_eq!(
-            generic_tokens("// TODO: - MyTriggeredType"),
+            tokens("// TODO: - MyTriggeredType"),
             vec!["MyTriggeredType"]
         );
     }
 
+    #[test]
+    fn generic_tokens_follow_a_custom_marker_token() {
+        let grammar = MarkerGrammar::C_LIKE.with_token("AI: -");
+        assert_eq!(
+            generic_tokens("// AI: - make NetworkClient retry", &grammar),
+            vec!["make", "NetworkClient", "retry"]
+        );
+        assert_eq!(
+            generic_tokens("// TEST: - cover RetryPolicy", &grammar),
+            vec!["cover", "RetryPolicy"]
+        );
+        assert!(generic_tokens("// TODO: - not the chosen token", &grammar).is_empty());
+    }
+
+    #[test]
+    fn generic_tokens_follow_the_hash_comment_syntax() {
+        let grammar = MarkerGrammar::HASH;
+        assert_eq!(
+            generic_tokens("# TODO: - validate the Payload", &grammar),
+            vec!["validate", "the", "Payload"]
+        );
+        assert!(generic_tokens("# uses the Cache", &grammar).is_empty());
+        assert_eq!(
+            extract_generic_identifiers("// not a Comment here", &grammar),
+            vec!["Comment"]
+        );
+    }
+
     #[test]
     fn extracts_type_names_from_declarations() {
         let source = "class MyClass {}\nstruct MyStruct {}\nenum MyEnum {}";
         assert_eq!(
-            as_set(extract_generic_identifiers(source)),
+            as_set(generic(source)),
             BTreeSet::from([
                 "MyClass".to_string(),
                 "MyEnum".to_string(),
@@ -334,18 +547,13 @@ mod generic_identifier_tests {
 
     #[test]
     fn extracts_type_name_from_bracket_notation() {
-        assert_eq!(
-            extract_generic_identifiers("let array: [CustomType] = []"),
-            vec!["CustomType"]
-        );
+        assert_eq!(generic("let array: [CustomType] = []"), vec!["CustomType"]);
     }
 
     #[test]
     fn extracts_mixed_tokens_on_one_line() {
         assert_eq!(
-            as_set(extract_generic_identifiers(
-                "class MyClass, struct MyStruct; enum MyEnum."
-            )),
+            as_set(generic("class MyClass, struct MyStruct; enum MyEnum.")),
             BTreeSet::from([
                 "MyClass".to_string(),
                 "MyEnum".to_string(),
@@ -357,22 +565,19 @@ mod generic_identifier_tests {
     #[test]
     fn deduplicates_repeated_type_names() {
         let source = "class DuplicateType {}\nstruct DuplicateType {}\nenum DuplicateType {}";
-        assert_eq!(
-            extract_generic_identifiers(source),
-            vec!["DuplicateType".to_string()]
-        );
+        assert_eq!(generic(source), vec!["DuplicateType".to_string()]);
     }
 
     #[test]
     fn splits_underscored_names_and_drops_lowercase_tokens() {
         // `My_Class` splits on the underscore into `My` and `Class`; the joined
         // `My_Class` token never survives, and lowercase words are dropped.
-        let result = as_set(extract_generic_identifiers("class My_Class {}"));
+        let result = as_set(generic("class My_Class {}"));
         assert!(!result.contains("My_Class"));
         assert_eq!(
             result,
             BTreeSet::from(["My".to_string(), "Class".to_string()])
         );
-        assert!(extract_generic_identifiers("let x = 5").is_empty());
+        assert!(generic("let x = 5").is_empty());
     }
 }
@@ -1,6 +1,6 @@
 // crates/lang_support/src/objc.rs
 
-use super::LanguageSupport;
+use super::{Declaration, DeclarationKind, LanguageSupport};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::path::{Path, PathBuf};
@@ -12,19 +12,33 @@ static OBJC_METHOD_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"^\s*[-+]\s*\([^)]*\)\s*[a-zA-Z_][a-zA-Z0-9_]*(?::\s*\([^)]*\)\s*[a-zA-Z_][a-zA-Z0-9_]*)*\s*\{"#).unwrap()
 });
 
+/// `@interface` / `@implementation` of a class, capturing the name and, for
+/// `@interface Foo (Networking)`, the category.  Anonymous class extensions
+/// (`@interface Foo ()`) belong with the primary declaration.
+static CLASS_DECL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"@(?:interface|implementation)\s+(\w+)(?:\s*\(\s*(\w*)\s*\))?").unwrap()
+});
+
 impl LanguageSupport for ObjCSupport {
     fn extract_identifiers(&self, _src: &str) -> Vec<String> {
         Vec::new()
     }
 
-    fn file_defines_any(&self, file_content: &str, idents: &[String]) -> bool {
-        idents.iter().any(|ident| {
-            let interface_pattern = format!(r"@interface\s+{}\b", regex::escape(ident));
-            let implementation_pattern = format!(r"@implementation\s+{}\b", regex::escape(ident));
-
-            Regex::new(&interface_pattern).is_ok_and(|re| re.is_match(file_content))
-                || Regex::new(&implementation_pattern).is_ok_and(|re| re.is_match(file_content))
-        })
+    /// Named categories extend their class; every other `@interface` or
+    /// `@implementation` declares it.
+    fn declarations(&self, file_content: &str) -> Vec<Declaration> {
+        CLASS_DECL_RE
+            .captures_iter(file_content)
+            .map(|caps| {
+                let is_category = caps.get(2).is_some_and(|name| !name.as_str().is_empty());
+                let kind = if is_category {
+                    DeclarationKind::Extension
+                } else {
+                    DeclarationKind::Type
+                };
+                Declaration::new(&caps[1], kind)
+            })
+            .collect()
     }
 
     fn is_function_candidate(&self, line: &str) -> bool {
@@ -0,0 +1,235 @@
+// crates/lang_support/src/python.rs
+
+//!
+//! Python (`.py`, `.pyi`) support, regex-driven like the Kotlin helper but
+//! with indentation-delimited blocks:
+//! * **extract_identifiers** -- call-sites (`load(`, `User(`) and declared
+//!   class names.
+//! * **declarations**       -- `class`, `def` / `async def` and module-level
+//!   assignments such as `UserId = NewType(...)`.
+//! * **resolve_dependency_path** -- `from pkg.mod import Name`, relative
+//!   `from .mod import Name` and `import pkg.mod`, resolved to a module file
+//!   or package `__init__.py` that exists on disk.
+//! * **enclosing scopes**   -- the classes and `def` around the TODO, for
+//!   `--include-references`.
+
+use super::scope::{indented_scope, last_type_before_marker, BlockHeader};
+use super::{
+    BlockDelimiting, Declaration, DeclarationKind, EnclosingScope, LanguageSupport, LexicalRules,
+    ScopeKind,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use todo_marker::MarkerGrammar;
+
+pub(super) struct PythonSupport;
+pub(super) const PYTHON: PythonSupport = PythonSupport;
+
+// ---------------------------------------------------------------------------
+//  Regexes
+// ---------------------------------------------------------------------------
+
+static CALL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b([A-Za-z_]\w*)\s*\(").unwrap());
+
+// `class Foo`, `class Foo(Base, metaclass=Meta)`, capturing the name.
+static CLASS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*class\s+([A-Za-z_]\w*)\s*(?:[(:\[]|$)").unwrap());
+
+// `def load(`, `async def load(`, `def load[T](`
+static DEF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*(?:async\s+)?def\s+([A-Za-z_]\w*)\s*[(\[]").unwrap());
+
+// Definitions anywhere in a file, capturing the name: `class Foo(`, `def
+// load(`, and module-level assignments such as `UserId = NewType(...)`.
+static CLASS_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*class\s+(\w+)\s*[(:\[]").unwrap());
+
+static DEF_DEFINITION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*(?:async\s+)?def\s+(\w+)\s*[(\[]").unwrap());
+
+static ASSIGNMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^(\w+)\s*(?::[^=\n]+)?=[^=]").unwrap());
+
+// `from .models import User`, `from app.models import (User,`
+static FROM_IMPORT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*from\s+(\.*)([\w.]*)\s+import\b").unwrap());
+
+// `import app.models`, `import app.models as models`
+static IMPORT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*import\s+([\w.]+)").unwrap());
+
+static RESERVED: &[&str] = &[
+    "and", "assert", "await", "class", "def", "del", "elif", "except", "for", "if", "in", "is",
+    "lambda", "not", "or", "print", "raise", "return", "super", "while", "with", "yield",
+];
+
+// `#` comments; `'` and `"` strings, tripled for multi-line literals.  String
+// prefixes (`r`, `f`, `b`) need no special casing: the scanner skips whole
+// literals, and f-string fields cannot hold the enclosing quote before 3.12.
+static PYTHON_LEXICAL_RULES: LexicalRules = LexicalRules {
+    line_comments: &["#"],
+    block_comment: None,
+    triple_quoted_strings: true,
+    ..LexicalRules::C_LIKE
+};
+
+fn push_unique(out: &mut Vec<String>, ident: &str) {
+    if !out.iter().any(|existing| existing == ident) {
+        out.push(ident.to_string());
+    }
+}
+
+/// What the logical header line in front of a block's `:` declares.
+/// Decorators sit on lines of their own, so the header starts with the
+/// declaration itself.
+fn classify_header(header: &str) -> Option<(usize, BlockHeader)> {
+    if let Some(caps) = CLASS_RE.captures(header) {
+        return Some((0, BlockHeader::Type(ScopeKind::Class, caps[1].to_string())));
+    }
+    let caps = DEF_RE.captures(header)?;
+    Some((0, BlockHeader::Function(format!("{}()", &caps[1]))))
+}
+
+/// `pkg/mod.py` or `pkg/mod/__init__.py` under `base`, if either exists.
+fn module_file(base: &Path, module: &str) -> Option<PathBuf> {
+    let relative: PathBuf = module.split('.').filter(|part| !part.is_empty()).collect();
+    if relative.as_os_str().is_empty() {
+        let init = base.join("__init__.py");
+        return init.is_file().then_some(init);
+    }
+    [
+        base.join(&relative).with_extension("py"),
+        base.join(&relative).join("__init__.py"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+}
+
+// ---------------------------------------------------------------------------
+//  Trait implementation
+// ---------------------------------------------------------------------------
+
+impl LanguageSupport for PythonSupport {
+    fn extract_identifiers(&self, src: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        for cap in CALL_RE.captures_iter(src) {
+            let ident = &cap[1];
+            if !RESERVED.contains(&ident) {
+                push_unique(&mut out, ident);
+            }
+        }
+        for line in src.lines() {
+            if let Some(caps) = CLASS_RE.captures(line) {
+                push_unique(&mut out, &caps[1]);
+            }
+        }
+        out
+    }
+
+    fn declarations(&self, content: &str) -> Vec<Declaration> {
+        let classes = CLASS_DEFINITION_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Type));
+        let defs = DEF_DEFINITION_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Function));
+        let assignments = ASSIGNMENT_RE
+            .captures_iter(content)
+            .map(|caps| Declaration::new(&caps[1], DeclarationKind::Value));
+        classes.chain(defs).chain(assignments).collect()
+    }
+
+    /// Relative imports resolve against `current_dir` and its parents, one
+    /// per extra leading dot; absolute ones against the nearest ancestor of
+    /// `current_dir` that contains the module.
+    fn resolve_dependency_path(&self, line: &str, current_dir: &Path) -> Option<PathBuf> {
+        let (dots, module) = match FROM_IMPORT_RE.captures(line) {
+            Some(caps) => (caps[1].len(), caps[2].to_string()),
+            None => (0, IMPORT_RE.captures(line)?[1].to_string()),
+        };
+
+        if dots > 0 {
+            let base = current_dir.ancestors().nth(dots - 1)?;
+            return module_file(base, &module);
+        }
+        if module.is_empty() {
+            return None;
+        }
+        current_dir
+            .ancestors()
+            .find_map(|base| module_file(base, &module))
+    }
+
+    fn is_function_candidate(&self, line: &str) -> bool {
+        DEF_RE.is_match(line)
+    }
+
+    fn is_type_candidate(&self, line: &str) -> bool {
+        CLASS_RE.is_match(line)
+    }
+
+    fn extract_type_name(&self, line: &str) -> Option<String> {
+        CLASS_RE.captures(line).map(|caps| caps[1].to_string())
+    }
+
+    fn supports_enclosing_type(&self) -> bool {
+        true
+    }
+
+    fn extract_enclosing_type_name(
+        &self,
+        content: &str,
+        grammar: &MarkerGrammar,
+    ) -> Option<String> {
+        last_type_before_marker(content, grammar, |line| self.extract_type_name(line))
+    }
+
+    fn enclosing_scope(&self, content: &str, offset: usize) -> Option<EnclosingScope> {
+        indented_scope(content, offset, &PYTHON_LEXICAL_RULES, classify_header)
+    }
+
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        &PYTHON_LEXICAL_RULES
+    }
+
+    fn marker_grammar(&self) -> MarkerGrammar {
+        MarkerGrammar::HASH
+    }
+
+    fn block_delimiting(&self) -> BlockDelimiting {
+        BlockDelimiting::Indentation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_candidates_cover_def_and_async_def() {
+        assert!(PYTHON.is_function_candidate("    def load(self, id: int) -> User:"));
+        assert!(PYTHON.is_function_candidate("async def fetch(url):"));
+        assert!(PYTHON.is_function_candidate("def first[T](items: list[T]) -> T:"));
+        assert!(!PYTHON.is_function_candidate("    default = load(1)"));
+        assert!(!PYTHON.is_function_candidate("@app.route('/users')"));
+    }
+
+    #[test]
+    fn type_names_come_from_class_declarations() {
+        assert_eq!(
+            PYTHON.extract_type_name("class User(BaseModel):"),
+            Some("User".to_string())
+        );
+        assert_eq!(
+            PYTHON.extract_type_name("class Empty:"),
+            Some("Empty".to_string())
+        );
+        assert_eq!(PYTHON.extract_type_name("classes = []"), None);
+    }
+
+    #[test]
+    fn identifiers_skip_keywords() {
+        let idents = PYTHON.extract_identifiers("if ready(x) and not (y):\n    return User(x)");
+        assert_eq!(idents, vec!["ready", "User"]);
+    }
+}
@@ -0,0 +1,308 @@
+// crates/lang_support/src/scope.rs
+
+//! Structured description of the declarations that enclose a position in a
+//! source file (usually the TODO marker).
+
+use crate::blocks::{blank_comments_and_literals, BlockMap, LexicalRules};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+use std::ops::RangeInclusive;
+use todo_marker::MarkerGrammar;
+
+/// The keyword that introduced an enclosing type declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    Class,
+    Struct,
+    Enum,
+    Actor,
+    Extension,
+    Protocol,
+    Interface,
+    Object,
+}
+
+impl ScopeKind {
+    /// The declaration keyword, e.g. `extension`.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            ScopeKind::Class => "class",
+            ScopeKind::Struct => "struct",
+            ScopeKind::Enum => "enum",
+            ScopeKind::Actor => "actor",
+            ScopeKind::Extension => "extension",
+            ScopeKind::Protocol => "protocol",
+            ScopeKind::Interface => "interface",
+            ScopeKind::Object => "object",
+        }
+    }
+}
+
+impl fmt::Display for ScopeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.keyword())
+    }
+}
+
+/// The function, method or initializer enclosing a position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionScope {
+    /// Name in the language's own idiom, e.g. `reload(id:_:)` for Swift.
+    pub name: String,
+    /// The declaration header up to its body, with whitespace collapsed,
+    /// e.g. `func reload(id: Int, _ force: Bool) -> Bool`.
+    pub signature: String,
+}
+
+/// Where a position sits: the chain of enclosing types and, if any, the
+/// innermost enclosing function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnclosingScope {
+    /// Enclosing type names, outermost first. For `extension Foo` the entry
+    /// is the extended type.
+    pub type_path: Vec<String>,
+    /// Kind of the innermost enclosing type, if there is one.
+    pub kind: Option<ScopeKind>,
+    /// Innermost enclosing function, if there is one.
+    pub function: Option<FunctionScope>,
+    /// 1-based, inclusive line range of the innermost enclosing declaration.
+    pub lines: RangeInclusive<usize>,
+}
+
+impl EnclosingScope {
+    /// The innermost enclosing type name.
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_path.last().map(String::as_str)
+    }
+
+    /// Dotted path such as `Outer.Inner.reload()`.
+    pub fn qualified_name(&self) -> String {
+        let mut parts: Vec<&str> = self.type_path.iter().map(String::as_str).collect();
+        if let Some(function) = &self.function {
+            parts.push(&function.name);
+        }
+        parts.join(".")
+    }
+}
+
+impl fmt::Display for EnclosingScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.qualified_name())
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Scopes of brace-delimited languages
+// ---------------------------------------------------------------------------
+
+/// What the header in front of a block's `{` declares.
+pub(crate) enum BlockHeader {
+    /// A type declaration of the given kind and name.
+    Type(ScopeKind, String),
+    /// A function or method, by display name such as `reload()`.
+    Function(String),
+}
+
+// A leading decorator or annotation such as `@Input()` or `@Override`.
+static DECORATOR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^@[\w.]+(?:\((?:[^()]|\([^()]*\))*\))?\s*").unwrap());
+
+/// Builds the scope around `offset` for languages without a syntax tree:
+/// every brace block containing the offset is classified by its header, and
+/// blocks `classify` does not recognise (loops, closures …) are skipped.
+///
+/// `classify` also returns where in the header the declaration begins, so
+/// statements that languages without semicolons leave in front of it (a
+/// Kotlin property, say) are dropped from the signature and line range.
+pub(crate) fn brace_scope(
+    content: &str,
+    offset: usize,
+    rules: &LexicalRules,
+    classify: impl Fn(&str) -> Option<(usize, BlockHeader)>,
+) -> Option<EnclosingScope> {
+    let blanked = blank_comments_and_literals(content, rules);
+    let blocks = BlockMap::new(content, rules);
+    scope_from_blocks(content, &blanked, &blocks, offset, classify, |open| {
+        block_header_start(&blanked, open)
+    })
+}
+
+/// [`brace_scope`] for indentation-delimited languages, where a block's
+/// header is the logical line ending in its `:`.
+pub(crate) fn indented_scope(
+    content: &str,
+    offset: usize,
+    rules: &LexicalRules,
+    classify: impl Fn(&str) -> Option<(usize, BlockHeader)>,
+) -> Option<EnclosingScope> {
+    let blanked = blank_comments_and_literals(content, rules);
+    let blocks = BlockMap::indented(content, rules);
+    scope_from_blocks(content, &blanked, &blocks, offset, classify, |colon| {
+        logical_line_start(&blanked, colon)
+    })
+}
+
+fn scope_from_blocks(
+    content: &str,
+    blanked: &str,
+    blocks: &BlockMap,
+    offset: usize,
+    classify: impl Fn(&str) -> Option<(usize, BlockHeader)>,
+    header_start: impl Fn(usize) -> usize,
+) -> Option<EnclosingScope> {
+    let mut type_path = Vec::new();
+    let mut kind = None;
+    let mut function = None;
+    let mut innermost = None;
+
+    for (open, close) in blocks.containing(offset) {
+        let header_start = header_start(open);
+        let Some((decl, header)) = classify(&blanked[header_start..open]) else {
+            continue;
+        };
+        let decl = header_start + decl;
+        let start = blanked[header_start..decl]
+            .rfind('\n')
+            .map_or(header_start, |newline| header_start + newline + 1);
+        match header {
+            BlockHeader::Type(scope_kind, name) => {
+                type_path.push(name);
+                kind = Some(scope_kind);
+                function = None;
+            }
+            BlockHeader::Function(name) => {
+                let signature = content[start..open].split_whitespace();
+                function = Some(FunctionScope {
+                    name,
+                    signature: signature.collect::<Vec<_>>().join(" "),
+                });
+            }
+        }
+        innermost = Some((start, close));
+    }
+
+    let (start, close) = innermost?;
+    Some(EnclosingScope {
+        type_path,
+        kind,
+        function,
+        lines: line_number(content, start)..=line_number(content, close),
+    })
+}
+
+/// Line-based fallback for `extract_enclosing_type_name`: the last type that
+/// `type_name` finds on a line before the first line holding an instruction
+/// in `grammar`.
+pub(crate) fn last_type_before_marker(
+    content: &str,
+    grammar: &MarkerGrammar,
+    type_name: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    content
+        .lines()
+        .take_while(|line| grammar.find_instruction(line).is_none())
+        .filter_map(type_name)
+        .last()
+}
+
+/// Start of the declaration header in front of the `{` at `open`: just past
+/// the previous `{`, `}` or `;` outside parentheses, then past leading
+/// comments, decorators and annotations.  `blanked` is the source with
+/// comments and literals blanked out, so braces inside them are ignored.
+fn block_header_start(blanked: &str, open: usize) -> usize {
+    let mut start = header_boundary(&blanked.as_bytes()[..open]);
+    loop {
+        let rest = &blanked[start..open];
+        let trimmed = rest.trim_start();
+        start += rest.len() - trimmed.len();
+        match DECORATOR_RE.find(trimmed) {
+            Some(decorator) => start += decorator.end(),
+            None => return start,
+        }
+    }
+}
+
+/// Offset just past the last `{`, `}` or `;` that is not nested in
+/// parentheses, so decorator arguments like `@Component({ … })` stay part
+/// of the header.
+fn header_boundary(before: &[u8]) -> usize {
+    let mut depth = 0usize;
+    for (i, byte) in before.iter().enumerate().rev() {
+        match byte {
+            b')' => depth += 1,
+            b'(' => depth = depth.saturating_sub(1),
+            b'{' | b'}' | b';' if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+    0
+}
+
+/// Start of the logical line holding `offset`, skipping back over lines
+/// continued by open brackets or a trailing backslash, and past indentation.
+fn logical_line_start(blanked: &str, offset: usize) -> usize {
+    let bytes = blanked.as_bytes();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for i in (0..offset).rev() {
+        match bytes[i] {
+            b')' | b']' | b'}' => depth += 1,
+            b'(' | b'[' | b'{' => depth = depth.saturating_sub(1),
+            b'\n' if depth == 0 && !blanked[..i].trim_end_matches('\r').ends_with('\\') => {
+                start = i + 1;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let line = &blanked[start..offset];
+    start + line.len() - line.trim_start().len()
+}
+
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(types: &[&str], function: Option<&str>) -> EnclosingScope {
+        EnclosingScope {
+            type_path: types.iter().map(|t| t.to_string()).collect(),
+            kind: Some(ScopeKind::Class),
+            function: function.map(|name| FunctionScope {
+                name: name.to_string(),
+                signature: String::new(),
+            }),
+            lines: 1..=1,
+        }
+    }
+
+    #[test]
+    fn qualified_name_joins_types_and_function() {
+        assert_eq!(
+            scope(&["Outer", "Inner"], Some("reload()")).qualified_name(),
+            "Outer.Inner.reload()"
+        );
+        assert_eq!(scope(&["Outer"], None).to_string(), "Outer");
+        assert_eq!(scope(&[], Some("main()")).qualified_name(), "main()");
+    }
+
+    #[test]
+    fn block_header_skips_comments_and_decorators() {
+        let src =
+            "class A {}\n// docs\n@Component({ selector: 'x' })\n@Injectable()\nexport class B {";
+        let blanked = blank_comments_and_literals(src, &LexicalRules::C_LIKE);
+        let start = block_header_start(&blanked, src.len() - 1);
+        assert_eq!(&src[start..src.len() - 1], "export class B ");
+        assert_eq!(line_number(src, start), 5);
+    }
+
+    #[test]
+    fn type_name_is_innermost_type() {
+        assert_eq!(scope(&["Outer", "Inner"], None).type_name(), Some("Inner"));
+        assert_eq!(scope(&[], Some("main()")).type_name(), None);
+    }
+}
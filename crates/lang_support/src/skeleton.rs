@@ -0,0 +1,195 @@
+// crates/lang_support/src/skeleton.rs
+
+//! The outline of a source file: declarations, signatures and doc comments
+//! with every function body elided, for files whose API matters more than
+//! their implementation.
+
+use crate::{BlockDelimiting, LanguageSupport};
+use std::ops::Range;
+
+/// What a brace-delimited body is replaced with.
+pub const ELIDED_BODY: &str = "{ … }";
+
+/// `content` with every function body `language` finds replaced: braces
+/// and all by [`ELIDED_BODY`], or, for indentation-delimited languages, by
+/// a single `...` line under the header.
+pub fn skeleton(language: &dyn LanguageSupport, content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut copied = 0;
+    for body in language.body_ranges(content) {
+        if body.start < copied {
+            continue;
+        }
+        out.push_str(&content[copied..body.start]);
+        match language.block_delimiting() {
+            BlockDelimiting::Braces => out.push_str(ELIDED_BODY),
+            BlockDelimiting::Indentation => {
+                out.push('\n');
+                out.push_str(body_indent(&content[body.clone()]));
+                out.push_str("...");
+            }
+        }
+        copied = body.end;
+    }
+    out.push_str(&content[copied..]);
+    out
+}
+
+/// The bodies of the outermost blocks opened on a line that
+/// [`is_function_candidate`](LanguageSupport::is_function_candidate), as
+/// [`LanguageSupport::body_ranges`] describes them.
+pub(crate) fn function_bodies<L>(language: &L, content: &str) -> Vec<Range<usize>>
+where
+    L: LanguageSupport + ?Sized,
+{
+    let indented = language.block_delimiting() == BlockDelimiting::Indentation;
+    let mut bodies: Vec<Range<usize>> = Vec::new();
+    for &(open, close) in language.block_map(content).pairs() {
+        if bodies.last().is_some_and(|body| open < body.end) {
+            continue;
+        }
+        let line_start = content[..open].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[open..]
+            .find('\n')
+            .map_or(content.len(), |i| open + i);
+        if !language.is_function_candidate(&content[line_start..line_end]) {
+            continue;
+        }
+        let body = if indented {
+            after_docstring(content, open + 1)..close
+        } else {
+            open..close + 1
+        };
+        if !body.is_empty() {
+            bodies.push(body);
+        }
+    }
+    bodies
+}
+
+/// Where an indented body starting at `start` goes on past a leading
+/// docstring, which belongs to the outline like any other doc comment.
+fn after_docstring(content: &str, start: usize) -> usize {
+    let body = &content[start..];
+    let first = body.trim_start();
+    let Some(quote) = ["\"\"\"", "'''"].into_iter().find(|q| first.starts_with(q)) else {
+        return start;
+    };
+    let opened = start + (body.len() - first.len()) + quote.len();
+    match content[opened..].find(quote) {
+        Some(i) => {
+            let closed = opened + i + quote.len();
+            content[closed..]
+                .find('\n')
+                .map_or(content.len(), |i| closed + i)
+        }
+        None => start,
+    }
+}
+
+/// The indentation of the first non-blank line of `body`.
+fn body_indent(body: &str) -> &str {
+    let line = body
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::for_extension;
+
+    fn outline(extension: &str, content: &str) -> String {
+        skeleton(for_extension(extension).unwrap(), content)
+    }
+
+    #[test]
+    fn swift_bodies_come_from_the_syntax_tree() {
+        let content = concat!(
+            "import Foundation\n",
+            "\n",
+            "/// A page of items.\n",
+            "struct Feed {\n",
+            "    let items: [Item]\n",
+            "    var count: Int { items.count }\n",
+            "    var limit = 10 {\n",
+            "        didSet { reload() }\n",
+            "    }\n",
+            "\n",
+            "    /// Fetches the next page.\n",
+            "    func load(\n",
+            "        page: Int\n",
+            "    ) async throws -> [Item] {\n",
+            "        let url = \"{\"\n",
+            "        return try await client.fetch(url, page)\n",
+            "    }\n",
+            "}\n",
+        );
+        assert_eq!(
+            outline("swift", content),
+            concat!(
+                "import Foundation\n",
+                "\n",
+                "/// A page of items.\n",
+                "struct Feed {\n",
+                "    let items: [Item]\n",
+                "    var count: Int { … }\n",
+                "    var limit = 10 { … }\n",
+                "\n",
+                "    /// Fetches the next page.\n",
+                "    func load(\n",
+                "        page: Int\n",
+                "    ) async throws -> [Item] { … }\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn other_brace_languages_elide_function_candidate_blocks() {
+        let content = concat!(
+            "class Cache {\n",
+            "    /** Drops everything. */\n",
+            "    fun clear(): Unit {\n",
+            "        if (dirty) { flush() }\n",
+            "    }\n",
+            "}\n",
+        );
+        assert_eq!(
+            outline("kt", content),
+            concat!(
+                "class Cache {\n",
+                "    /** Drops everything. */\n",
+                "    fun clear(): Unit { … }\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn python_bodies_keep_their_docstrings() {
+        let content = concat!(
+            "class Cache:\n",
+            "    def clear(self):\n",
+            "        \"\"\"Drops everything.\"\"\"\n",
+            "        self.items = {}\n",
+            "\n",
+            "    def size(self):\n",
+            "        return len(self.items)\n",
+        );
+        assert_eq!(
+            outline("py", content),
+            concat!(
+                "class Cache:\n",
+                "    def clear(self):\n",
+                "        \"\"\"Drops everything.\"\"\"\n",
+                "        ...\n",
+                "\n",
+                "    def size(self):\n",
+                "        ...\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,516 @@
+// crates/lang_support/src/slices.rs
+
+//! The lines of a file worth showing when it is included for one or two of
+//! its declarations rather than as a whole: those declarations, with their
+//! doc comments and attributes, and the imports they may rely on; or, to
+//! focus on one line, the function around it and what that function uses.
+//!
+//! Lines are 0-based and ranges end-exclusive throughout.
+
+use crate::{BlockDelimiting, DeclarationKind, LanguageSupport};
+use regex::Regex;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// The lines of every import statement in `content`, as
+/// [`is_import`](LanguageSupport::is_import) recognises them.  A statement
+/// whose brackets stay open, such as `import {` or `from app import (`,
+/// runs on until they close.
+pub fn import_lines(language: &dyn LanguageSupport, content: &str) -> Vec<Range<usize>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut imports = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if language.is_import(lines[i]) {
+            let end = statement_end(&lines, i);
+            imports.push(i..end);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    imports
+}
+
+/// The blocks opened on a line whose
+/// [`extract_type_name`](LanguageSupport::extract_type_name) is `name`, or
+/// that [`is_function_candidate`](LanguageSupport::is_function_candidate)
+/// and calls `name`, as [`LanguageSupport::declaration_lines`] describes
+/// them.  A declaration without a block, such as a Kotlin `data class` or a
+/// type alias, ends with its statement.
+pub(crate) fn declarations_named<L>(language: &L, content: &str, name: &str) -> Vec<Range<usize>>
+where
+    L: LanguageSupport + ?Sized,
+{
+    let Ok(function_name) = Regex::new(&format!(r"\b{}\s*[(<]", regex::escape(name))) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let blocks = language.block_map(content);
+    let mut line_start = 0;
+    let mut declarations: Vec<Range<usize>> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let start = line_start;
+        line_start += line.len() + 1;
+        let declares = language.extract_type_name(line).as_deref() == Some(name)
+            || (language.is_function_candidate(line) && function_name.is_match(line));
+        if !declares || declarations.last().is_some_and(|last| i < last.end) {
+            continue;
+        }
+        // The body opens at the first block after the header, unless the
+        // header ends first.
+        let body = blocks
+            .pairs()
+            .iter()
+            .find(|&&(open, _)| open >= start)
+            .filter(|&&(open, _)| {
+                let header = &content[start..open];
+                !header.contains(';') && !header.contains("\n\n")
+            });
+        let end = match body {
+            Some(&(_, close)) => line_of(content, close) + 1,
+            None => statement_end(&lines, i),
+        };
+        declarations.push(with_leading_comments(language, &lines, i)..end);
+    }
+    declarations
+}
+
+/// The lines to keep of `content` to focus on line `line`, usually a TODO
+/// marker: the imports, the function around the line, the headers and
+/// closing lines of the blocks around that function, and the members of
+/// its enclosing type it mentions by name.  `None` when no block encloses
+/// the line.
+///
+/// The function is the outermost enclosing block declared as one, so
+/// closures inside it belong to it; failing that, the block directly inside
+/// the innermost enclosing type, such as an initializer or a computed
+/// property.  A line directly in a type's body is kept on its own.
+pub fn focus_lines(
+    language: &dyn LanguageSupport,
+    content: &str,
+    line: usize,
+) -> Option<Vec<Range<usize>>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let offset: usize = content.split_inclusive('\n').take(line).map(str::len).sum();
+    let blocks = language.block_map(content);
+    let enclosing: Vec<Block> = blocks
+        .containing(offset)
+        .map(|(open, close)| Block::new(language, content, &lines, open, close))
+        .collect();
+    let innermost = enclosing.len().checked_sub(1)?;
+    let innermost_type = enclosing.iter().rposition(|block| block.is_type);
+    let f = enclosing
+        .iter()
+        .position(|block| block.is_function)
+        .or(match innermost_type {
+            Some(t) if t < innermost => Some(t + 1),
+            // Directly in a type's body: only the line itself.
+            Some(_) => None,
+            None => Some(innermost),
+        });
+
+    let mut kept = import_lines(language, content);
+    for outer in &enclosing[..f.unwrap_or(enclosing.len())] {
+        kept.push(outer.header.clone());
+        kept.extend(outer.closing_line.map(|i| i..i + 1));
+    }
+    let Some(f) = f else {
+        kept.push(with_leading_comments(language, &lines, line)..line + 1);
+        return Some(kept);
+    };
+
+    let function = &enclosing[f];
+    kept.push(with_leading_comments(language, &lines, function.header.start)..function.end);
+    if let Some(parent) = f
+        .checked_sub(1)
+        .map(|p| &enclosing[p])
+        .filter(|p| p.is_type)
+    {
+        let used: HashSet<&str> = content[function.open..function.close]
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .collect();
+        kept.extend(
+            members(language, content, &lines, parent)
+                .into_iter()
+                .filter(|member| member.start != function.header.start)
+                .filter(|member| {
+                    member_name(lines[member.start])
+                        .is_some_and(|name| used.contains(name.as_str()))
+                })
+                .map(|member| with_leading_comments(language, &lines, member.start)..member.end),
+        );
+    }
+    Some(kept)
+}
+
+/// A block of a file, with the lines of the declaration that opens it.
+struct Block {
+    open: usize,
+    close: usize,
+    /// The lines from the declaration's first to the one opening the block.
+    header: Range<usize>,
+    /// The line after the block.
+    end: usize,
+    /// The line of its closing brace, for brace-delimited blocks.
+    closing_line: Option<usize>,
+    is_function: bool,
+    is_type: bool,
+}
+
+impl Block {
+    fn new(
+        language: &dyn LanguageSupport,
+        content: &str,
+        lines: &[&str],
+        open: usize,
+        close: usize,
+    ) -> Self {
+        let open_line = line_of(content, open);
+        let header = header_start(lines, open_line)..open_line + 1;
+        let header_text = lines[header.clone()].join("\n");
+        let declared = language.declarations(header_text.trim_start());
+        let declares = |kinds: &[DeclarationKind]| {
+            declared
+                .iter()
+                .any(|declaration| kinds.contains(&declaration.kind))
+        };
+        let closing_line = match language.block_delimiting() {
+            BlockDelimiting::Braces => Some(line_of(content, close)),
+            BlockDelimiting::Indentation => None,
+        };
+        Self {
+            open,
+            close,
+            is_function: lines[header.clone()]
+                .iter()
+                .any(|line| language.is_function_candidate(line))
+                || declares(&[DeclarationKind::Function]),
+            is_type: lines[header.clone()].iter().any(|line| {
+                language.is_type_candidate(line) || language.extract_type_name(line).is_some()
+            }) || declares(&[DeclarationKind::Type, DeclarationKind::Extension]),
+            header,
+            end: line_of(content, close) + 1,
+            closing_line,
+        }
+    }
+}
+
+/// The members declared directly in `parent`'s body, each from its first
+/// line to its last; comments and attributes are left to the member below.
+fn members(
+    language: &dyn LanguageSupport,
+    content: &str,
+    lines: &[&str],
+    parent: &Block,
+) -> Vec<Range<usize>> {
+    let mut children: Vec<(usize, usize)> = Vec::new();
+    for &(open, close) in language.block_map(content).pairs() {
+        let nested = children.last().is_some_and(|&(_, last)| open < last);
+        if parent.open < open && close < parent.close && !nested {
+            children.push((open, close));
+        }
+    }
+    let mut children = children.into_iter().map(|(open, close)| {
+        header_start(lines, line_of(content, open))..line_of(content, close) + 1
+    });
+
+    let body_end = parent.closing_line.unwrap_or(parent.end);
+    let mut next_child = children.next();
+    let mut members = Vec::new();
+    let mut i = parent.header.end;
+    while i < body_end {
+        let member = match &next_child {
+            Some(child) if child.start <= i => {
+                let member = i..child.end;
+                next_child = children.next();
+                member
+            }
+            _ => i..i + 1,
+        };
+        i = member.end.max(i + 1);
+        let first = lines[member.start];
+        if !first.trim().is_empty() && !annotates(language, first) {
+            members.push(member);
+        }
+    }
+    members
+}
+
+/// The name a member's first line declares: the last word before its
+/// parameters, type, value or body, e.g. `load` for `func load<T>(` and
+/// `items` for `private(set) var items: [Item] = []`.
+fn member_name(header: &str) -> Option<String> {
+    let mut plain = String::new();
+    let mut generics = 0;
+    for c in header.replace("(set)", "").chars() {
+        match c {
+            '<' => generics += 1,
+            '>' if generics > 0 => generics -= 1,
+            _ if generics == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    let end = plain.find(['(', ':', '=', '{', ';']).unwrap_or(plain.len());
+    plain[..end]
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .rfind(|word| !word.is_empty())
+        .map(str::to_string)
+}
+
+/// The first line of a declaration whose block opens on `open_line`, going
+/// up while a parameter list spans several lines.
+fn header_start(lines: &[&str], open_line: usize) -> usize {
+    let parens = |line: &str| line.matches('(').count() as i32 - line.matches(')').count() as i32;
+    let mut start = open_line;
+    let mut depth = parens(lines[open_line]);
+    while depth < 0 && start > 0 {
+        start -= 1;
+        depth += parens(lines[start]);
+    }
+    start
+}
+
+/// Moves `start` up over the doc comments, attributes and decorators
+/// directly above line `start`.
+pub(crate) fn with_leading_comments<L>(language: &L, lines: &[&str], mut start: usize) -> usize
+where
+    L: LanguageSupport + ?Sized,
+{
+    while start > 0 && annotates(language, lines[start - 1]) {
+        start -= 1;
+    }
+    start
+}
+
+/// Whether `line` is a comment, attribute or decorator, which belong to the
+/// declaration below them.
+fn annotates<L>(language: &L, line: &str) -> bool
+where
+    L: LanguageSupport + ?Sized,
+{
+    let rules = language.lexical_rules();
+    let line = line.trim_start();
+    rules
+        .line_comments
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || rules.block_comment.is_some_and(|(open, close)| {
+            line.starts_with(open) || line.starts_with('*') || line.ends_with(close)
+        })
+        || line.starts_with('@')
+}
+
+/// The line after the statement starting on line `i`, which goes on while
+/// its parentheses, brackets or braces are open.
+fn statement_end(lines: &[&str], i: usize) -> usize {
+    let mut depth = 0i32;
+    for (j, line) in lines.iter().enumerate().skip(i) {
+        for c in line.chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth <= 0 {
+            return j + 1;
+        }
+    }
+    lines.len()
+}
+
+/// The line holding byte `offset` of `content`.
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::for_extension;
+
+    fn lines_of<'a>(content: &'a str, ranges: &[Range<usize>]) -> Vec<Vec<&'a str>> {
+        let lines: Vec<&str> = content.lines().collect();
+        ranges
+            .iter()
+            .map(|range| lines[range.clone()].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn swift_declarations_come_with_their_doc_comments_and_extensions() {
+        let content = concat!(
+            "import Foundation\n",
+            "@testable import App\n",
+            "\n",
+            "struct Other {}\n",
+            "\n",
+            "/// A signed-in user.\n",
+            "@MainActor\n",
+            "final class User {\n",
+            "    struct User {}\n",
+            "    let name: String\n",
+            "}\n",
+            "\n",
+            "extension User: Codable {}\n",
+            "typealias Users = [User]\n",
+            "func greet(_ user: User) {}\n",
+        );
+        let swift = for_extension("swift").unwrap();
+
+        assert_eq!(
+            lines_of(content, &import_lines(swift, content)),
+            vec![vec!["import Foundation"], vec!["@testable import App"]]
+        );
+        assert_eq!(
+            swift.declaration_lines(content, "User"),
+            vec![5..11, 12..13]
+        );
+        assert_eq!(swift.declaration_lines(content, "Users"), vec![13..14]);
+        assert_eq!(swift.declaration_lines(content, "greet"), vec![14..15]);
+        assert!(swift.declaration_lines(content, "Missing").is_empty());
+    }
+
+    #[test]
+    fn other_languages_find_declarations_from_their_headers() {
+        let kotlin = for_extension("kt").unwrap();
+        let content = concat!(
+            "import app.Base\n",
+            "\n",
+            "// A point on the map.\n",
+            "data class Point(\n",
+            "    val x: Int,\n",
+            "    val y: Int,\n",
+            ")\n",
+            "\n",
+            "class Map {\n",
+            "    val points = listOf<Point>()\n",
+            "}\n",
+        );
+        assert_eq!(kotlin.declaration_lines(content, "Point"), vec![2..7]);
+        assert_eq!(kotlin.declaration_lines(content, "Map"), vec![8..11]);
+
+        let python = for_extension("py").unwrap();
+        let content = concat!(
+            "from app.models import (\n",
+            "    Base,\n",
+            ")\n",
+            "\n",
+            "@dataclass\n",
+            "class User(Base):\n",
+            "    name: str\n",
+            "\n",
+            "    def greet(self):\n",
+            "        return self.name\n",
+            "\n",
+            "def helper():\n",
+            "    pass\n",
+        );
+        assert_eq!(import_lines(python, content), vec![0..3]);
+        let helper = 11..13;
+        assert_eq!(python.declaration_lines(content, "helper"), vec![helper]);
+        assert_eq!(python.declaration_lines(content, "User"), vec![4..10]);
+    }
+
+    fn focus(extension: &str, content: &str, line: usize) -> Vec<String> {
+        let language = for_extension(extension).unwrap();
+        let mut kept = focus_lines(language, content, line).unwrap();
+        kept.sort_by_key(|range| range.start);
+        lines_of(content, &kept)
+            .concat()
+            .iter()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn swift_focus_keeps_the_function_and_the_members_it_uses() {
+        let content = concat!(
+            "import SwiftUI\n",
+            "\n",
+            "final class FeedModel {\n",
+            "    /// Loaded so far.\n",
+            "    private(set) var items: [Item] = []\n",
+            "    var title = \"Feed\"\n",
+            "\n",
+            "    func reset() {\n",
+            "        items = []\n",
+            "    }\n",
+            "\n",
+            "    func load(\n",
+            "        page: Int\n",
+            "    ) async {\n",
+            "        Task {\n",
+            "            // TODO: - paginate\n",
+            "            items += await fetch(page)\n",
+            "        }\n",
+            "    }\n",
+            "\n",
+            "    private func fetch(_ page: Int) async -> [Item] {\n",
+            "        []\n",
+            "    }\n",
+            "}\n",
+        );
+        assert_eq!(
+            focus("swift", content, 15),
+            vec![
+                "import SwiftUI",
+                "final class FeedModel {",
+                "    /// Loaded so far.",
+                "    private(set) var items: [Item] = []",
+                "    func load(",
+                "        page: Int",
+                "    ) async {",
+                "        Task {",
+                "            // TODO: - paginate",
+                "            items += await fetch(page)",
+                "        }",
+                "    }",
+                "    private func fetch(_ page: Int) async -> [Item] {",
+                "        []",
+                "    }",
+                "}",
+            ]
+        );
+        assert_eq!(
+            focus("swift", content, 5),
+            vec![
+                "import SwiftUI",
+                "final class FeedModel {",
+                "    var title = \"Feed\"",
+                "}",
+            ]
+        );
+        assert!(focus_lines(for_extension("swift").unwrap(), content, 0).is_none());
+    }
+
+    #[test]
+    fn python_focus_follows_indentation() {
+        let content = concat!(
+            "import json\n",
+            "\n",
+            "class Store:\n",
+            "    path = \"store.json\"\n",
+            "    limit = 10\n",
+            "\n",
+            "    def save(self, data):\n",
+            "        # TODO: - write atomically\n",
+            "        json.dump(data, open(self.path, \"w\"))\n",
+            "\n",
+            "    def load(self):\n",
+            "        return json.load(open(self.path))\n",
+        );
+        assert_eq!(
+            focus("py", content, 7),
+            vec![
+                "import json",
+                "class Store:",
+                "    path = \"store.json\"",
+                "    def save(self, data):",
+                "        # TODO: - write atomically",
+                "        json.dump(data, open(self.path, \"w\"))",
+            ]
+        );
+    }
+}
@@ -2,15 +2,32 @@
 
 //!
 //! * `extract_identifiers` -- very similar to the old `TypeExtractor`: grabs
-//!   capitalised type names **and** unqualified function calls so that helper
+//!   declared type names **and** unqualified function calls so that helper
 //!   methods (`foo()` -> `func foo`) are pulled in.
-//! * `file_defines_any`    -- mirrors the old `SwiftMatcher`: reports *true* if
-//!   the file declares **any** of the requested identifiers.
-
-use super::LanguageSupport;
+//! * `declarations`        -- the declared types and functions and the types
+//!   named by `extension` blocks, which back `file_defines_any` (the old
+//!   `SwiftMatcher`) and the symbol index.
+//! * `body_ranges`         -- function, accessor and observer bodies, which
+//!   a skeleton elides.
+//! * `declaration_lines`   -- where a type and its extensions, or a
+//!   function, are declared, for showing a file's matched declarations only.
+//!
+//! Both are driven by a tree‑sitter syntax tree, so declarations and calls
+//! inside comments or string literals are ignored, and `actor`, `extension`,
+//! nested and generic declarations are seen.  The original regex scan is kept
+//! as a fallback for sources the grammar cannot parse cleanly.
+
+use super::scope::last_type_before_marker;
+use super::{
+    skeleton, slices, Declaration, DeclarationKind, EnclosingScope, FunctionScope, Interpolation,
+    LanguageSupport, LexicalRules, ScopeKind,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use todo_marker::TODO_MARKER;
+use std::cell::RefCell;
+use std::ops::Range;
+use todo_marker::MarkerGrammar;
+use tree_sitter::{Node, Parser, Tree};
 
 pub(super) struct SwiftSupport;
 pub(super) const SWIFT: SwiftSupport = SwiftSupport;
@@ -35,12 +52,35 @@ static SWIFT_CLASS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*class\s+\w+.
 
 static SWIFT_ENUM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^\s*enum\s+\w+.*\{"#).unwrap());
 
+// Fallback declaration scan: `class Foo`, `func foo(`, `extension Foo`
+static TYPE_DECL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:class|struct|enum|protocol|typealias)\s+(\w+)").unwrap());
+
+static FUNC_DECL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bfunc\s+(\w+)\s*\(").unwrap());
+
+static EXTENSION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bextension\s+(\w+)").unwrap());
+
 static TYPE_NAME_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(class|struct|enum)\s+(\w+)").unwrap());
 
 // Matches a *call-site* that looks like `identifier(`
 static CALL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b([A-Za-z_][A-Za-z0-9_]*)\s*\(").unwrap());
 
+// Nested block comments, `"""` multi-line and `#"…"#` raw strings, and
+// `\(…)` interpolation.
+static SWIFT_LEXICAL_RULES: LexicalRules = LexicalRules {
+    nested_block_comments: true,
+    quotes: b"\"",
+    triple_quoted_strings: true,
+    raw_hash_strings: true,
+    interpolation: Some(Interpolation {
+        quote: b'"',
+        open: "\\(",
+        close: b')',
+    }),
+    ..LexicalRules::C_LIKE
+};
+
 // Reserved words & common keywords we don't want as identifiers
 static RESERVED: &[&str] = &[
     "if", "for", "while", "switch", "guard", "return", "catch", "throw", "init", "deinit",
@@ -50,62 +90,372 @@ fn is_reserved(word: &str) -> bool {
     RESERVED.binary_search(&word).is_ok()
 }
 
+fn starts_lowercase(ident: &str) -> bool {
+    ident
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_lowercase())
+        .unwrap_or(false)
+}
+
+fn push_unique(out: &mut Vec<String>, ident: &str) {
+    if !out.iter().any(|existing| existing == ident) {
+        out.push(ident.to_string());
+    }
+}
+
 // ---------------------------------------------------------------------------
-//  Trait impl
+//  Syntax tree
 // ---------------------------------------------------------------------------
 
-impl LanguageSupport for SwiftSupport {
-    /// Collects type names *and* free function / static method identifiers.
-    fn extract_identifiers(&self, src: &str) -> Vec<String> {
-        let mut out = Vec::new();
+thread_local! {
+    // Parsers are not `Sync`; keep one per thread and reuse it across calls.
+    static PARSER: RefCell<Option<Parser>> = RefCell::new(new_parser());
+}
+
+fn new_parser() -> Option<Parser> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_swift::LANGUAGE.into())
+        .ok()?;
+    Some(parser)
+}
 
-        for cap in DECL_RE.captures_iter(src) {
-            let ident = &cap[1];
-            if !out.contains(&ident.to_string()) {
-                out.push(ident.to_string());
+/// Parses `src` into a syntax tree.
+///
+/// Returns `None` when the grammar is unavailable or the tree contains syntax
+/// errors, so callers fall back to the regex scan rather than trusting a
+/// partially recovered tree.
+fn parse(src: &str) -> Option<Tree> {
+    let tree = PARSER.with(|parser| parser.borrow_mut().as_mut()?.parse(src, None))?;
+    if tree.root_node().has_error() {
+        return None;
+    }
+    Some(tree)
+}
+
+/// Names harvested from a Swift syntax tree.
+#[derive(Default)]
+struct SwiftSymbols {
+    /// Type names introduced by `class`, `struct`, `enum`, `actor`,
+    /// `protocol` and `typealias` declarations, at any nesting depth.
+    declared_types: Vec<String>,
+    /// Types named by `extension Foo` blocks.
+    extended_types: Vec<String>,
+    /// Functions and methods declared anywhere in the file, including
+    /// protocol requirements.
+    declared_functions: Vec<String>,
+    /// Callee names of call-sites, e.g. `fetch` for `client.fetch()`.
+    calls: Vec<String>,
+}
+
+impl SwiftSymbols {
+    fn from_tree(tree: &Tree, src: &str) -> Self {
+        let mut symbols = Self::default();
+        symbols.visit(tree.root_node(), src.as_bytes());
+        symbols
+    }
+
+    fn visit(&mut self, node: Node, src: &[u8]) {
+        match node.kind() {
+            "class_declaration" | "protocol_declaration" | "typealias_declaration" => {
+                if let Some(name) = declared_type_name(node, src) {
+                    if declaration_kind(node) == Some("extension") {
+                        push_unique(&mut self.extended_types, name);
+                    } else {
+                        push_unique(&mut self.declared_types, name);
+                    }
+                }
+            }
+            "function_declaration" | "protocol_function_declaration" => {
+                if let Some(name) = node
+                    .child_by_field_name("name")
+                    .and_then(|name| name.utf8_text(src).ok())
+                {
+                    push_unique(&mut self.declared_functions, name);
+                }
             }
+            "call_expression" => {
+                if let Some(name) = node
+                    .named_child(0)
+                    .and_then(|callee| callee_name(callee, src))
+                {
+                    push_unique(&mut self.calls, name);
+                }
+            }
+            _ => {}
         }
 
-        for cap in CALL_RE.captures_iter(src) {
-            let ident = &cap[1];
-            if !is_reserved(ident)
-                && ident
-                    .chars()
-                    .next()
-                    .map(|c| c.is_ascii_lowercase())
-                    .unwrap_or(false)
-                && !out.contains(&ident.to_string())
-            {
-                out.push(ident.to_string());
-            }
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.visit(child, src);
         }
+    }
+}
 
-        out
+/// The keyword (`class`, `struct`, `extension` …) of a type declaration.
+fn declaration_kind(node: Node) -> Option<&'static str> {
+    node.child_by_field_name("declaration_kind")
+        .map(|kind| kind.kind())
+}
+
+/// The bare type name of a declaration, without generic parameters. For
+/// `extension Foo` the name is wrapped in a `user_type`.
+fn declared_type_name<'a>(node: Node, src: &'a [u8]) -> Option<&'a str> {
+    let name = node.child_by_field_name("name")?;
+    let ident = match name.kind() {
+        "type_identifier" => name,
+        "user_type" => name.named_child(0)?,
+        _ => return None,
+    };
+    ident.utf8_text(src).ok()
+}
+
+/// The name being called: `foo` for `foo()`, `run` for `self.helper.run()`.
+fn callee_name<'a>(callee: Node, src: &'a [u8]) -> Option<&'a str> {
+    match callee.kind() {
+        "simple_identifier" => callee.utf8_text(src).ok(),
+        "navigation_expression" => callee
+            .child_by_field_name("suffix")?
+            .child_by_field_name("suffix")
+            .filter(|suffix| suffix.kind() == "simple_identifier")?
+            .utf8_text(src)
+            .ok(),
+        _ => None,
     }
+}
 
-    /// Returns *true* if the file defines **any** of the requested identifiers.
-    fn file_defines_any(&self, file_content: &str, idents: &[String]) -> bool {
-        for ident in idents {
-            let pattern = format!(
-                r"\b(?:class|struct|enum|protocol|typealias)\s+{}\b",
-                regex::escape(ident)
-            );
-            if Regex::new(&pattern)
-                .map(|re| re.is_match(file_content))
-                .unwrap_or(false)
-            {
-                return true;
+// ---------------------------------------------------------------------------
+//  Enclosing scope
+// ---------------------------------------------------------------------------
+
+/// Walks from the root towards `offset`, recording every type and function
+/// declaration whose span contains it.
+fn scope_at(tree: &Tree, src: &str, offset: usize) -> Option<EnclosingScope> {
+    let bytes = src.as_bytes();
+    let mut type_path = Vec::new();
+    let mut kind = None;
+    let mut function = None;
+    let mut innermost = None;
+
+    let mut node = tree.root_node();
+    while let Some(child) = child_containing(node, offset) {
+        match child.kind() {
+            "class_declaration" | "protocol_declaration" => {
+                if let Some(name) = declared_type_name(child, bytes) {
+                    type_path.push(name.to_string());
+                    kind = scope_kind(child);
+                    function = None;
+                    innermost = Some(child);
+                }
+            }
+            "function_declaration" | "init_declaration" | "deinit_declaration" => {
+                if let Some(scope) = function_scope(child, bytes) {
+                    function = Some(scope);
+                    innermost = Some(child);
+                }
             }
+            _ => {}
+        }
+        node = child;
+    }
+
+    let innermost = innermost?;
+    Some(EnclosingScope {
+        type_path,
+        kind,
+        function,
+        lines: innermost.start_position().row + 1..=innermost.end_position().row + 1,
+    })
+}
+
+fn child_containing(node: Node, offset: usize) -> Option<Node> {
+    let mut cursor = node.walk();
+    let found = node
+        .named_children(&mut cursor)
+        .find(|child| child.start_byte() <= offset && offset < child.end_byte());
+    found
+}
+
+fn scope_kind(node: Node) -> Option<ScopeKind> {
+    if node.kind() == "protocol_declaration" {
+        return Some(ScopeKind::Protocol);
+    }
+    match declaration_kind(node)? {
+        "class" => Some(ScopeKind::Class),
+        "struct" => Some(ScopeKind::Struct),
+        "enum" => Some(ScopeKind::Enum),
+        "actor" => Some(ScopeKind::Actor),
+        "extension" => Some(ScopeKind::Extension),
+        _ => None,
+    }
+}
+
+/// Describes a function, `init` or `deinit` by its selector-style name,
+/// e.g. `reload(id:_:)`, and its header text.
+fn function_scope(node: Node, src: &[u8]) -> Option<FunctionScope> {
+    let base = match node.kind() {
+        "init_declaration" => "init",
+        "deinit_declaration" => return Some(function_header(node, src, "deinit".to_string())),
+        _ => node.child_by_field_name("name")?.utf8_text(src).ok()?,
+    };
+
+    let mut cursor = node.walk();
+    let labels: String = node
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "parameter")
+        .filter_map(|param| {
+            param
+                .child_by_field_name("external_name")
+                .or_else(|| param.child_by_field_name("name"))
+                .and_then(|label| label.utf8_text(src).ok())
+        })
+        .map(|label| format!("{label}:"))
+        .collect();
+
+    Some(function_header(node, src, format!("{base}({labels})")))
+}
+
+fn function_header(node: Node, src: &[u8], name: String) -> FunctionScope {
+    let end = node
+        .child_by_field_name("body")
+        .map_or(node.end_byte(), |body| body.start_byte());
+    let header = String::from_utf8_lossy(&src[node.start_byte()..end]);
+    FunctionScope {
+        name,
+        signature: header.split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Skeleton
+// ---------------------------------------------------------------------------
+
+/// Function, initializer and accessor bodies and property observer blocks
+/// under `node`, braces included, outermost only.
+fn collect_bodies(node: Node, bodies: &mut Vec<Range<usize>>) {
+    if matches!(
+        node.kind(),
+        "function_body" | "computed_property" | "willset_didset_block"
+    ) {
+        bodies.push(node.byte_range());
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_bodies(child, bodies);
+    }
+}
+
+// ---------------------------------------------------------------------------
+//  Declaration lines
+// ---------------------------------------------------------------------------
+
+/// The `class`, `struct`, `enum`, `actor`, `protocol`, `typealias`,
+/// `extension` and `func` declarations of `name` under `node`, outermost
+/// only, as row ranges.
+fn collect_declarations(node: Node, src: &[u8], name: &str, rows: &mut Vec<Range<usize>>) {
+    let declared = match node.kind() {
+        "class_declaration" | "protocol_declaration" | "typealias_declaration" => {
+            declared_type_name(node, src)
+        }
+        "function_declaration" | "protocol_function_declaration" => node
+            .child_by_field_name("name")
+            .and_then(|name| name.utf8_text(src).ok()),
+        _ => None,
+    };
+    if declared == Some(name) {
+        rows.push(node.start_position().row..node.end_position().row + 1);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_declarations(child, src, name, rows);
+    }
+}
 
-            let func_pat = format!(r"\bfunc\s+{}\s*\(", regex::escape(ident));
-            if Regex::new(&func_pat)
-                .map(|re| re.is_match(file_content))
-                .unwrap_or(false)
-            {
-                return true;
+// ---------------------------------------------------------------------------
+//  Regex fallback
+// ---------------------------------------------------------------------------
+
+fn extract_identifiers_regex(src: &str) -> Vec<String> {
+    let mut out = Vec::new();
+
+    for cap in DECL_RE.captures_iter(src) {
+        push_unique(&mut out, &cap[1]);
+    }
+
+    for cap in CALL_RE.captures_iter(src) {
+        let ident = &cap[1];
+        if !is_reserved(ident) && starts_lowercase(ident) {
+            push_unique(&mut out, ident);
+        }
+    }
+
+    out
+}
+
+fn declarations_regex(file_content: &str) -> Vec<Declaration> {
+    let types = TYPE_DECL_RE
+        .captures_iter(file_content)
+        .map(|caps| Declaration::new(&caps[1], DeclarationKind::Type));
+    let functions = FUNC_DECL_RE
+        .captures_iter(file_content)
+        .map(|caps| Declaration::new(&caps[1], DeclarationKind::Function));
+    let extensions = EXTENSION_RE
+        .captures_iter(file_content)
+        .map(|caps| Declaration::new(&caps[1], DeclarationKind::Extension));
+    types.chain(functions).chain(extensions).collect()
+}
+
+// ---------------------------------------------------------------------------
+//  Trait impl
+// ---------------------------------------------------------------------------
+
+impl LanguageSupport for SwiftSupport {
+    /// Collects type names *and* free function / static method identifiers.
+    fn extract_identifiers(&self, src: &str) -> Vec<String> {
+        let Some(tree) = parse(src) else {
+            return extract_identifiers_regex(src);
+        };
+        let symbols = SwiftSymbols::from_tree(&tree, src);
+
+        let mut out = Vec::new();
+        for ident in symbols.declared_types.iter().chain(&symbols.extended_types) {
+            push_unique(&mut out, ident);
+        }
+        for ident in &symbols.calls {
+            if !is_reserved(ident) && starts_lowercase(ident) {
+                push_unique(&mut out, ident);
             }
         }
-        false
+        out
+    }
+
+    /// Types, functions and extended types declared anywhere in the file.
+    ///
+    /// `extension Foo` blocks extend a type rather than define it, so they
+    /// are reported as extensions of `Foo`; declarations inside them are
+    /// reported as usual.
+    fn declarations(&self, file_content: &str) -> Vec<Declaration> {
+        let Some(tree) = parse(file_content) else {
+            return declarations_regex(file_content);
+        };
+        let symbols = SwiftSymbols::from_tree(&tree, file_content);
+
+        let types = symbols
+            .declared_types
+            .into_iter()
+            .map(|name| Declaration::new(name, DeclarationKind::Type));
+        let functions = symbols
+            .declared_functions
+            .into_iter()
+            .map(|name| Declaration::new(name, DeclarationKind::Function));
+        let extensions = symbols
+            .extended_types
+            .into_iter()
+            .map(|name| Declaration::new(name, DeclarationKind::Extension));
+        types.chain(functions).chain(extensions).collect()
     }
 
     fn is_function_candidate(&self, line: &str) -> bool {
@@ -127,17 +477,42 @@ impl LanguageSupport for SwiftSupport {
         true
     }
 
-    fn extract_enclosing_type_name(&self, content: &str) -> Option<String> {
-        let mut last_type: Option<String> = None;
-        for line in content.lines() {
-            if line.contains(TODO_MARKER) {
-                break;
-            }
-            if let Some(name) = self.extract_type_name(line) {
-                last_type = Some(name);
-            }
-        }
-        last_type
+    fn extract_enclosing_type_name(
+        &self,
+        content: &str,
+        grammar: &MarkerGrammar,
+    ) -> Option<String> {
+        last_type_before_marker(content, grammar, |line| self.extract_type_name(line))
+    }
+
+    fn enclosing_scope(&self, content: &str, offset: usize) -> Option<EnclosingScope> {
+        scope_at(&parse(content)?, content, offset)
+    }
+
+    fn body_ranges(&self, content: &str) -> Vec<Range<usize>> {
+        let Some(tree) = parse(content) else {
+            return skeleton::function_bodies(self, content);
+        };
+        let mut bodies = Vec::new();
+        collect_bodies(tree.root_node(), &mut bodies);
+        bodies
+    }
+
+    /// Extensions of `name` in the same file count as its declarations.
+    fn declaration_lines(&self, content: &str, name: &str) -> Vec<Range<usize>> {
+        let Some(tree) = parse(content) else {
+            return slices::declarations_named(self, content, name);
+        };
+        let mut rows = Vec::new();
+        collect_declarations(tree.root_node(), content.as_bytes(), name, &mut rows);
+        let lines: Vec<&str> = content.lines().collect();
+        rows.into_iter()
+            .map(|rows| slices::with_leading_comments(self, &lines, rows.start)..rows.end)
+            .collect()
+    }
+
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        &SWIFT_LEXICAL_RULES
     }
 }
 
@@ -145,6 +520,52 @@ impl LanguageSupport for SwiftSupport {
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_rejects_source_with_syntax_errors() {
+        assert!(parse("class Foo { func bar() {} }").is_some());
+        assert!(parse("class Foo { func bar( {").is_none());
+    }
+
+    #[test]
+    fn symbols_cover_actor_extension_nested_and_generic_declarations() {
+        let src = "\
+actor Cache<Key: Hashable> {
+    struct Entry {}
+    func lookup<T>(key: T) {}
+}
+extension Widget {
+    func render() {}
+}
+protocol Loader { func load() }
+typealias Handler = () -> Void
+";
+        let tree = parse(src).expect("valid Swift should parse");
+        let symbols = SwiftSymbols::from_tree(&tree, src);
+        assert_eq!(
+            symbols.declared_types,
+            vec!["Cache", "Entry", "Loader", "Handler"]
+        );
+        assert_eq!(symbols.extended_types, vec!["Widget"]);
+        assert_eq!(symbols.declared_functions, vec!["lookup", "render", "load"]);
+    }
+
+    #[test]
+    fn callee_name_uses_last_navigation_segment() {
+        let src = "self.helper.run()\nWidget.make()\nfetch()\n";
+        let tree = parse(src).expect("valid Swift should parse");
+        let symbols = SwiftSymbols::from_tree(&tree, src);
+        assert_eq!(symbols.calls, vec!["run", "make", "fetch"]);
+    }
+
+    #[test]
+    fn regex_fallback_is_used_for_unparseable_source() {
+        let src = "class Broken {\n    func half( {\n";
+        assert!(SWIFT.file_defines_any(src, &["Broken".to_string()]));
+        assert!(SWIFT
+            .extract_identifiers(src)
+            .contains(&"Broken".to_string()));
+    }
+
     #[test]
     fn function_candidate_plain() {
         assert!(SWIFT.is_function_candidate("func doSomething() {"));
@@ -232,7 +653,7 @@ struct HelperStruct {
     // TODO: - Implement something
 }";
         assert_eq!(
-            SWIFT.extract_enclosing_type_name(content),
+            SWIFT.extract_enclosing_type_name(content, &MarkerGrammar::C_LIKE),
             Some("HelperStruct".to_string())
         );
     }
@@ -244,14 +665,41 @@ class EarlyClass {}
 // TODO: - Do something
 struct LateStruct {}";
         assert_eq!(
-            SWIFT.extract_enclosing_type_name(content),
+            SWIFT.extract_enclosing_type_name(content, &MarkerGrammar::C_LIKE),
             Some("EarlyClass".to_string())
         );
     }
 
+    #[test]
+    fn enclosing_type_stops_at_block_comment_and_custom_markers() {
+        let content = "\
+class EarlyClass {
+    /* TODO: - Do something */
+}
+struct LateStruct {}";
+        assert_eq!(
+            SWIFT.extract_enclosing_type_name(content, &MarkerGrammar::C_LIKE),
+            Some("EarlyClass".to_string())
+        );
+
+        let content = content.replace("/* TODO: -", "// AI: -");
+        let grammar = MarkerGrammar::C_LIKE.with_token("AI: -");
+        assert_eq!(
+            SWIFT.extract_enclosing_type_name(&content, &grammar),
+            Some("EarlyClass".to_string())
+        );
+        assert_eq!(
+            SWIFT.extract_enclosing_type_name(&content, &MarkerGrammar::C_LIKE),
+            Some("LateStruct".to_string())
+        );
+    }
+
     #[test]
     fn enclosing_type_none_when_no_type_precedes_marker() {
         let content = "func doSomething() {}\n// TODO: - Fix something";
-        assert_eq!(SWIFT.extract_enclosing_type_name(content), None);
+        assert_eq!(
+            SWIFT.extract_enclosing_type_name(content, &MarkerGrammar::C_LIKE),
+            None
+        );
     }
 }
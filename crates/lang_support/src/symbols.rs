@@ -0,0 +1,342 @@
+// crates/lang_support/src/symbols.rs
+
+//! Declared names across a whole source collection, gathered in one pass.
+//!
+//! Looking up a few hundred identifiers used to mean one scan of every file
+//! per identifier.  [`SymbolIndex`] asks each file's language for its
+//! [`declarations`](crate::LanguageSupport::declarations) once, and splits
+//! the file into words once, so every later lookup is a map access.
+
+use crate::SourceFile;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// What a declaration introduces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeclarationKind {
+    /// `class`, `struct`, `enum`, `protocol`, `interface`, `typealias` …
+    Type,
+    /// A function or method.
+    Function,
+    /// Any other named binding: a module-level assignment or an export.
+    Value,
+    /// Members added to a type declared elsewhere, e.g. a Swift
+    /// `extension Foo` or an Obj‑C category `@interface Foo (Networking)`.
+    Extension,
+}
+
+/// One name a file declares.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+}
+
+impl Declaration {
+    pub fn new(name: impl Into<String>, kind: DeclarationKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+
+    /// Whether this is a primary definition of its name rather than an
+    /// extension of it.
+    pub fn defines(&self) -> bool {
+        self.kind != DeclarationKind::Extension
+    }
+}
+
+/// What one file contributes to a [`SymbolIndex`]: its distinct
+/// declarations and words, each sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSymbols {
+    pub declarations: Vec<Declaration>,
+    pub words: Vec<String>,
+}
+
+impl FileSymbols {
+    /// Parses and tokenises `source`, reading it if it has not been read.
+    pub fn scan(source: &SourceFile) -> Self {
+        let content = source.content();
+        let declarations: BTreeSet<Declaration> =
+            source.language.declarations(content).into_iter().collect();
+        let words: BTreeSet<&str> = content
+            .split(|c: char| !is_word_char(c))
+            .filter(|word| !word.is_empty())
+            .collect();
+        Self {
+            declarations: declarations.into_iter().collect(),
+            words: words.into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+/// A walked source collection with its declarations and words indexed.
+///
+/// The tables are built on the first lookup, reading every file then, so an
+/// index that is only asked for its [`sources`](Self::sources) reads nothing.
+pub struct SymbolIndex {
+    sources: Vec<SourceFile>,
+    tables: OnceLock<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    /// Declared name → (index into `sources`, kind), one entry per file and
+    /// kind.
+    declarations: HashMap<String, Vec<(usize, DeclarationKind)>>,
+    /// Word → indices into `sources` of the files containing it.
+    words: HashMap<String, Vec<usize>>,
+}
+
+impl Tables {
+    /// Parses and tokenises the files in parallel, then merges the results
+    /// in source order.
+    fn build(sources: &[SourceFile]) -> Self {
+        Self::merge(sources.par_iter().map(FileSymbols::scan).collect())
+    }
+
+    fn merge(per_file: Vec<FileSymbols>) -> Self {
+        let mut tables = Tables::default();
+        for (i, symbols) in per_file.into_iter().enumerate() {
+            for declaration in symbols.declarations {
+                tables
+                    .declarations
+                    .entry(declaration.name)
+                    .or_default()
+                    .push((i, declaration.kind));
+            }
+            for word in symbols.words {
+                tables.words.entry(word).or_default().push(i);
+            }
+        }
+        tables
+    }
+}
+
+impl SymbolIndex {
+    pub fn new(sources: Vec<SourceFile>) -> Self {
+        Self {
+            sources,
+            tables: OnceLock::new(),
+        }
+    }
+
+    /// An index over `sources` whose tables come from `symbols`, one entry
+    /// per source in the same order, e.g. as kept by a persistent cache.
+    pub fn with_symbols(sources: Vec<SourceFile>, symbols: Vec<FileSymbols>) -> Self {
+        assert_eq!(
+            sources.len(),
+            symbols.len(),
+            "one FileSymbols per source file"
+        );
+        Self {
+            sources,
+            tables: OnceLock::from(Tables::merge(symbols)),
+        }
+    }
+
+    fn tables(&self) -> &Tables {
+        self.tables.get_or_init(|| Tables::build(&self.sources))
+    }
+
+    pub fn sources(&self) -> &[SourceFile] {
+        &self.sources
+    }
+
+    /// Every file declaring `name`, with the kind of each declaration.
+    pub fn declarations_of(
+        &self,
+        name: &str,
+    ) -> impl Iterator<Item = (&SourceFile, DeclarationKind)> {
+        self.tables()
+            .declarations
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|&(i, kind)| (&self.sources[i], kind))
+    }
+
+    /// Files holding a primary definition of any of `names`.
+    pub fn files_defining<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a String>,
+    ) -> BTreeSet<PathBuf> {
+        self.files_where(names, Declaration::defines)
+    }
+
+    /// Files that extend any of `names` without defining any of them.
+    pub fn files_extending<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a String> + Clone,
+    ) -> BTreeSet<PathBuf> {
+        let defining = self.files_defining(names.clone());
+        let mut extending = self.files_where(names, |declaration| {
+            declaration.kind == DeclarationKind::Extension
+        });
+        extending.retain(|path| !defining.contains(path));
+        extending
+    }
+
+    /// Files containing `name` as a whole word.
+    pub fn files_mentioning(&self, name: &str) -> BTreeSet<PathBuf> {
+        if !name.is_empty() && name.chars().all(is_word_char) {
+            return self
+                .tables()
+                .words
+                .get(name)
+                .into_iter()
+                .flatten()
+                .map(|&i| self.sources[i].path.clone())
+                .collect();
+        }
+
+        // Not a single word, so not in the word index; scan for it.
+        let Ok(re) = Regex::new(&format!(r"\b{}\b", regex::escape(name))) else {
+            return BTreeSet::new();
+        };
+        self.sources
+            .iter()
+            .filter(|source| re.is_match(source.content()))
+            .map(|source| source.path.clone())
+            .collect()
+    }
+
+    fn files_where<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a String>,
+        keep: impl Fn(&Declaration) -> bool,
+    ) -> BTreeSet<PathBuf> {
+        names
+            .into_iter()
+            .flat_map(|name| {
+                self.declarations_of(name)
+                    .filter(|&(_, kind)| keep(&Declaration::new(name.as_str(), kind)))
+                    .map(|(source, _)| source.path.clone())
+            })
+            .collect()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walk_source_files;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn index_maps_names_to_files_and_kinds() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Feed.swift"),
+            "struct Feed {\n    func load() {}\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("Feed+Cache.swift"), "extension Feed {}\n").unwrap();
+        fs::write(
+            root.join("api.js"),
+            "function fetchFeed() {}\nmodule.exports = fetchFeed\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("models.py"),
+            "class User:\n    pass\nUserId = int\n",
+        )
+        .unwrap();
+
+        let index = SymbolIndex::new(walk_source_files(root));
+
+        let mut feed: Vec<(PathBuf, DeclarationKind)> = index
+            .declarations_of("Feed")
+            .map(|(source, kind)| (source.path.clone(), kind))
+            .collect();
+        feed.sort();
+        assert_eq!(
+            feed,
+            vec![
+                (root.join("Feed+Cache.swift"), DeclarationKind::Extension),
+                (root.join("Feed.swift"), DeclarationKind::Type),
+            ]
+        );
+        let mut kinds: Vec<DeclarationKind> = index
+            .declarations_of("fetchFeed")
+            .map(|(_, kind)| kind)
+            .collect();
+        kinds.sort();
+        assert_eq!(
+            kinds,
+            vec![DeclarationKind::Function, DeclarationKind::Value]
+        );
+
+        assert_eq!(
+            index.files_defining(&names(&["load", "UserId", "Missing"])),
+            BTreeSet::from([root.join("Feed.swift"), root.join("models.py")])
+        );
+        assert_eq!(
+            index.files_extending(&names(&["Feed"])),
+            BTreeSet::from([root.join("Feed+Cache.swift")])
+        );
+    }
+
+    #[test]
+    fn mentions_are_whole_words() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("A.swift"), "let feed = Feed.shared\n").unwrap();
+        fs::write(root.join("B.swift"), "let feeds = FeedList()\n").unwrap();
+
+        let index = SymbolIndex::new(walk_source_files(root));
+
+        assert_eq!(
+            index.files_mentioning("Feed"),
+            BTreeSet::from([root.join("A.swift")])
+        );
+        assert_eq!(
+            index.files_mentioning("Feed.shared"),
+            BTreeSet::from([root.join("A.swift")])
+        );
+        assert!(index.files_mentioning("Missing").is_empty());
+    }
+
+    #[test]
+    fn prebuilt_symbols_stand_in_for_the_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("Feed.swift");
+        fs::write(&path, "struct Feed {}\nlet x = Cache()\n").unwrap();
+
+        let sources = walk_source_files(dir.path());
+        let symbols = FileSymbols::scan(&sources[0]);
+        assert_eq!(
+            symbols.declarations,
+            vec![Declaration::new("Feed", DeclarationKind::Type)]
+        );
+        assert_eq!(
+            symbols.words,
+            names(&["Cache", "Feed", "let", "struct", "x"])
+        );
+
+        fs::write(&path, "struct Renamed {}\n").unwrap();
+        let index = SymbolIndex::with_symbols(walk_source_files(dir.path()), vec![symbols]);
+        assert_eq!(
+            index.files_defining(&names(&["Feed"])),
+            BTreeSet::from([path.clone()])
+        );
+        assert_eq!(index.files_mentioning("Cache"), BTreeSet::from([path]));
+    }
+}
@@ -0,0 +1,338 @@
+// crates/lang_support/src/typescript.rs
+
+//!
+//! TypeScript builds on the JavaScript support and adds the declarations
+//! JavaScript lacks:
+//! * **extract_identifiers** -- call-sites (including generic ones such as
+//!   `useState<User>(…)`), decorators and declared `interface`, `type`,
+//!   `enum` and `class` names.
+//! * **declarations**       -- every JavaScript form plus `interface Foo`,
+//!   `type Foo =`, `enum Foo`, `abstract class Foo`, `namespace Foo` and
+//!   `export default …` declarations.
+//! * **enclosing scopes**   -- class, interface and enum blocks and the
+//!   method around the TODO, so `--include-references` works for TS.
+//!
+//! `.tsx` files share everything except the lexical rules: `/` is never
+//! taken to start a regex literal there, since JSX closing tags such as
+//! `</div>` would be misread as one.
+
+use super::javascript::JS;
+use super::scope::{brace_scope, last_type_before_marker, BlockHeader};
+use super::{
+    Declaration, DeclarationKind, EnclosingScope, Interpolation, LanguageSupport, LexicalRules,
+    ScopeKind,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use todo_marker::MarkerGrammar;
+
+pub(super) struct TypeScriptSupport {
+    jsx: bool,
+}
+pub(super) const TS: TypeScriptSupport = TypeScriptSupport { jsx: false };
+pub(super) const TSX: TypeScriptSupport = TypeScriptSupport { jsx: true };
+
+// ---------------------------------------------------------------------------
+//  Regexes
+// ---------------------------------------------------------------------------
+
+// Call-site `foo(`, optionally with type arguments: `foo<Bar, Baz[]>(`
+static CALL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|[^\w$])([A-Za-z_$][\w$]*)\s*(?:<([\w$\s,.\[\]|&]*)>)?\s*\(").unwrap()
+});
+
+// `@Component`, `@Input()`
+static DECORATOR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"@([A-Za-z_$][\w$]*)").unwrap());
+
+// `class Foo`, `interface Foo`, `type Foo`, `enum Foo`
+static DECL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:class|interface|enum|type|namespace)\s+([A-Z][\w$]*)").unwrap());
+
+// Declarations JavaScript lacks, each capturing the declared name.
+static DECLARATION_RES: Lazy<Vec<(Regex, DeclarationKind)>> = Lazy::new(|| {
+    [
+        (
+            r"\b(?:interface|enum|namespace)\s+([\w$]+)",
+            DeclarationKind::Type,
+        ),
+        (r"\btype\s+([\w$]+)\s*(?:<|=)", DeclarationKind::Type),
+        (
+            r"\bexport\s+default\s+(?:abstract\s+)?(?:class\s+|function\s*)?([\w$]+)",
+            DeclarationKind::Value,
+        ),
+    ]
+    .into_iter()
+    .map(|(pattern, kind)| (Regex::new(pattern).unwrap(), kind))
+    .collect()
+});
+
+static PASCAL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[A-Z][\w$]*").unwrap());
+
+static TS_FUNCTION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*[\w$]+\s*(?:<[^>]*>)?\s*\([^)]*\)\s*(?::\s*[^{]+)?\{",
+    )
+    .unwrap()
+});
+
+static TS_ARROW_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:const|let|var)\s+[\w$]+\s*(?::[^=]+)?=\s*(?:async\s+)?(?:<[^>]*>)?\s*\([^)]*\)\s*(?::\s*[^=]+)?=>\s*\{",
+    )
+    .unwrap()
+});
+
+static TS_METHOD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:(?:public|private|protected|static|async|readonly|override|abstract|get|set)\s+)*\*?\s*(#?[\w$]+)\s*(?:<[^>]*>)?\s*\([^)]*\)\s*(?::\s*[^{]+)?\{\s*$",
+    )
+    .unwrap()
+});
+
+static TS_TYPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?(?:const\s+)?(?:class|interface|enum)\s+[\w$]+.*\{",
+    )
+    .unwrap()
+});
+
+static TYPE_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(class|interface|enum)\s+([A-Za-z_$][\w$]*)").unwrap());
+
+// Block headers, matched against the whitespace-collapsed text before `{`.
+static HEADER_FUNCTION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bfunction\s*\*?\s*([A-Za-z_$][\w$]*)\s*(?:<.*>)?\s*\(").unwrap());
+
+static HEADER_ARROW_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?:export\s+)?(?:(?:const|let|var)\s+)?([A-Za-z_$][\w$]*)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:<.*>)?\s*(?:\(.*\)|[A-Za-z_$][\w$]*)\s*(?::.+)?=>$",
+    )
+    .unwrap()
+});
+
+static HEADER_METHOD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?:(?:public|private|protected|static|async|readonly|override|abstract|get|set|declare)\s+)*\*?\s*(#?[A-Za-z_$][\w$]*)\s*(?:<.*>)?\s*\(.*\)\s*(?::.+)?$",
+    )
+    .unwrap()
+});
+
+// Keywords that look like a method header: `if (…) {`, `catch (e) {` …
+static CONTROL: &[&str] = &[
+    "catch", "for", "function", "if", "return", "switch", "while", "with",
+];
+
+// Reserved words we don't want as identifiers
+static RESERVED: &[&str] = &[
+    "async",
+    "await",
+    "catch",
+    "class",
+    "const",
+    "constructor",
+    "for",
+    "function",
+    "if",
+    "keyof",
+    "let",
+    "new",
+    "return",
+    "super",
+    "switch",
+    "typeof",
+    "var",
+    "while",
+];
+
+static TS_LEXICAL_RULES: LexicalRules = LexicalRules {
+    quotes: b"\"'`",
+    interpolation: Some(Interpolation {
+        quote: b'`',
+        open: "${",
+        close: b'}',
+    }),
+    regex_literals: true,
+    ..LexicalRules::C_LIKE
+};
+
+static TSX_LEXICAL_RULES: LexicalRules = LexicalRules {
+    regex_literals: false,
+    ..TS_LEXICAL_RULES
+};
+
+fn push_unique(out: &mut Vec<String>, ident: &str) {
+    if !out.iter().any(|existing| existing == ident) {
+        out.push(ident.to_string());
+    }
+}
+
+/// Classifies the header in front of a `{` as a type or function declaration.
+fn classify_header(header: &str) -> Option<BlockHeader> {
+    let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if let Some(caps) = TYPE_NAME_RE.captures(&header) {
+        let kind = match &caps[1] {
+            "class" => ScopeKind::Class,
+            "interface" => ScopeKind::Interface,
+            _ => ScopeKind::Enum,
+        };
+        return Some(BlockHeader::Type(kind, caps[2].to_string()));
+    }
+
+    let name = HEADER_FUNCTION_RE
+        .captures(&header)
+        .or_else(|| HEADER_ARROW_RE.captures(&header))
+        .or_else(|| HEADER_METHOD_RE.captures(&header))
+        .map(|caps| caps[1].to_string())?;
+    if CONTROL.contains(&name.as_str()) {
+        return None;
+    }
+    Some(BlockHeader::Function(format!("{name}()")))
+}
+
+// ---------------------------------------------------------------------------
+//  Trait implementation
+// ---------------------------------------------------------------------------
+
+impl LanguageSupport for TypeScriptSupport {
+    fn extract_identifiers(&self, src: &str) -> Vec<String> {
+        let mut out = Vec::new();
+
+        for cap in CALL_RE.captures_iter(src) {
+            let ident = &cap[1];
+            if !RESERVED.contains(&ident) {
+                push_unique(&mut out, ident);
+            }
+            if let Some(type_args) = cap.get(2) {
+                for ty in PASCAL_RE.find_iter(type_args.as_str()) {
+                    push_unique(&mut out, ty.as_str());
+                }
+            }
+        }
+
+        for cap in DECORATOR_RE.captures_iter(src) {
+            push_unique(&mut out, &cap[1]);
+        }
+
+        for cap in DECL_RE.captures_iter(src) {
+            push_unique(&mut out, &cap[1]);
+        }
+
+        out
+    }
+
+    fn declarations(&self, content: &str) -> Vec<Declaration> {
+        let mut out = JS.declarations(content);
+        for (re, kind) in DECLARATION_RES.iter() {
+            for caps in re.captures_iter(content) {
+                out.push(Declaration::new(&caps[1], *kind));
+            }
+        }
+        out
+    }
+
+    fn resolve_dependency_path(&self, line: &str, current_dir: &Path) -> Option<PathBuf> {
+        JS.resolve_dependency_path(line, current_dir)
+    }
+
+    fn is_function_candidate(&self, line: &str) -> bool {
+        if JS.is_function_candidate(line) || TS_FUNCTION_RE.is_match(line) {
+            return true;
+        }
+        if TS_ARROW_RE.is_match(line) {
+            return true;
+        }
+        TS_METHOD_RE
+            .captures(line)
+            .is_some_and(|caps| !CONTROL.contains(&&caps[1]))
+    }
+
+    fn is_type_candidate(&self, line: &str) -> bool {
+        TS_TYPE_RE.is_match(line)
+    }
+
+    fn extract_type_name(&self, line: &str) -> Option<String> {
+        TYPE_NAME_RE.captures(line).map(|caps| caps[2].to_string())
+    }
+
+    fn supports_enclosing_type(&self) -> bool {
+        true
+    }
+
+    fn extract_enclosing_type_name(
+        &self,
+        content: &str,
+        grammar: &MarkerGrammar,
+    ) -> Option<String> {
+        last_type_before_marker(content, grammar, |line| self.extract_type_name(line))
+    }
+
+    fn enclosing_scope(&self, content: &str, offset: usize) -> Option<EnclosingScope> {
+        brace_scope(content, offset, self.lexical_rules(), |header| {
+            classify_header(header).map(|kind| (0, kind))
+        })
+    }
+
+    fn lexical_rules(&self) -> &'static LexicalRules {
+        if self.jsx {
+            &TSX_LEXICAL_RULES
+        } else {
+            &TS_LEXICAL_RULES
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_candidates_exclude_control_flow() {
+        assert!(TS.is_function_candidate("  async reload(id: number): Promise<void> {"));
+        assert!(TS.is_function_candidate("  private static build<T>(x: T) {"));
+        assert!(TS.is_function_candidate("export function load<T>(url: string): T {"));
+        assert!(
+            TS.is_function_candidate("export const run = async (n: number): Promise<void> => {")
+        );
+        assert!(!TS.is_function_candidate("  if (ready) {"));
+        assert!(!TS.is_function_candidate("  } catch (err) {"));
+    }
+
+    #[test]
+    fn type_candidates_cover_interfaces_enums_and_abstract_classes() {
+        assert!(TS.is_type_candidate("export interface User {"));
+        assert!(TS.is_type_candidate("export default abstract class Base<T> {"));
+        assert!(TS.is_type_candidate("const enum Mode {"));
+        assert!(!TS.is_type_candidate("type Id = string;"));
+        assert_eq!(
+            TS.extract_type_name("export abstract class Repo<T> {"),
+            Some("Repo".to_string())
+        );
+    }
+
+    #[test]
+    fn header_classification() {
+        assert!(matches!(
+            classify_header("export default abstract class Base<T>"),
+            Some(BlockHeader::Type(ScopeKind::Class, name)) if name == "Base"
+        ));
+        assert!(matches!(
+            classify_header("const handler = async (req: Request): Promise<void> =>"),
+            Some(BlockHeader::Function(name)) if name == "handler()"
+        ));
+        assert!(matches!(
+            classify_header("protected async\n  reload(id: number): Promise<void>"),
+            Some(BlockHeader::Function(name)) if name == "reload()"
+        ));
+        assert!(classify_header("} else if (x)").is_none());
+        assert!(classify_header("while (true)").is_none());
+        assert!(classify_header("items.forEach((item) =>").is_none());
+    }
+
+    #[test]
+    fn tsx_does_not_lex_regex_literals() {
+        assert!(TS.lexical_rules().regex_literals);
+        assert!(!TSX.lexical_rules().regex_literals);
+    }
+}
@@ -0,0 +1,523 @@
+// crates/lang_support/src/walk.rs
+
+//! Discovery of the source files a search looks at.
+//!
+//! The directory walk runs on several threads, and so does deciding whether
+//! a file qualifies (its size, and a sniff of its first bytes for binary,
+//! minified or generated content).  Paths come back sorted, whatever order
+//! the threads find them in.  The full content of a file is only read when
+//! a consumer first asks for it.
+//!
+//! What is skipped is one policy for every search: directories by name,
+//! `.gitignore` and `.promptignore` files, and the content checks above.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder, WalkState};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::{for_extension, LanguageSupport};
+
+/// How many leading bytes of a file are sniffed for binary or minified
+/// content.
+const SNIFF_LEN: usize = 8 * 1024;
+
+/// A line this long within the sniffed bytes marks a file as minified.
+const MINIFIED_LINE_LENGTH: usize = 1_000;
+
+/// How many leading lines may carry a "generated file" header.
+const GENERATED_HEADER_LINES: usize = 5;
+
+/// Headers such as `// @generated`, `// Code generated by protoc. DO NOT
+/// EDIT.`, `// This is a generated file` or `/* Autogenerated */`.  Apart
+/// from the two conventional tags, the comment has to open with the word, so
+/// a TODO that merely mentions generated code does not count.
+static GENERATED_HEADER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"@generated\b|DO NOT EDIT|(?i:^\s*(?://+|#+|/?\*+|--)\s*(?:this\s+(?:file|code)\s+(?:is|was)\s+|this\s+is\s+an?\s+|code\s+)?(?:auto(?:matically)?[-\s]?)?generated\b)",
+    )
+    .unwrap()
+});
+
+/// Which files a source walk skips, shared by instruction discovery,
+/// definition search, reference search and search-root detection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Files larger than this many bytes are skipped; `None` means no limit.
+    pub max_file_size: Option<u64>,
+    /// Skip files whose first bytes hold a NUL byte or are not UTF-8.
+    pub skip_binary: bool,
+    /// Skip `*.min.*` files and files with a very long line near the top.
+    pub skip_minified: bool,
+    /// Skip files whose first lines say they are generated.
+    pub skip_generated: bool,
+    /// Honour `.gitignore` files (with `.git/info/exclude` and the global
+    /// excludes file) and [`PROMPTIGNORE`](Self::PROMPTIGNORE) files, both in
+    /// gitignore syntax and both read from the walked directories and their
+    /// parents.
+    pub use_ignore_files: bool,
+    /// Names of directories never walked into, wherever they appear.
+    pub ignored_dirs: Vec<String>,
+}
+
+impl WalkOptions {
+    /// One MiB: far beyond hand-written source, well below bundles and
+    /// generated fixtures.
+    pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+    /// Build output, dependency checkouts and Git's own directory.
+    pub const DEFAULT_IGNORED_DIRS: &'static [&'static str] = &[
+        ".git",
+        ".build",
+        "Pods",
+        "Carthage",
+        "DerivedData",
+        "node_modules",
+    ];
+
+    /// Name of the ignore file for paths that belong in Git but not in
+    /// prompts.
+    pub const PROMPTIGNORE: &'static str = ".promptignore";
+
+    fn has_ignored_component(&self, path: &Path) -> bool {
+        path.components().any(|component| match component {
+            Component::Normal(name) => self.ignored_dirs.iter().any(|dir| name == dir.as_str()),
+            _ => false,
+        })
+    }
+
+    /// Whether this policy excludes `path`, a file under `root`, judging it
+    /// as a walk of `root` would.  This is for paths that come from
+    /// elsewhere than a walk, e.g. `git status`; to judge many paths, use
+    /// one [`ignore_matcher`](Self::ignore_matcher) for all of them.
+    pub fn ignores(&self, root: &Path, path: &Path) -> bool {
+        self.ignore_matcher(root).ignores(path)
+    }
+
+    /// Judges paths under `root` as [`ignores`](Self::ignores) does,
+    /// reading each directory's ignore files once for all of them.
+    pub fn ignore_matcher(&self, root: &Path) -> IgnoreMatcher<'_> {
+        IgnoreMatcher {
+            options: self,
+            root: root.to_path_buf(),
+            dirs: Mutex::default(),
+        }
+    }
+}
+
+/// A [`WalkOptions`] policy for paths under one root, with the rules of the
+/// ignore files it has read kept per directory.
+pub struct IgnoreMatcher<'a> {
+    options: &'a WalkOptions,
+    root: PathBuf,
+    dirs: Mutex<HashMap<PathBuf, Arc<Vec<Gitignore>>>>,
+}
+
+impl IgnoreMatcher<'_> {
+    /// Whether the policy excludes `path`.
+    pub fn ignores(&self, path: &Path) -> bool {
+        if self.options.has_ignored_component(path) {
+            return true;
+        }
+        if !self.options.use_ignore_files || !path.starts_with(&self.root) {
+            return false;
+        }
+        // The deepest ignore file with a say decides, and a .promptignore
+        // overrides the .gitignore beside it.
+        for dir in path.ancestors().skip(1) {
+            for rules in self.rules_in(dir).iter() {
+                match rules.matched_path_or_any_parents(path, false) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if dir == self.root {
+                break;
+            }
+        }
+        false
+    }
+
+    /// The rules of `dir`'s .promptignore and .gitignore, in that order,
+    /// read on first use.
+    fn rules_in(&self, dir: &Path) -> Arc<Vec<Gitignore>> {
+        if let Some(rules) = self.dirs.lock().unwrap().get(dir) {
+            return Arc::clone(rules);
+        }
+        let rules = [WalkOptions::PROMPTIGNORE, ".gitignore"]
+            .into_iter()
+            .filter_map(|name| {
+                let file = dir.join(name);
+                if !file.is_file() {
+                    return None;
+                }
+                let mut builder = GitignoreBuilder::new(dir);
+                builder.add(&file);
+                builder.build().ok()
+            })
+            .collect();
+        Arc::clone(
+            self.dirs
+                .lock()
+                .unwrap()
+                .entry(dir.to_path_buf())
+                .or_insert(Arc::new(rules)),
+        )
+    }
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: Some(Self::DEFAULT_MAX_FILE_SIZE),
+            skip_binary: true,
+            skip_minified: true,
+            skip_generated: true,
+            use_ignore_files: true,
+            ignored_dirs: Self::DEFAULT_IGNORED_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect(),
+        }
+    }
+}
+
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub language: &'static dyn LanguageSupport,
+    content: OnceLock<String>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, language: &'static dyn LanguageSupport) -> Self {
+        Self {
+            path,
+            language,
+            content: OnceLock::new(),
+        }
+    }
+
+    /// `path` as a source file, if its language is supported, it lies
+    /// outside ignored directories and it passes the content checks of
+    /// `options`.  Ignore files are not consulted; see
+    /// [`WalkOptions::ignores`].
+    pub fn open(path: PathBuf, options: &WalkOptions) -> Option<Self> {
+        if options.has_ignored_component(&path) {
+            return None;
+        }
+        let language = for_extension(path.extension()?.to_str()?)?;
+        qualifies(&path, options).then(|| Self::new(path, language))
+    }
+
+    /// The file's content, read on first use.  A file that can no longer be
+    /// read reads as empty.
+    pub fn content(&self) -> &str {
+        self.content
+            .get_or_init(|| fs::read_to_string(&self.path).unwrap_or_default())
+    }
+}
+
+/// Walks a root directory and returns readable files supported by
+/// `lang_support`, skipping files as the default [`WalkOptions`] say.
+///
+/// Files inside generated/vendor directories are skipped so definition and
+/// reference searches share the same source-file policy.
+pub fn walk_source_files(root: impl AsRef<Path>) -> Vec<SourceFile> {
+    walk_source_files_with_options(root, &WalkOptions::default())
+}
+
+/// Like [`walk_source_files`], skipping files as `options` say.
+pub fn walk_source_files_with_options(
+    root: impl AsRef<Path>,
+    options: &WalkOptions,
+) -> Vec<SourceFile> {
+    walk_source_paths(root, options)
+        .into_par_iter()
+        .filter_map(|path| SourceFile::open(path, options))
+        .collect()
+}
+
+/// The paths a walk of `root` considers: files in supported languages that
+/// the ignore policy of `options` lets through.  None of them is opened, so
+/// the list is cheap to order before [`SourceFile::open`] checks each file.
+pub fn walk_source_paths(root: impl AsRef<Path>, options: &WalkOptions) -> Vec<PathBuf> {
+    let mut paths = walk_files(root, options);
+    paths.retain(|path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(for_extension)
+            .is_some()
+    });
+    paths
+}
+
+/// Every file under `root` the ignore policy of `options` lets through,
+/// whatever its language, in path order.
+pub fn walk_files(root: impl AsRef<Path>, options: &WalkOptions) -> Vec<PathBuf> {
+    let policy = options.clone();
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .standard_filters(false)
+        .git_ignore(options.use_ignore_files)
+        .git_exclude(options.use_ignore_files)
+        .git_global(options.use_ignore_files)
+        .parents(options.use_ignore_files)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            !entry.file_type().is_some_and(|kind| kind.is_dir())
+                || !policy.has_ignored_component(Path::new(entry.file_name()))
+        });
+    if options.use_ignore_files {
+        builder.add_custom_ignore_filename(WalkOptions::PROMPTIGNORE);
+    }
+
+    let paths = Mutex::new(Vec::new());
+    builder.build_parallel().run(|| {
+        let paths = &paths;
+        Box::new(move |entry| {
+            if let Ok(entry) = entry {
+                if entry.file_type().is_some_and(|kind| kind.is_file())
+                    && !options.has_ignored_component(entry.path())
+                {
+                    paths.lock().unwrap().push(entry.into_path());
+                }
+            }
+            WalkState::Continue
+        })
+    });
+    // Threads finish directories in any order; sorting keeps walks repeatable.
+    let mut paths = paths.into_inner().unwrap();
+    paths.sort();
+    paths
+}
+
+/// Whether `path` is readable and passes the size, binary, minified and
+/// generated checks of `options`.
+fn qualifies(path: &Path, options: &WalkOptions) -> bool {
+    if options.skip_minified && is_minified_name(path) {
+        return false;
+    }
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    if let Some(max) = options.max_file_size {
+        if file.metadata().map_or(true, |meta| meta.len() > max) {
+            return false;
+        }
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    if file.take(SNIFF_LEN as u64).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    !(options.skip_binary && looks_binary(&head)
+        || options.skip_minified && looks_minified(&head)
+        || options.skip_generated && looks_generated(&head))
+}
+
+fn is_minified_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(".min."))
+}
+
+/// A NUL byte or invalid UTF-8; a character cut off at the end of `head`
+/// does not count.
+fn looks_binary(head: &[u8]) -> bool {
+    head.contains(&0) || std::str::from_utf8(head).is_err_and(|err| err.error_len().is_some())
+}
+
+fn looks_minified(head: &[u8]) -> bool {
+    head.split(|&b| b == b'\n')
+        .any(|line| line.len() >= MINIFIED_LINE_LENGTH)
+}
+
+fn looks_generated(head: &[u8]) -> bool {
+    String::from_utf8_lossy(head)
+        .lines()
+        .take(GENERATED_HEADER_LINES)
+        .any(|line| GENERATED_HEADER_RE.is_match(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use tempfile::tempdir;
+
+    fn names(files: &[SourceFile]) -> BTreeSet<String> {
+        files
+            .iter()
+            .map(|file| {
+                file.path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn oversized_binary_and_minified_files_are_skipped() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("Model.swift"), "struct Model {}\n").unwrap();
+        fs::write(root.join("Big.swift"), "let x = 1\n".repeat(200)).unwrap();
+        fs::write(root.join("Blob.swift"), b"struct A {}\n\0\x01\x02").unwrap();
+        fs::write(root.join("Latin1.js"), b"const s = '\xe9t\xe9';\n").unwrap();
+        fs::write(root.join("vendor.min.js"), "function a(){}\n").unwrap();
+        fs::write(
+            root.join("bundle.js"),
+            format!("{}\n", "var a=1;".repeat(126)),
+        )
+        .unwrap();
+
+        let options = WalkOptions {
+            max_file_size: Some(1_500),
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&walk_source_files_with_options(root, &options)),
+            BTreeSet::from(["Model.swift".to_string()])
+        );
+
+        let everything = WalkOptions {
+            max_file_size: None,
+            skip_binary: false,
+            skip_minified: false,
+            ..Default::default()
+        };
+        assert_eq!(walk_source_files_with_options(root, &everything).len(), 6);
+    }
+
+    #[test]
+    fn ignore_files_and_ignored_dirs_are_honoured() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for sub in ["Sources", "dist", "node_modules/pkg", "Generated", "Vendor"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        fs::write(root.join(".promptignore"), "Generated/\n*.fixture.js\n").unwrap();
+        fs::write(root.join("Vendor/.promptignore"), "*\n!Keep.swift\n").unwrap();
+        fs::write(root.join("Sources/Model.swift"), "struct Model {}\n").unwrap();
+        fs::write(root.join("Sources/data.fixture.js"), "const a = 1\n").unwrap();
+        fs::write(root.join("dist/app.js"), "const a = 1\n").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "const a = 1\n").unwrap();
+        fs::write(root.join("Generated/R.swift"), "struct R {}\n").unwrap();
+        fs::write(root.join("Vendor/Lib.swift"), "struct Lib {}\n").unwrap();
+        fs::write(root.join("Vendor/Keep.swift"), "struct Keep {}\n").unwrap();
+
+        let options = WalkOptions::default();
+        assert_eq!(
+            names(&walk_source_files_with_options(root, &options)),
+            BTreeSet::from(["Keep.swift".to_string(), "Model.swift".to_string()])
+        );
+        assert!(options.ignores(root, &root.join("dist/app.js")));
+        assert!(options.ignores(root, &root.join("Sources/data.fixture.js")));
+        assert!(options.ignores(root, &root.join("Vendor/Lib.swift")));
+        assert!(!options.ignores(root, &root.join("Vendor/Keep.swift")));
+
+        // A matcher reads each ignore file once, so later edits go unseen.
+        let matcher = options.ignore_matcher(root);
+        assert!(matcher.ignores(&root.join("Vendor/Lib.swift")));
+        fs::write(root.join("Vendor/.promptignore"), "").unwrap();
+        assert!(matcher.ignores(&root.join("Vendor/Lib.swift")));
+        assert!(matcher.ignores(&root.join("dist/app.js")));
+        assert!(!matcher.ignores(&root.join("Sources/Model.swift")));
+        assert!(!options.ignores(root, &root.join("Vendor/Lib.swift")));
+        fs::write(root.join("Vendor/.promptignore"), "*\n!Keep.swift\n").unwrap();
+
+        let no_ignore_files = WalkOptions {
+            use_ignore_files: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            walk_source_files_with_options(root, &no_ignore_files).len(),
+            6
+        );
+        assert!(!no_ignore_files.ignores(root, &root.join("dist/app.js")));
+    }
+
+    #[test]
+    fn walk_files_returns_every_file_in_path_order() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for sub in ["b/inner", "a", "c"] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        let expected: Vec<PathBuf> = ["a/z.txt", "b/inner/x.swift", "b/y.js", "c/w.py", "top.md"]
+            .iter()
+            .map(|name| root.join(name))
+            .collect();
+        for path in expected.iter().rev() {
+            fs::write(path, "x\n").unwrap();
+        }
+
+        assert_eq!(walk_files(root, &WalkOptions::default()), expected);
+    }
+
+    #[test]
+    fn generated_headers_are_skipped() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let files = [
+            (
+                "R.generated.swift",
+                "//\n// This is a generated file, do not edit!\n// Generated by R.swift\n",
+            ),
+            (
+                "Api.swift",
+                "// Code generated by apollo. DO NOT EDIT.\nstruct Api {}\n",
+            ),
+            ("schema.ts", "/* @generated */\nexport type A = {}\n"),
+            (
+                "models.py",
+                "# Auto-generated from schema.json\nclass A:\n    pass\n",
+            ),
+            (
+                "Feed.swift",
+                "// TODO: - cache what the server generated\nstruct Feed {}\n",
+            ),
+            ("Late.swift", "struct A {}\n\n\n\n\n// Generated by hand\n"),
+        ];
+        for (name, content) in files {
+            fs::write(root.join(name), content).unwrap();
+        }
+
+        assert_eq!(
+            names(&walk_source_files(root)),
+            BTreeSet::from(["Feed.swift".to_string(), "Late.swift".to_string()])
+        );
+        let keep_generated = WalkOptions {
+            skip_generated: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            walk_source_files_with_options(root, &keep_generated).len(),
+            6
+        );
+    }
+
+    #[test]
+    fn content_is_read_on_first_use() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("Model.swift");
+        fs::write(&path, "struct Model {}\n").unwrap();
+
+        let files = walk_source_files(dir.path());
+        fs::write(&path, "struct Renamed {}\n").unwrap();
+
+        assert_eq!(files[0].content(), "struct Renamed {}\n");
+        fs::write(&path, "struct Again {}\n").unwrap();
+        assert_eq!(files[0].content(), "struct Renamed {}\n");
+    }
+}
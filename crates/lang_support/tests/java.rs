@@ -0,0 +1,69 @@
+// crates/lang_support/tests/java.rs
+
+use lang_support::{for_extension, ScopeKind};
+
+#[test]
+fn file_defines_any_matches_java_declarations() {
+    let java = for_extension("java").unwrap();
+    let cases = [
+        ("public final class Feed extends Base {}", "Feed"),
+        ("interface Loader {}", "Loader"),
+        ("enum Mode { LIVE }", "Mode"),
+        ("public record Point(int x, int y) {}", "Point"),
+        (
+            "  public List<Item> load(int page) throws IOException {",
+            "load",
+        ),
+    ];
+
+    for (src, ident) in cases {
+        assert!(
+            java.file_defines_any(src, &[ident.to_string()]),
+            "{src} should define {ident}"
+        );
+    }
+    assert!(!java.file_defines_any("items = load(2);", &["load".to_string()]));
+}
+
+#[test]
+fn extract_identifiers_finds_calls_and_declarations() {
+    let java = for_extension("java").unwrap();
+    let src = "public class FeedActivity {\n    void onCreate() {\n        FeedAdapter adapter = new FeedAdapter(this);\n        if (ready) { render(); }\n    }\n}\n";
+
+    let idents = java.extract_identifiers(src);
+    for name in ["FeedActivity", "onCreate", "FeedAdapter", "render"] {
+        assert!(
+            idents.contains(&name.to_string()),
+            "missing {name}: {idents:?}"
+        );
+    }
+    assert!(!idents.contains(&"if".to_string()));
+}
+
+#[test]
+fn enclosing_scope_tracks_nested_classes_and_methods() {
+    let java = for_extension("java").unwrap();
+    let src = r#"public class FeedActivity extends Activity {
+    static class Loader {
+        @Override
+        public void run() throws IOException {
+            String s = "}";
+            if (s.isEmpty()) {
+                // TODO: - Handle empty
+            }
+        }
+    }
+}
+"#;
+    let offset = src.find("// TODO: -").unwrap();
+    let scope = java.enclosing_scope(src, offset).expect("scope");
+
+    assert_eq!(scope.type_path, vec!["FeedActivity", "Loader"]);
+    assert_eq!(scope.kind, Some(ScopeKind::Class));
+    assert_eq!(scope.qualified_name(), "FeedActivity.Loader.run()");
+    assert_eq!(
+        scope.function.as_ref().map(|f| f.signature.as_str()),
+        Some("public void run() throws IOException")
+    );
+    assert_eq!(scope.lines, 4..=9);
+}
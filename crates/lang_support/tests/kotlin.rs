@@ -0,0 +1,89 @@
+// crates/lang_support/tests/kotlin.rs
+
+use lang_support::{for_extension, ScopeKind};
+
+#[test]
+fn kotlin_extensions_resolve() {
+    for ext in ["kt", "kts"] {
+        assert!(for_extension(ext).is_some(), "{ext} should resolve");
+    }
+}
+
+#[test]
+fn file_defines_any_matches_kotlin_declarations() {
+    let kotlin = for_extension("kt").unwrap();
+    let cases = [
+        ("class Feed", "Feed"),
+        ("data class User(val id: String)", "User"),
+        ("sealed class Result<out T>", "Result"),
+        ("enum class Mode { LIVE }", "Mode"),
+        ("interface Loader", "Loader"),
+        ("object Registry", "Registry"),
+        ("typealias UserId = String", "UserId"),
+        ("fun load(id: String) = Unit", "load"),
+        ("suspend fun <T> fetch(url: String): T", "fetch"),
+        ("fun View.show() {}", "show"),
+    ];
+
+    for (src, ident) in cases {
+        assert!(
+            kotlin.file_defines_any(src, &[ident.to_string()]),
+            "{src} should define {ident}"
+        );
+    }
+    assert!(!kotlin.file_defines_any("val feed = Feed()", &["Feed".to_string()]));
+}
+
+#[test]
+fn extract_identifiers_finds_calls_and_declarations() {
+    let kotlin = for_extension("kt").unwrap();
+    let src = "class FeedViewModel {\n    fun load() {\n        val adapter = FeedAdapter(context)\n        repository.fetchPage(1)\n        if (ready) render()\n    }\n}\n";
+
+    let idents = kotlin.extract_identifiers(src);
+    for name in [
+        "FeedViewModel",
+        "load",
+        "FeedAdapter",
+        "fetchPage",
+        "render",
+    ] {
+        assert!(
+            idents.contains(&name.to_string()),
+            "missing {name}: {idents:?}"
+        );
+    }
+    assert!(!idents.contains(&"if".to_string()));
+}
+
+#[test]
+fn enclosing_scope_tracks_objects_and_funs() {
+    let kotlin = for_extension("kt").unwrap();
+    let src = r#"class FeedViewModel(private val repo: Repo) : ViewModel() {
+    companion object {
+        private const val TEMPLATE = "${'$'}{ not a block }"
+
+        fun create(repo: Repo): FeedViewModel {
+            repo.items.forEach { item ->
+                // TODO: - Validate
+            }
+            return FeedViewModel(repo)
+        }
+    }
+}
+"#;
+    let offset = src.find("// TODO: -").unwrap();
+    let scope = kotlin.enclosing_scope(src, offset).expect("scope");
+
+    assert_eq!(scope.type_path, vec!["FeedViewModel", "Companion"]);
+    assert_eq!(scope.kind, Some(ScopeKind::Object));
+    assert_eq!(scope.qualified_name(), "FeedViewModel.Companion.create()");
+    assert_eq!(
+        scope.function.as_ref().map(|f| f.signature.as_str()),
+        Some("fun create(repo: Repo): FeedViewModel")
+    );
+    assert_eq!(scope.lines, 5..=10);
+    assert_eq!(
+        kotlin.extract_enclosing_type_name(src, &kotlin.marker_grammar()),
+        Some("Companion".to_string())
+    );
+}
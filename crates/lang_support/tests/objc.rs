@@ -69,3 +69,23 @@ fn objc_resolve_dependency_path_is_none() {
         .resolve_dependency_path("#import \"Message.h\"", std::path::Path::new("."))
         .is_none());
 }
+
+#[test]
+fn objc_category_extends_but_does_not_define() {
+    let objc = for_extension("h").unwrap();
+    let want = vec!["Message".to_string()];
+    let category = "@interface Message (Networking)\n@end";
+
+    assert!(objc.file_extends_any(category, &want));
+    assert!(!objc.file_defines_any(category, &want));
+}
+
+#[test]
+fn objc_class_extension_stays_with_primary_definition() {
+    let objc = for_extension("m").unwrap();
+    let want = vec!["Message".to_string()];
+    let content = "@interface Message ()\n@end\n@implementation Message\n@end";
+
+    assert!(objc.file_defines_any(content, &want));
+    assert!(!objc.file_extends_any(content, &want));
+}
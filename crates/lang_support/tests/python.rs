@@ -0,0 +1,133 @@
+// crates/lang_support/tests/python.rs
+
+use lang_support::{for_extension, ScopeKind};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn python_extensions_resolve() {
+    for ext in ["py", "pyi"] {
+        assert!(for_extension(ext).is_some(), "{ext} should resolve");
+    }
+}
+
+#[test]
+fn file_defines_any_matches_python_declarations() {
+    let python = for_extension("py").unwrap();
+    let cases = [
+        ("class User:\n    pass", "User"),
+        ("class Repo(Base, metaclass=Meta):\n    pass", "Repo"),
+        ("    def load(self, id):\n        pass", "load"),
+        ("async def fetch(url):\n    pass", "fetch"),
+        ("UserId = NewType('UserId', int)", "UserId"),
+        ("Handler: TypeAlias = Callable[[int], None]", "Handler"),
+    ];
+
+    for (src, ident) in cases {
+        assert!(
+            python.file_defines_any(src, &[ident.to_string()]),
+            "{src} should define {ident}"
+        );
+    }
+    assert!(!python.file_defines_any("user = User()", &["User".to_string()]));
+    assert!(!python.file_defines_any("if User == other:", &["User".to_string()]));
+}
+
+#[test]
+fn enclosing_scope_follows_indentation_and_decorators() {
+    let python = for_extension("py").unwrap();
+    let src = r#"import json
+
+
+class FeedViewModel:
+    """Loads the feed: {not a block}"""
+
+    @staticmethod
+    def create(repo: "Repo",
+               cache=None) -> "FeedViewModel":
+        items = {
+            "a": 1,
+        }
+        # TODO: - reuse the cached items
+        return FeedViewModel(repo)
+
+    def other(self):
+        pass
+"#;
+    let offset = src.find("# TODO").unwrap();
+    let scope = python.enclosing_scope(src, offset).expect("scope");
+
+    assert_eq!(scope.type_path, vec!["FeedViewModel".to_string()]);
+    assert_eq!(scope.kind, Some(ScopeKind::Class));
+    assert_eq!(scope.qualified_name(), "FeedViewModel.create()");
+    assert_eq!(
+        scope.function.unwrap().signature,
+        r#"def create(repo: "Repo", cache=None) -> "FeedViewModel""#
+    );
+    assert_eq!(scope.lines, 8..=14);
+
+    let after = src.find("pass").unwrap();
+    let scope = python.enclosing_scope(src, after).expect("scope");
+    assert_eq!(scope.qualified_name(), "FeedViewModel.other()");
+}
+
+#[test]
+fn enclosing_type_name_stops_at_the_hash_marker() {
+    let python = for_extension("py").unwrap();
+    let src = "class Feed:\n    def load(self):\n        # TODO: - cache it\n        pass\n\nclass Later:\n    pass\n";
+
+    assert_eq!(
+        python.extract_enclosing_type_name(src, &python.marker_grammar()),
+        Some("Feed".to_string())
+    );
+}
+
+#[test]
+fn module_level_code_has_no_enclosing_scope() {
+    let python = for_extension("py").unwrap();
+    let src = "class A:\n    pass\n\nx = 1  # TODO: - here\n";
+    assert_eq!(
+        python.enclosing_scope(src, src.find("# TODO").unwrap()),
+        None
+    );
+}
+
+#[test]
+fn resolve_dependency_path_finds_modules_and_packages() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let app = root.join("app");
+    let views = app.join("views");
+    fs::create_dir_all(app.join("models")).unwrap();
+    fs::create_dir_all(&views).unwrap();
+    fs::write(app.join("__init__.py"), "").unwrap();
+    fs::write(app.join("models").join("__init__.py"), "").unwrap();
+    fs::write(app.join("services.py"), "").unwrap();
+    fs::write(views.join("forms.py"), "").unwrap();
+
+    let python = for_extension("py").unwrap();
+    let resolve = |line: &str| python.resolve_dependency_path(line, &views);
+
+    assert_eq!(
+        resolve("from app.models import User"),
+        Some(app.join("models").join("__init__.py"))
+    );
+    assert_eq!(
+        resolve("from .forms import LoginForm"),
+        Some(views.join("forms.py"))
+    );
+    assert_eq!(
+        resolve("from ..services import (Mailer,"),
+        Some(app.join("services.py"))
+    );
+    assert_eq!(
+        resolve("import app.services as services"),
+        Some(app.join("services.py"))
+    );
+    assert_eq!(
+        resolve("from .. import services"),
+        Some(app.join("__init__.py"))
+    );
+    assert_eq!(resolve("from missing import Thing"), None);
+    assert_eq!(resolve("x = 1"), None);
+}
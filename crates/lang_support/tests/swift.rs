@@ -1,6 +1,6 @@
 // crates/lang_support/tests/swift.rs
 
-use lang_support::for_extension;
+use lang_support::{for_extension, ScopeKind};
 use std::path::Path;
 
 #[test]
@@ -76,3 +76,127 @@ fn swift_file_defines_free_function_identifier() {
         &["renderWidget".into()]
     ));
 }
+
+#[test]
+fn swift_ignores_declarations_and_calls_in_comments_and_strings() {
+    let swift = for_extension("swift").unwrap();
+
+    let src = r#"
+// class CommentedOut {}
+/* struct BlockCommented {} */
+let label = "enum InString { } and callInString()"
+class Real {}
+"#;
+    let idents = swift.extract_identifiers(src);
+
+    assert_eq!(idents, vec!["Real".to_string()]);
+    assert!(!swift.file_defines_any(src, &["CommentedOut".into(), "InString".into()]));
+}
+
+#[test]
+fn swift_extracts_actor_extension_and_nested_generic_types() {
+    let swift = for_extension("swift").unwrap();
+
+    let idents = swift.extract_identifiers(
+        r#"
+actor Store {
+    struct Page<Item> {}
+}
+extension Widget {}
+"#,
+    );
+
+    for name in ["Store", "Page", "Widget"] {
+        assert!(idents.contains(&name.to_string()), "missing {name}");
+    }
+}
+
+#[test]
+fn swift_file_defines_actor_nested_and_generic_members() {
+    let swift = for_extension("swift").unwrap();
+    let src = r#"
+actor Store {
+    enum Mode { case live }
+    func load<T: Decodable>(_ type: T.Type) -> T? { nil }
+}
+"#;
+
+    assert!(swift.file_defines_any(src, &["Store".into()]));
+    assert!(swift.file_defines_any(src, &["Mode".into()]));
+    assert!(swift.file_defines_any(src, &["load".into()]));
+}
+
+#[test]
+fn swift_extension_alone_does_not_define_the_extended_type() {
+    let swift = for_extension("swift").unwrap();
+    let src = "extension Widget {\n    func render() {}\n}\n";
+
+    assert!(!swift.file_defines_any(src, &["Widget".into()]));
+    assert!(swift.file_defines_any(src, &["render".into()]));
+    assert!(swift.file_extends_any(src, &["Widget".into()]));
+    assert!(!swift.file_extends_any(src, &["Gadget".into()]));
+}
+
+#[test]
+fn swift_enclosing_scope_tracks_nested_types_and_method() {
+    let swift = for_extension("swift").unwrap();
+    let src = r#"struct Outer {
+    class Inner {
+        func reload(id: Int, _ force: Bool, with options: [String]) {
+            // TODO: - refresh
+        }
+    }
+}
+"#;
+    let offset = src.find("// TODO: -").unwrap();
+    let scope = swift.enclosing_scope(src, offset).expect("scope");
+
+    assert_eq!(scope.type_path, vec!["Outer", "Inner"]);
+    assert_eq!(scope.kind, Some(ScopeKind::Class));
+    let function = scope.function.as_ref().expect("function");
+    assert_eq!(function.name, "reload(id:_:with:)");
+    assert_eq!(
+        function.signature,
+        "func reload(id: Int, _ force: Bool, with options: [String])"
+    );
+    assert_eq!(scope.lines, 3..=5);
+    assert_eq!(scope.qualified_name(), "Outer.Inner.reload(id:_:with:)");
+}
+
+#[test]
+fn swift_enclosing_scope_covers_extensions_protocols_actors_and_init() {
+    let swift = for_extension("swift").unwrap();
+    let cases = [
+        (
+            "extension Feed {\n    func refresh() {\n        // TODO: - x\n    }\n}\n",
+            "Feed.refresh()",
+            ScopeKind::Extension,
+        ),
+        (
+            "protocol Loader {\n    // TODO: - x\n    func load()\n}\n",
+            "Loader",
+            ScopeKind::Protocol,
+        ),
+        (
+            "actor Store {\n    init(name: String) {\n        // TODO: - x\n    }\n}\n",
+            "Store.init(name:)",
+            ScopeKind::Actor,
+        ),
+    ];
+
+    for (src, expected, kind) in cases {
+        let offset = src.find("// TODO: -").unwrap();
+        let scope = swift.enclosing_scope(src, offset).expect("scope");
+        assert_eq!(scope.qualified_name(), expected);
+        assert_eq!(scope.kind, Some(kind));
+    }
+}
+
+#[test]
+fn swift_enclosing_scope_is_none_outside_declarations() {
+    let swift = for_extension("swift").unwrap();
+    let src = "class Foo {}\n// TODO: - top level\nlet x = 1\n";
+    let offset = src.find("// TODO: -").unwrap();
+
+    assert!(swift.enclosing_scope(src, offset).is_none());
+}
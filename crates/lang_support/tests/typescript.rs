@@ -0,0 +1,124 @@
+// crates/lang_support/tests/typescript.rs
+
+use lang_support::{for_extension, ScopeKind};
+
+#[test]
+fn typescript_extensions_resolve() {
+    for ext in ["ts", "tsx", "mts", "cts", "TS"] {
+        assert!(for_extension(ext).is_some(), "{ext} should resolve");
+    }
+}
+
+#[test]
+fn extract_identifiers_finds_generic_calls_decorators_and_declarations() {
+    let ts = for_extension("ts").unwrap();
+    let src = r#"
+@Component({ selector: 'app-feed' })
+export default class FeedView extends BaseView {
+    @Input() items: Item[] = [];
+
+    load() {
+        const [user, setUser] = useState<UserProfile | null>(null);
+        this.api.fetch<FeedPage>('/feed');
+        buildRequest();
+    }
+}
+interface FeedOptions {}
+type FeedId = string;
+enum FeedMode { Live }
+"#;
+
+    let idents = ts.extract_identifiers(src);
+    for name in [
+        "Component",
+        "Input",
+        "FeedView",
+        "useState",
+        "UserProfile",
+        "fetch",
+        "FeedPage",
+        "buildRequest",
+        "FeedOptions",
+        "FeedId",
+        "FeedMode",
+    ] {
+        assert!(
+            idents.contains(&name.to_string()),
+            "missing {name}: {idents:?}"
+        );
+    }
+    assert!(!idents.contains(&"if".to_string()));
+}
+
+#[test]
+fn file_defines_any_matches_typescript_declarations() {
+    let ts = for_extension("ts").unwrap();
+    let cases = [
+        ("export interface User { id: string }", "User"),
+        ("type UserId = string;", "UserId"),
+        ("type Result<T> = { ok: T };", "Result"),
+        ("export const enum Mode { A }", "Mode"),
+        ("export abstract class Repository<T> {}", "Repository"),
+        ("export default class Store {}", "Store"),
+        ("export default function render() {}", "render"),
+        ("export namespace Api {}", "Api"),
+        ("export function helper() {}", "helper"),
+        ("const helper = () => {}", "helper"),
+    ];
+
+    for (src, ident) in cases {
+        assert!(
+            ts.file_defines_any(src, &[ident.to_string()]),
+            "{src} should define {ident}"
+        );
+    }
+    assert!(!ts.file_defines_any("let user: User;", &["User".to_string()]));
+}
+
+#[test]
+fn enclosing_scope_tracks_classes_and_methods() {
+    let ts = for_extension("ts").unwrap();
+    let src = r#"import { Api } from './api';
+
+@Injectable()
+export class FeedService {
+    private readonly pattern = /}/g;
+
+    async reload(id: string): Promise<void> {
+        if (id) {
+            // TODO: - Retry on failure
+        }
+    }
+}
+"#;
+    let offset = src.find("// TODO: -").unwrap();
+    let scope = ts.enclosing_scope(src, offset).expect("scope");
+
+    assert_eq!(scope.type_path, vec!["FeedService"]);
+    assert_eq!(scope.kind, Some(ScopeKind::Class));
+    let function = scope.function.as_ref().expect("function");
+    assert_eq!(function.name, "reload()");
+    assert_eq!(
+        function.signature,
+        "async reload(id: string): Promise<void>"
+    );
+    assert_eq!(scope.lines, 7..=11);
+    assert_eq!(scope.qualified_name(), "FeedService.reload()");
+}
+
+#[test]
+fn enclosing_type_name_finds_last_type_before_marker() {
+    let tsx = for_extension("tsx").unwrap();
+    let src = "interface Props {}\nexport default class Feed extends React.Component<Props> {\n  // TODO: - x\n}\nclass Later {}\n";
+
+    assert_eq!(
+        tsx.extract_enclosing_type_name(src, &tsx.marker_grammar()),
+        Some("Feed".to_string())
+    );
+    let offset = src.find("// TODO: -").unwrap();
+    assert_eq!(
+        tsx.enclosing_scope(src, offset)
+            .map(|scope| scope.qualified_name()),
+        Some("Feed".to_string())
+    );
+}
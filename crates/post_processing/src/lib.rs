@@ -1,15 +1,18 @@
 // crates/post_processing/src/lib.rs
 
 use anyhow::{anyhow, Result};
-use todo_marker::TODO_MARKER;
+use todo_marker::MarkerGrammar;
 
 /// Scrubs extra TODO markers from the given prompt if diff mode is not enabled.
 ///
 /// This function takes a `primary_marker` parameter that must exactly match one of the lines in the prompt.
-/// It removes all extra marker lines except:
+/// It removes all extra marker comments except:
 ///   - The first occurrence of a line exactly matching the supplied primary marker (if present), and
 ///   - The very last line that contains the marker substring.
 ///
+/// A stray marker that is the whole line drops the line; one that trails
+/// code is rewritten to an inert `// TODO(other):` so the code survives.
+///
 /// If the primary marker isn't found, an error is returned.
 ///
 /// # Arguments
@@ -26,43 +29,81 @@ pub fn scrub_extra_todo_markers(
     prompt: &str,
     diff_enabled: bool,
     primary_marker: &str,
+) -> Result<String> {
+    scrub_extra_todo_markers_with_grammar(
+        prompt,
+        diff_enabled,
+        primary_marker,
+        &MarkerGrammar::default(),
+    )
+}
+
+/// Like [`scrub_extra_todo_markers`], but for markers carrying `grammar`'s
+/// token.  Stray markers are recognised in every language's comment syntax,
+/// since the prompt may hold files of several languages.
+pub fn scrub_extra_todo_markers_with_grammar(
+    prompt: &str,
+    diff_enabled: bool,
+    primary_marker: &str,
+    grammar: &MarkerGrammar,
+) -> Result<String> {
+    scrub_extra_todo_markers_keeping(prompt, diff_enabled, &[primary_marker], grammar)
+}
+
+/// Like [`scrub_extra_todo_markers_with_grammar`], but keeps the first
+/// occurrence of each of several primary markers, one per task of a
+/// multi-task prompt.  Every primary marker must be present.
+pub fn scrub_extra_todo_markers_keeping(
+    prompt: &str,
+    diff_enabled: bool,
+    primary_markers: &[&str],
+    grammar: &MarkerGrammar,
 ) -> Result<String> {
     if diff_enabled {
         return Ok(prompt.to_string());
     }
 
-    let marker = TODO_MARKER;
     let lines: Vec<&str> = prompt.lines().collect();
 
-    let primary_found = lines.iter().any(|line| line.trim() == primary_marker);
-    if !primary_found {
-        return Err(anyhow!(
-            "Primary marker '{}' not found in prompt",
-            primary_marker
-        ));
+    for primary_marker in primary_markers {
+        let primary_found = lines.iter().any(|line| line.trim() == *primary_marker);
+        if !primary_found {
+            return Err(anyhow!(
+                "Primary marker '{}' not found in prompt",
+                primary_marker
+            ));
+        }
     }
 
     let last_marker_index = lines
         .iter()
         .enumerate()
-        .filter(|(_, line)| line.contains(marker))
+        .filter(|(_, line)| grammar.mentions_marker_in_any_syntax(line))
         .map(|(i, _)| i)
         .next_back()
         .ok_or_else(|| anyhow!("No marker lines found in prompt"))?;
 
     let mut output_lines = Vec::with_capacity(lines.len());
-    let mut primary_marker_included = false;
+    let mut primary_markers_included = vec![false; primary_markers.len()];
 
     for (i, line) in lines.iter().enumerate() {
-        if line.contains(marker) {
+        if grammar.mentions_marker_in_any_syntax(line) {
+            let primary = primary_markers
+                .iter()
+                .position(|marker| line.trim() == *marker)
+                .filter(|&p| !primary_markers_included[p]);
             if i == last_marker_index {
-                output_lines.push(*line);
-            } else if line.trim() == primary_marker && !primary_marker_included {
-                output_lines.push(*line);
-                primary_marker_included = true;
+                output_lines.push(line.to_string());
+            } else if let Some(p) = primary {
+                output_lines.push(line.to_string());
+                primary_markers_included[p] = true;
+            } else if !grammar.is_marker_comment_line(line) {
+                // Code shares the line with the stray marker: keep the code
+                // and defuse only the comment.
+                output_lines.push(grammar.neutralize_markers(line));
             }
         } else {
-            output_lines.push(*line);
+            output_lines.push(line.to_string());
         }
     }
 
@@ -114,4 +155,86 @@ End code
         let output = scrub_extra_todo_markers(input, true, primary_marker).unwrap();
         assert_eq!(output, input);
     }
+
+    #[test]
+    fn test_scrubs_markers_in_every_comment_syntax() {
+        let prompt =
+            "# TODO: - Primary\n// TODO: - Stray\nx = 1  # TODO: - Stray\nclosing \"# TODO: - \"";
+        let grammar = MarkerGrammar::HASH;
+        let output =
+            scrub_extra_todo_markers_with_grammar(prompt, false, "# TODO: - Primary", &grammar)
+                .unwrap();
+        assert_eq!(
+            output,
+            "# TODO: - Primary\nx = 1  # TODO(other): Stray\nclosing \"# TODO: - \""
+        );
+    }
+
+    #[test]
+    fn test_keeps_code_sharing_a_line_with_a_stray_marker() {
+        let primary_marker = "// TODO: - Primary Marker";
+        let input = r#"// TODO: - Primary Marker
+let x = compute() // TODO: - cache this
+    // FIXME: - stray comment
+let y = 2 /* REVIEW: - naming */
+// TODO: - CTA Marker"#;
+        let expected = r#"// TODO: - Primary Marker
+let x = compute() // TODO(other): cache this
+let y = 2 /* REVIEW(other): naming */
+// TODO: - CTA Marker"#;
+        let output = scrub_extra_todo_markers(input, false, primary_marker).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_keeps_every_primary_marker_of_a_task_group() {
+        let input = r#"func login() {
+    // TODO: - #auth make login async
+    // TODO: - stray
+}
+def refresh():
+    # FIXME: - #auth expire old tokens
+    # FIXME: - #auth expire old tokens
+Can you do the tasks marked by "// TODO: - #auth"?"#;
+        let expected = r#"func login() {
+    // TODO: - #auth make login async
+}
+def refresh():
+    # FIXME: - #auth expire old tokens
+Can you do the tasks marked by "// TODO: - #auth"?"#;
+
+        let output = scrub_extra_todo_markers_keeping(
+            input,
+            false,
+            &[
+                "// TODO: - #auth make login async",
+                "# FIXME: - #auth expire old tokens",
+            ],
+            &MarkerGrammar::default(),
+        )
+        .unwrap();
+        assert_eq!(output, expected);
+
+        let err = scrub_extra_todo_markers_keeping(
+            input,
+            false,
+            &["// TODO: - #auth make login async", "// TODO: - #auth gone"],
+            &MarkerGrammar::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("#auth gone"));
+    }
+
+    #[test]
+    fn test_scrubs_custom_token() {
+        let prompt = "// AI: - Primary\n// AI: - Stray\n// TODO: - kept\nclosing \"// AI: - \"";
+        let grammar = MarkerGrammar::C_LIKE.with_token("AI: -");
+        let output =
+            scrub_extra_todo_markers_with_grammar(prompt, false, "// AI: - Primary", &grammar)
+                .unwrap();
+        assert_eq!(
+            output,
+            "// AI: - Primary\n// TODO: - kept\nclosing \"// AI: - \""
+        );
+    }
 }
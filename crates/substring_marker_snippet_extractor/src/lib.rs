@@ -4,8 +4,9 @@
 # substring_marker_snippet_extractor
 
 Marker-filtering and enclosing-block extraction for the prompt-generation
-pipeline. Substring markers (`// v` … `// ^`) delimit the region of a source
-file that should be included in a prompt, and the enclosing-block helpers find
+pipeline. Substring markers (`// v` … `// ^`, or the same in the file's own
+comment syntax, such as `# v` … `# ^`) delimit the region of a source file
+that should be included in a prompt, and the enclosing-block helpers find
 the surrounding function/class/enum for additional context.
 
 The `FileProcessor` trait and its default implementation have moved to the
@@ -17,5 +18,6 @@ pub mod utils;
 
 pub use utils::marker_utils::{
     extract_enclosing_block, extract_enclosing_block_from_content, file_uses_markers,
-    filter_substring_markers, EnclosingBlockScope, FileAnalysis,
+    file_uses_markers_with_grammar, filter_line_ranges, filter_substring_markers,
+    filter_substring_markers_with_grammar, EnclosingBlockScope, FileAnalysis,
 };
@@ -5,9 +5,10 @@
 //! pipeline.
 
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
 
-use lang_support::LanguageSupport;
+use lang_support::{BlockMap, LanguageSupport, LexicalRules};
 
 // ---------------------------------------------------------------------------
 //  Public API – marker filtering
@@ -17,15 +18,65 @@ use lang_support::LanguageSupport;
 /// replaced by `placeholder`.  Multiple blocks are concatenated with a blank
 /// line between them.
 pub fn filter_substring_markers(content: &str, placeholder: &str) -> String {
+    filter_substring_markers_with_grammar(content, placeholder, &MarkerGrammar::default())
+}
+
+/// [`filter_substring_markers`] for substring markers written in `grammar`'s
+/// comment syntax, e.g. `# v` … `# ^`.
+pub fn filter_substring_markers_with_grammar(
+    content: &str,
+    placeholder: &str,
+    grammar: &MarkerGrammar,
+) -> String {
+    filter_regions(
+        content.lines().map(|line| {
+            if grammar.is_substring_open(line) {
+                Region::Open
+            } else if grammar.is_substring_close(line) {
+                Region::Close
+            } else {
+                Region::Line(line)
+            }
+        }),
+        placeholder,
+    )
+}
+
+/// Returns only the lines of `content` inside `ranges` (0-based line
+/// indices, end-exclusive, in any order), laid out exactly as
+/// [`filter_substring_markers`] lays out a file whose regions are marked
+/// with `// v` … `// ^`.
+pub fn filter_line_ranges(content: &str, ranges: &[Range<usize>], placeholder: &str) -> String {
+    let mut inside = false;
+    let regions = content.lines().enumerate().flat_map(|(i, line)| {
+        let was_inside = std::mem::replace(&mut inside, ranges.iter().any(|r| r.contains(&i)));
+        let boundary = match (was_inside, inside) {
+            (false, true) => Some(Region::Open),
+            (true, false) => Some(Region::Close),
+            _ => None,
+        };
+        boundary.into_iter().chain([Region::Line(line)])
+    });
+    filter_regions(regions, placeholder)
+}
+
+/// A line of a file being filtered, with the markers that open and close
+/// the regions to keep.
+enum Region<'a> {
+    Open,
+    Close,
+    Line(&'a str),
+}
+
+fn filter_regions<'a>(regions: impl Iterator<Item = Region<'a>>, placeholder: &str) -> String {
     let mut output = String::new();
     let mut state = "omitted";
     let mut omitted_line_count = 0;
     let mut last_was_closing = false;
 
-    for line in content.lines() {
-        let trimmed = line.trim();
-        match trimmed {
-            "// v" => {
+    for region in regions {
+        match region {
+            Region::Open => {
                 if omitted_line_count > 0 {
                     output.push_str("\n\n");
                     output.push_str(placeholder);
@@ -35,12 +86,12 @@ pub fn filter_substring_markers(content: &str, placeholder: &str) -> String {
                 state = "included";
                 last_was_closing = false;
             }
-            "// ^" => {
+            Region::Close => {
                 state = "omitted";
                 omitted_line_count = 0;
                 last_was_closing = true;
             }
-            _ => match state {
+            Region::Line(line) => match state {
                 "included" => {
                     output.push_str(line);
                     output.push('\n');
@@ -65,17 +116,36 @@ pub fn filter_substring_markers(content: &str, placeholder: &str) -> String {
 
 /// `true` if the file uses both `// v` *and* `// ^`.
 pub fn file_uses_markers(content: &str) -> bool {
-    let has_open = content.lines().any(|line| line.trim() == "// v");
-    let has_close = content.lines().any(|line| line.trim() == "// ^");
+    file_uses_markers_with_grammar(content, &MarkerGrammar::default())
+}
+
+/// [`file_uses_markers`] for substring markers written in `grammar`'s
+/// comment syntax.
+pub fn file_uses_markers_with_grammar(content: &str, grammar: &MarkerGrammar) -> bool {
+    let has_open = content.lines().any(|line| grammar.is_substring_open(line));
+    let has_close = content.lines().any(|line| grammar.is_substring_close(line));
     has_open && has_close
 }
 
-use todo_marker::{is_todo_inside_markers, todo_index};
+use todo_marker::MarkerGrammar;
 
 fn is_candidate_line(line: &str) -> bool {
     lang_support::is_function_candidate_any_lang(line)
 }
 
+/// `true` for a line holding only a decorator, attribute or annotation such
+/// as `@staticmethod`, `@app.route("/")` or `@MainActor`.
+fn is_decorator_line(line: &str) -> bool {
+    let Some(rest) = line.trim().strip_prefix('@') else {
+        return false;
+    };
+    let name_len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(rest.len());
+    let arguments = &rest[name_len..];
+    name_len > 0 && (arguments.is_empty() || arguments.starts_with('(') && arguments.ends_with(')'))
+}
+
 /// Selects which declaration kinds may provide enclosing context.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EnclosingBlockScope {
@@ -97,6 +167,7 @@ pub enum EnclosingBlockScope {
 pub struct FileAnalysis<'a> {
     content: &'a str,
     language: Option<&'static dyn LanguageSupport>,
+    grammar: MarkerGrammar,
     has_markers: bool,
     todo_idx: Option<usize>,
     todo_inside_markers: bool,
@@ -107,26 +178,46 @@ impl<'a> FileAnalysis<'a> {
     ///
     /// Enclosing function detection falls back to all registered languages.
     pub fn new(content: &'a str) -> Self {
-        Self::with_language(content, None)
+        Self::with_language(content, None, MarkerGrammar::default())
     }
 
-    /// Analyse content using the language resolved from `file_path`.
+    /// Analyse content using the language resolved from `file_path` and that
+    /// language's marker grammar.
     pub fn for_path(content: &'a str, file_path: &Path) -> Self {
+        Self::for_path_with_grammar(
+            content,
+            file_path,
+            lang_support::marker_grammar(file_path, None),
+        )
+    }
+
+    /// Analyse content using the language resolved from `file_path`, with
+    /// markers written in `grammar` (e.g. a user-chosen token).
+    pub fn for_path_with_grammar(
+        content: &'a str,
+        file_path: &Path,
+        grammar: MarkerGrammar,
+    ) -> Self {
         let language = file_path
             .extension()
             .and_then(|extension| extension.to_str())
             .and_then(lang_support::for_extension);
-        Self::with_language(content, language)
+        Self::with_language(content, language, grammar)
     }
 
-    fn with_language(content: &'a str, language: Option<&'static dyn LanguageSupport>) -> Self {
-        let has_markers = file_uses_markers(content);
-        let todo_idx = todo_index(content);
+    fn with_language(
+        content: &'a str,
+        language: Option<&'static dyn LanguageSupport>,
+        grammar: MarkerGrammar,
+    ) -> Self {
+        let has_markers = file_uses_markers_with_grammar(content, &grammar);
+        let todo_idx = grammar.todo_index(content);
         let todo_inside_markers =
-            has_markers && todo_idx.is_some_and(|idx| is_todo_inside_markers(content, idx));
+            has_markers && todo_idx.is_some_and(|idx| grammar.is_todo_inside_markers(content, idx));
         Self {
             content,
             language,
+            grammar,
             has_markers,
             todo_idx,
             todo_inside_markers,
@@ -146,9 +237,15 @@ impl<'a> FileAnalysis<'a> {
         self.todo_idx
     }
 
-    /// Filter content through substring markers (delegates to `filter_substring_markers`).
+    /// The marker grammar the analysis was made with.
+    pub fn grammar(&self) -> &MarkerGrammar {
+        &self.grammar
+    }
+
+    /// Filter content through substring markers (delegates to
+    /// `filter_substring_markers_with_grammar`).
     pub fn filtered_content(&self, placeholder: &str) -> String {
-        filter_substring_markers(self.content, placeholder)
+        filter_substring_markers_with_grammar(self.content, placeholder, &self.grammar)
     }
 
     /// Extract the enclosing block around the TODO marker using `scope`.
@@ -167,7 +264,38 @@ impl<'a> FileAnalysis<'a> {
         if self.todo_inside_markers {
             return None;
         }
-        extract_enclosing_block_with_language(self.content, self.language, scope)
+        self.block_around_todo(scope)
+    }
+
+    /// The file focused on its TODO markers: around each, the enclosing
+    /// function, the outline of the blocks around it and the members of its
+    /// type it uses, plus the imports, with the rest collapsed as
+    /// [`filtered_content`] would collapse it.  Every marker is kept, so a
+    /// marker picked over the first one, or the file's other tasks, stay in.
+    ///
+    /// Returns `None` if the file uses substring markers, which already say
+    /// what to show, has no TODO marker, is in an unknown language, or a
+    /// marker sits outside every block.
+    ///
+    /// [`filtered_content`]: Self::filtered_content
+    pub fn focused_content(&self, placeholder: &str) -> Option<String> {
+        if self.has_markers || self.todo_idx.is_none() {
+            return None;
+        }
+        let language = self.language?;
+        let mut lines = Vec::new();
+        for (i, line) in self.content.lines().enumerate() {
+            if self.grammar.find_instruction(line).is_some() {
+                lines.extend(lang_support::focus_lines(language, self.content, i)?);
+            }
+        }
+        Some(filter_line_ranges(self.content, &lines, placeholder))
+    }
+
+    /// The enclosing block around the TODO marker, whether or not the file
+    /// uses substring markers.
+    pub fn block_around_todo(&self, scope: EnclosingBlockScope) -> Option<String> {
+        extract_enclosing_block_with_language(self.content, self.language, &self.grammar, scope)
     }
 }
 
@@ -180,23 +308,25 @@ impl<'a> FileAnalysis<'a> {
 /// Scans lines before the TODO marker for the last candidate line, using the
 /// supplied source extension and candidate scope.
 ///
-/// Returns the brace-delimited block starting from that candidate, or `None`
-/// if no candidate is found.
+/// Returns the block starting from that candidate (brace- or indentation-
+/// delimited, as the language dictates), or `None` if no candidate is found.
 pub fn extract_enclosing_block_from_content(
     content: &str,
     extension: Option<&str>,
     scope: EnclosingBlockScope,
 ) -> Option<String> {
     let language = extension.and_then(lang_support::for_extension);
-    extract_enclosing_block_with_language(content, language, scope)
+    let grammar = language.map_or_else(MarkerGrammar::default, |lang| lang.marker_grammar());
+    extract_enclosing_block_with_language(content, language, &grammar, scope)
 }
 
 fn extract_enclosing_block_with_language(
     content: &str,
     language: Option<&dyn LanguageSupport>,
+    grammar: &MarkerGrammar,
     scope: EnclosingBlockScope,
 ) -> Option<String> {
-    let todo_idx = todo_index(content)?;
+    let todo_idx = grammar.todo_index(content)?;
 
     let lines: Vec<&str> = content.lines().collect();
     let mut candidate_index = None;
@@ -217,30 +347,31 @@ fn extract_enclosing_block_with_language(
         }
     }
 
-    let start_index = candidate_index?;
-    let mut brace_count = 0;
-    let mut found_open = false;
-    let mut extracted_lines = Vec::new();
-
-    for line in &lines[start_index..] {
-        if !found_open {
-            if line.contains('{') {
-                found_open = true;
-                brace_count += line.matches('{').count();
-                brace_count = brace_count.saturating_sub(line.matches('}').count());
-            }
-            extracted_lines.push(*line);
-        } else {
-            extracted_lines.push(*line);
-            brace_count += line.matches('{').count();
-            brace_count = brace_count.saturating_sub(line.matches('}').count());
-            if brace_count == 0 {
-                break;
-            }
-        }
-    }
-
-    Some(extracted_lines.join("\n"))
+    // Attributes, annotations and decorators on lines of their own belong
+    // to the declaration below them.
+    let mut start_index = candidate_index?;
+    while start_index > 0 && is_decorator_line(lines[start_index - 1]) {
+        start_index -= 1;
+    }
+    let start_offset: usize = content
+        .split_inclusive('\n')
+        .take(start_index)
+        .map(str::len)
+        .sum();
+
+    // The block runs to the end of the first structural block opened at or
+    // after the candidate line (its matching `}`, or the last line of an
+    // indented body), or to the end of the content if it is never closed.
+    let blocks = language.map_or_else(
+        || BlockMap::new(content, &LexicalRules::C_LIKE),
+        |language| language.block_map(content),
+    );
+    let end_index = match blocks.first_from(start_offset) {
+        Some((_, Some(close))) => content[..close].matches('\n').count(),
+        _ => lines.len() - 1,
+    };
+
+    Some(lines[start_index..=end_index].join("\n"))
 }
 
 /// File-path-based wrapper using function-only matching for the file's language.
@@ -271,6 +402,21 @@ Line after";
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_filter_line_ranges_matches_marker_output() {
+        let input = "import A\nLine before\ncontent line 1\ncontent line 2\nLine after";
+        let marked = "// v\nimport A\n// ^\nLine before\n// v\ncontent line 1\ncontent line 2\n// ^\nLine after";
+        assert_eq!(
+            filter_line_ranges(input, &[2..4, 0..1], "// ..."),
+            filter_substring_markers(marked, "// ...")
+        );
+        let everything = 0..input.lines().count();
+        assert_eq!(
+            filter_line_ranges(input, &[everything], "// ..."),
+            format!("{}\n", input)
+        );
+    }
+
     #[test]
     fn test_file_uses_markers_true() {
         let content = "Some text\n// v\nmarker content\n// ^\nMore text";
@@ -673,8 +819,10 @@ mod candidate_line_characterization_tests {
     }
 
     #[test]
-    fn test_arrow_function_not_candidate() {
-        assert!(!is_candidate_line("const x = () => {"));
+    fn test_assigned_arrow_function_is_candidate() {
+        // TypeScript declares functions this way, so any language does.
+        assert!(is_candidate_line("const x = () => {"));
+        assert!(!is_candidate_line("items.forEach(() => {"));
     }
 
     #[test]
@@ -856,6 +1004,80 @@ class Container {\n\
         assert!(without.unwrap().contains("func innerMethod()"));
         assert!(with.unwrap().contains("func innerMethod()"));
     }
+
+    /// Braces inside literals and comments do not end the block early.
+    #[test]
+    fn char_braces_in_literals_do_not_truncate_block() {
+        let content = "\
+func render() {\n\
+    let open = \"{\"\n\
+    let close = \"}\" // }\n\
+    // TODO: - Render\n\
+    finish()\n\
+}\n\
+func after() {}";
+        let block = extract_enclosing_block_from_content(
+            content,
+            Some("swift"),
+            EnclosingBlockScope::Functions,
+        )
+        .expect("block");
+        assert!(block.ends_with("finish()\n}"), "{block}");
+    }
+
+    #[test]
+    fn char_javascript_regex_literal_does_not_truncate_block() {
+        let content = "\
+function parse(s) {\n\
+    const re = /^}+$/;\n\
+    // TODO: - Parse\n\
+    return re.test(s);\n\
+}\n\
+function after() {}";
+        let block = extract_enclosing_block_from_content(
+            content,
+            Some("js"),
+            EnclosingBlockScope::Functions,
+        )
+        .expect("block");
+        assert!(block.ends_with("return re.test(s);\n}"), "{block}");
+    }
+
+    /// Python blocks end at the first dedent and keep their decorators.
+    #[test]
+    fn char_python_block_follows_indentation() {
+        let content = r#"class Feed:
+    @cached(ttl=60)
+    def items(self):
+        query = """
+SELECT *
+"""
+        # TODO: - Page the query
+        return run(query)
+
+    def other(self):
+        pass
+"#;
+        let block = extract_enclosing_block_from_content(
+            content,
+            Some("py"),
+            EnclosingBlockScope::Functions,
+        )
+        .expect("block");
+        assert!(
+            block.starts_with("    @cached(ttl=60)\n    def items"),
+            "{block}"
+        );
+        assert!(block.ends_with("return run(query)"), "{block}");
+    }
+
+    #[test]
+    fn decorator_lines() {
+        assert!(is_decorator_line("    @staticmethod"));
+        assert!(is_decorator_line("@app.route(\"/users\")"));
+        assert!(!is_decorator_line("@implementation Feed"));
+        assert!(!is_decorator_line("@property (nonatomic) int count;"));
+    }
 }
 
 #[cfg(test)]
@@ -923,6 +1145,16 @@ mod file_analysis_tests {
 
         assert!(block.is_some());
         assert!(block.unwrap().contains("function doWork()"));
+
+        for header in ["fun doWork(): Int {", "public int doWork() {"] {
+            let content = format!("// v\nlet x = 1\n// ^\n{header}\n    // TODO: - Fix\n}}\n");
+            let analysis = FileAnalysis::new(&content);
+            let block = analysis.enclosing_block(EnclosingBlockScope::Functions);
+            assert!(
+                block.is_some_and(|block| block.contains(header)),
+                "{header}"
+            );
+        }
     }
 
     #[test]
@@ -955,6 +1187,48 @@ mod file_analysis_tests {
         assert!(!filtered.contains("postamble"));
     }
 
+    #[test]
+    fn focused_content_collapses_what_the_todo_does_not_touch() {
+        let content = concat!(
+            "import Foundation\n",
+            "struct Feed {\n",
+            "    var page = 0\n",
+            "    var title = \"\"\n",
+            "    mutating func next() {\n",
+            "        // TODO: - stop at the last page\n",
+            "        page += 1\n",
+            "    }\n",
+            "}\n",
+        );
+        let analysis = FileAnalysis::for_path(content, Path::new("Feed.swift"));
+        assert_eq!(
+            analysis.focused_content("// ...").unwrap(),
+            concat!(
+                "import Foundation\n",
+                "struct Feed {\n",
+                "    var page = 0\n",
+                "\n\n// ...\n\n",
+                "    mutating func next() {\n",
+                "        // TODO: - stop at the last page\n",
+                "        page += 1\n",
+                "    }\n",
+                "}\n",
+            )
+        );
+
+        // A second marker outside the function is kept too.
+        let second = content.replace("= \"\"", "= \"\" // FIXME: - localize");
+        let analysis = FileAnalysis::for_path(&second, Path::new("Feed.swift"));
+        assert!(analysis
+            .focused_content("// ...")
+            .unwrap()
+            .contains("    var title = \"\" // FIXME: - localize\n"));
+
+        let marked = format!("// v\n{}// ^\n", content);
+        let analysis = FileAnalysis::for_path(&marked, Path::new("Feed.swift"));
+        assert!(analysis.focused_content("// ...").is_none());
+    }
+
     #[test]
     fn todo_idx_returns_none_without_todo() {
         let content = "// v\nlet x = 1\n// ^\n";
@@ -0,0 +1,392 @@
+// crates/todo_marker/src/grammar.rs
+
+//! The comment syntax and token that make up a TODO marker and the
+//! substring markers, per language.
+
+use crate::MarkerVerb;
+use std::borrow::Cow;
+
+/// One way of writing a comment, e.g. `//` or `/* … */`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentSyntax {
+    /// Opening delimiter, e.g. `//`, `#` or `<!--`.
+    pub open: &'static str,
+    /// Closing delimiter of block comments, e.g. `*/` or `-->`.
+    pub close: Option<&'static str>,
+}
+
+/// How TODO and substring markers are written in a file: a marker is a
+/// comment opener, a space and the token, e.g. `// TODO: -` or
+/// `# TODO: -`.  Every [`MarkerVerb`] has its own token (`// TEST: -`); a
+/// user-chosen token replaces the `TODO` one.  Substring markers are the lone
+/// `v` / `^` comments (`// v`, `/* ^ */`).
+///
+/// The first comment syntax is the primary one, used when the tool writes
+/// markers itself (the closing instruction, the `// ...` placeholder).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerGrammar {
+    comments: &'static [CommentSyntax],
+    token: Cow<'static, str>,
+}
+
+/// The token used unless the user chooses another.
+pub const DEFAULT_TOKEN: &str = MarkerVerb::Todo.token();
+
+/// Every comment syntax a supported language uses; a prompt mixes files of
+/// several languages, so stray markers are looked for in all of them.
+const KNOWN_COMMENTS: &[CommentSyntax] = &[
+    CommentSyntax {
+        open: "//",
+        close: None,
+    },
+    CommentSyntax {
+        open: "/*",
+        close: Some("*/"),
+    },
+    CommentSyntax {
+        open: "#",
+        close: None,
+    },
+    CommentSyntax {
+        open: "<!--",
+        close: Some("-->"),
+    },
+];
+
+impl MarkerGrammar {
+    /// `//` and `/* */` comments.
+    pub const C_LIKE: MarkerGrammar = MarkerGrammar::new(
+        &[
+            CommentSyntax {
+                open: "//",
+                close: None,
+            },
+            CommentSyntax {
+                open: "/*",
+                close: Some("*/"),
+            },
+        ],
+        DEFAULT_TOKEN,
+    );
+
+    /// `#` comments (Python, shell, Ruby …).
+    pub const HASH: MarkerGrammar = MarkerGrammar::new(
+        &[CommentSyntax {
+            open: "#",
+            close: None,
+        }],
+        DEFAULT_TOKEN,
+    );
+
+    /// `<!-- -->` comments (HTML, XML).
+    pub const MARKUP: MarkerGrammar = MarkerGrammar::new(
+        &[CommentSyntax {
+            open: "<!--",
+            close: Some("-->"),
+        }],
+        DEFAULT_TOKEN,
+    );
+
+    pub const fn new(comments: &'static [CommentSyntax], token: &'static str) -> Self {
+        Self {
+            comments,
+            token: Cow::Borrowed(token),
+        }
+    }
+
+    /// The same comment syntax with a different token for the `TODO` verb,
+    /// e.g. `AI: -`.
+    pub fn with_token(self, token: impl Into<String>) -> Self {
+        Self {
+            token: Cow::Owned(token.into()),
+            ..self
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn comments(&self) -> &'static [CommentSyntax] {
+        self.comments
+    }
+
+    fn primary(&self) -> CommentSyntax {
+        self.comments[0]
+    }
+
+    /// The token of `verb`: the grammar's own token for `TODO`, the verb's
+    /// default token otherwise.
+    pub fn token_for(&self, verb: MarkerVerb) -> &str {
+        match verb {
+            MarkerVerb::Todo => &self.token,
+            verb => verb.token(),
+        }
+    }
+
+    fn tokens(&self) -> impl Iterator<Item = (MarkerVerb, &str)> {
+        MarkerVerb::ALL
+            .into_iter()
+            .map(move |verb| (verb, self.token_for(verb)))
+    }
+
+    /// The marker in the primary comment syntax, without a trailing space,
+    /// e.g. `// TODO: -`.
+    pub fn marker(&self) -> String {
+        self.marker_for(MarkerVerb::Todo)
+    }
+
+    /// The marker of `verb` in the primary comment syntax, e.g. `# TEST: -`.
+    pub fn marker_for(&self, verb: MarkerVerb) -> String {
+        format!("{} {}", self.primary().open, self.token_for(verb))
+    }
+
+    /// `true` if `line` mentions a marker of any verb in any comment syntax,
+    /// e.g. a stray `// TODO: -` left in another file.
+    pub fn mentions_marker(&self, line: &str) -> bool {
+        self.mentions_marker_in(self.comments, line)
+    }
+
+    /// Like [`MarkerGrammar::mentions_marker`], but in the comment syntax of
+    /// any supported language, for prompts that mix languages.
+    pub fn mentions_marker_in_any_syntax(&self, line: &str) -> bool {
+        self.mentions_marker_in(KNOWN_COMMENTS, line)
+    }
+
+    /// Number of markers in `line`, of any verb and in any supported
+    /// language's comment syntax.
+    pub fn count_markers_in_any_syntax(&self, line: &str) -> usize {
+        self.any_syntax_markers()
+            .map(|(marker, _)| line.matches(marker.as_str()).count())
+            .sum()
+    }
+
+    /// `true` if the trimmed `line` is nothing but a marker comment.
+    pub fn is_marker_comment_line(&self, line: &str) -> bool {
+        let line = line.trim_start();
+        self.any_syntax_markers()
+            .any(|(marker, _)| line.starts_with(marker.as_str()))
+    }
+
+    /// Rewrites every marker in `line` to an inert form that no longer reads
+    /// as an instruction, keeping the rest of the line: `x() // TODO: - cache`
+    /// becomes `x() // TODO(other): cache`.
+    pub fn neutralize_markers(&self, line: &str) -> String {
+        self.any_syntax_markers()
+            .fold(line.to_string(), |line, (marker, inert)| {
+                line.replace(&marker, &inert)
+            })
+    }
+
+    /// Every marker in every known comment syntax, paired with its inert form.
+    fn any_syntax_markers(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        KNOWN_COMMENTS.iter().flat_map(move |comment| {
+            self.tokens().map(move |(_, token)| {
+                let keyword = token.trim_end_matches([' ', '-', ':']);
+                (
+                    format!("{} {}", comment.open, token),
+                    format!("{} {}(other):", comment.open, keyword),
+                )
+            })
+        })
+    }
+
+    fn mentions_marker_in(&self, comments: &[CommentSyntax], line: &str) -> bool {
+        comments.iter().any(|comment| {
+            self.tokens()
+                .any(|(_, token)| line.contains(&format!("{} {}", comment.open, token)))
+        })
+    }
+
+    /// Byte offset and verb of the first instruction in `text`: a marker
+    /// followed by a space, in any comment syntax.
+    pub fn find_marker(&self, text: &str) -> Option<(usize, MarkerVerb)> {
+        self.comments
+            .iter()
+            .flat_map(|comment| {
+                self.tokens().filter_map(move |(verb, token)| {
+                    text.find(&format!("{} {} ", comment.open, token))
+                        .map(|offset| (offset, verb))
+                })
+            })
+            .min_by_key(|(offset, _)| *offset)
+    }
+
+    /// Byte offset of the first instruction in `text`, whatever its verb.
+    pub fn find_instruction(&self, text: &str) -> Option<usize> {
+        self.find_marker(text).map(|(offset, _)| offset)
+    }
+
+    /// The verb of the first instruction in `text`, if any.
+    pub fn verb_of(&self, text: &str) -> Option<MarkerVerb> {
+        self.find_marker(text).map(|(_, verb)| verb)
+    }
+
+    /// Zero‑based index of the first line holding a TODO instruction.
+    pub fn todo_index(&self, content: &str) -> Option<usize> {
+        content
+            .lines()
+            .position(|line| self.find_instruction(line).is_some())
+    }
+
+    /// `true` if the trimmed `line` is an opening substring marker (`// v`).
+    pub fn is_substring_open(&self, line: &str) -> bool {
+        self.is_substring_marker(line, 'v')
+    }
+
+    /// `true` if the trimmed `line` is a closing substring marker (`// ^`).
+    pub fn is_substring_close(&self, line: &str) -> bool {
+        self.is_substring_marker(line, '^')
+    }
+
+    fn is_substring_marker(&self, line: &str, symbol: char) -> bool {
+        let line = line.trim();
+        self.comments.iter().any(|comment| {
+            let Some(rest) = line.strip_prefix(comment.open) else {
+                return false;
+            };
+            let rest = match comment.close {
+                Some(close) => match rest.strip_suffix(close) {
+                    Some(rest) => rest,
+                    None => return false,
+                },
+                None => rest,
+            };
+            rest.trim() == symbol.to_string()
+        })
+    }
+
+    /// `text` as a comment in the primary syntax, e.g. `# note`.
+    pub fn comment(&self, text: &str) -> String {
+        match self.primary().close {
+            Some(close) => format!("{} {} {}", self.primary().open, text, close),
+            None => format!("{} {}", self.primary().open, text),
+        }
+    }
+
+    /// Placeholder for code omitted between substring markers, e.g.
+    /// `// ...` or `<!-- ... -->`.
+    pub fn placeholder(&self) -> String {
+        self.comment("...")
+    }
+
+    /// Returns `true` if the TODO marker at `todo_idx` lives inside an
+    /// active substring‑marker block.
+    pub fn is_todo_inside_markers(&self, content: &str, todo_idx: usize) -> bool {
+        let mut marker_depth = 0;
+        for (i, line) in content.lines().enumerate() {
+            if self.is_substring_open(line) {
+                marker_depth += 1;
+            } else if self.is_substring_close(line) && marker_depth > 0 {
+                marker_depth -= 1;
+            }
+            if i == todo_idx {
+                break;
+            }
+        }
+        marker_depth > 0
+    }
+}
+
+impl Default for MarkerGrammar {
+    fn default() -> Self {
+        Self::C_LIKE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_follow_the_comment_syntax() {
+        assert_eq!(MarkerGrammar::C_LIKE.marker(), "// TODO: -");
+        assert_eq!(MarkerGrammar::HASH.marker(), "# TODO: -");
+        assert_eq!(
+            MarkerGrammar::C_LIKE.with_token("AI: -").marker(),
+            "// AI: -"
+        );
+
+        let grammar = MarkerGrammar::C_LIKE;
+        assert_eq!(grammar.find_instruction("x /* TODO: - y */"), Some(2));
+        assert_eq!(grammar.find_instruction("// TODO: -"), None);
+        assert!(grammar.mentions_marker("// TODO: -"));
+        assert!(!MarkerGrammar::HASH.mentions_marker("// TODO: - x"));
+        assert!(MarkerGrammar::HASH.mentions_marker_in_any_syntax("// TODO: - x"));
+        assert!(!MarkerGrammar::HASH.mentions_marker_in_any_syntax("// TODO: x"));
+    }
+
+    #[test]
+    fn every_verb_is_a_marker() {
+        let grammar = MarkerGrammar::C_LIKE;
+        assert_eq!(
+            grammar.find_marker("x // TEST: - cover errors"),
+            Some((2, MarkerVerb::Test))
+        );
+        assert_eq!(
+            MarkerGrammar::HASH.verb_of("# EXPLAIN: - why?"),
+            Some(MarkerVerb::Explain)
+        );
+        assert_eq!(grammar.marker_for(MarkerVerb::Review), "// REVIEW: -");
+        assert!(grammar.mentions_marker("// FIXME: - crash"));
+        assert!(!grammar.mentions_marker("// FIXME: crash"));
+
+        let custom = grammar.with_token("AI: -");
+        assert_eq!(custom.verb_of("// AI: - do it"), Some(MarkerVerb::Todo));
+        assert_eq!(custom.verb_of("// TODO: - not this"), None);
+        assert_eq!(
+            custom.verb_of("// TEST: - still a verb"),
+            Some(MarkerVerb::Test)
+        );
+    }
+
+    #[test]
+    fn stray_markers_can_be_neutralized() {
+        let grammar = MarkerGrammar::C_LIKE;
+        assert_eq!(
+            grammar.neutralize_markers("let x = compute() // TODO: - cache this"),
+            "let x = compute() // TODO(other): cache this"
+        );
+        assert_eq!(
+            grammar.neutralize_markers("x = 1  # FIXME: - off by one"),
+            "x = 1  # FIXME(other): off by one"
+        );
+        assert_eq!(
+            MarkerGrammar::C_LIKE
+                .with_token("AI: -")
+                .neutralize_markers("f() /* AI: - inline */"),
+            "f() /* AI(other): inline */"
+        );
+        assert!(grammar.is_marker_comment_line("    // TEST: - cover it"));
+        assert!(!grammar.is_marker_comment_line("f() // TEST: - cover it"));
+        assert_eq!(
+            grammar.count_markers_in_any_syntax("// TODO: - a /* REVIEW: - b */"),
+            2
+        );
+        assert_eq!(grammar.count_markers_in_any_syntax("// TODO(other): a"), 0);
+    }
+
+    #[test]
+    fn substring_markers_follow_the_comment_syntax() {
+        let c_like = MarkerGrammar::C_LIKE;
+        assert!(c_like.is_substring_open("  // v"));
+        assert!(c_like.is_substring_open("/* v */"));
+        assert!(c_like.is_substring_close("// ^"));
+        assert!(!c_like.is_substring_open("// very"));
+
+        let markup = MarkerGrammar::MARKUP;
+        assert!(markup.is_substring_open("<!-- v -->"));
+        assert!(!markup.is_substring_open("<!-- v"));
+        assert_eq!(markup.placeholder(), "<!-- ... -->");
+        assert_eq!(MarkerGrammar::HASH.placeholder(), "# ...");
+    }
+
+    #[test]
+    fn todo_position_uses_the_grammar() {
+        let content = "# v\nx = 1\n# TODO: - here\n# ^\n";
+        let grammar = MarkerGrammar::HASH;
+        assert_eq!(grammar.todo_index(content), Some(2));
+        assert!(grammar.is_todo_inside_markers(content, 2));
+        assert_eq!(MarkerGrammar::C_LIKE.todo_index(content), None);
+    }
+}
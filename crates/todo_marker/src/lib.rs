@@ -1,7 +1,15 @@
 // crates/todo_marker/src/lib.rs
 
 //! Shared TODO marker constants and position‑analysis helpers used
-//! throughout the prompt‑generation tool‑chain.
+//! throughout the prompt‑generation tool‑chain.  The constants describe the
+//! default C‑like marker; [`MarkerGrammar`] covers other comment syntaxes,
+//! user-chosen tokens and the [`MarkerVerb`]s other than `TODO`.
+
+mod grammar;
+mod verb;
+
+pub use grammar::{CommentSyntax, MarkerGrammar, DEFAULT_TOKEN};
+pub use verb::MarkerVerb;
 
 /// Exact form **without** the trailing space.
 pub const TODO_MARKER: &str = "// TODO: -";
@@ -10,31 +18,16 @@ pub const TODO_MARKER: &str = "// TODO: -";
 /// parsers look for when scanning file content).
 pub const TODO_MARKER_WS: &str = "// TODO: - ";
 
-/// Zero‑based index of the first line that contains `TODO_MARKER_WS`.
-/// Returns `None` if the marker is not present.
+/// Zero‑based index of the first line that contains a TODO marker in the
+/// default (C‑like) [`MarkerGrammar`].  Returns `None` if there is none.
 pub fn todo_index(content: &str) -> Option<usize> {
-    content
-        .lines()
-        .position(|line| line.contains(TODO_MARKER_WS))
+    MarkerGrammar::default().todo_index(content)
 }
 
 /// Returns `true` if the TODO marker at `todo_idx` lives inside an active
 /// substring‑marker block (`// v` … `// ^`).
 pub fn is_todo_inside_markers(content: &str, todo_idx: usize) -> bool {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut marker_depth = 0;
-    for (i, line) in lines.iter().enumerate() {
-        let trimmed = line.trim();
-        if trimmed == "// v" {
-            marker_depth += 1;
-        } else if trimmed == "// ^" && marker_depth > 0 {
-            marker_depth -= 1;
-        }
-        if i == todo_idx {
-            break;
-        }
-    }
-    marker_depth > 0
+    MarkerGrammar::default().is_todo_inside_markers(content, todo_idx)
 }
 
 #[cfg(test)]
@@ -0,0 +1,60 @@
+// crates/todo_marker/src/verb.rs
+
+//! The verbs a marker can carry.  `// TODO: -` asks for a change; the other
+//! verbs ask for a fix, tests, an explanation or a review instead.
+
+use std::fmt;
+
+/// What the marker asks for, read from its keyword (`TODO`, `TEST`, …).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MarkerVerb {
+    /// `TODO: -` — make the change described.
+    #[default]
+    Todo,
+    /// `FIXME: -` — fix the bug described.
+    Fixme,
+    /// `TEST: -` — write tests for the enclosing function.
+    Test,
+    /// `EXPLAIN: -` — answer a question without changing code.
+    Explain,
+    /// `REVIEW: -` — critique the enclosing block.
+    Review,
+}
+
+impl MarkerVerb {
+    pub const ALL: [MarkerVerb; 5] = [
+        MarkerVerb::Todo,
+        MarkerVerb::Fixme,
+        MarkerVerb::Test,
+        MarkerVerb::Explain,
+        MarkerVerb::Review,
+    ];
+
+    /// The keyword as written in the marker, e.g. `EXPLAIN`.
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            MarkerVerb::Todo => "TODO",
+            MarkerVerb::Fixme => "FIXME",
+            MarkerVerb::Test => "TEST",
+            MarkerVerb::Explain => "EXPLAIN",
+            MarkerVerb::Review => "REVIEW",
+        }
+    }
+
+    /// The default token for the verb, e.g. `TEST: -`.
+    pub const fn token(self) -> &'static str {
+        match self {
+            MarkerVerb::Todo => "TODO: -",
+            MarkerVerb::Fixme => "FIXME: -",
+            MarkerVerb::Test => "TEST: -",
+            MarkerVerb::Explain => "EXPLAIN: -",
+            MarkerVerb::Review => "REVIEW: -",
+        }
+    }
+}
+
+impl fmt::Display for MarkerVerb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.keyword())
+    }
+}
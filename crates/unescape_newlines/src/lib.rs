@@ -1,8 +1,28 @@
 // crates/unescape_newlines/src/lib.rs
 
 /// Converts literal "\n" sequences in the input string to actual newline characters.
+///
+/// An escaped backslash (`\\`) is kept as written, so `\\n` (a backslash
+/// followed by `n`) is not mistaken for a newline escape.  Meant for text the
+/// tool writes itself, never for quoted source code.
 pub fn unescape_newlines(input: &str) -> String {
-    input.replace("\\n", "\n")
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some(next) => {
+                output.push('\\');
+                output.push(next);
+            }
+            None => output.push('\\'),
+        }
+    }
+    output
 }
 
 #[cfg(test)]
@@ -43,4 +63,17 @@ mod tests {
         let expected = "Line1\n\nLine2";
         assert_eq!(unescape_newlines(input), expected);
     }
+
+    #[test]
+    fn test_escaped_backslash_is_not_a_newline() {
+        let input = "C:\\\\new\\nline";
+        let expected = "C:\\\\new\nline";
+        assert_eq!(unescape_newlines(input), expected);
+    }
+
+    #[test]
+    fn test_other_escapes_and_trailing_backslash_are_kept() {
+        let input = "tab\\there\\";
+        assert_eq!(unescape_newlines(input), input);
+    }
 }